@@ -49,6 +49,10 @@ pub struct Trigger {
     #[serde(default = "default_enabled")]
     pub enabled: bool,
 
+    /// Match only prompts (lines ended by IAC GA / IAC EOR)
+    #[serde(default)]
+    pub prompt_only: bool,
+
     /// Cached compiled regex (not serialized)
     #[serde(skip)]
     regex: Option<Regex>,
@@ -79,6 +83,7 @@ impl Trigger {
             pattern,
             action,
             enabled: true,
+            prompt_only: false,
             regex: None,
         };
 
@@ -198,6 +203,21 @@ impl Trigger {
         Ok(commands)
     }
 
+    /// Sound files this trigger's action plays, in order
+    pub fn sounds(&self) -> Vec<String> {
+        fn collect(action: &TriggerAction, sounds: &mut Vec<String>) {
+            match action {
+                TriggerAction::PlaySound(file) => sounds.push(file.clone()),
+                TriggerAction::Sequence(actions) => actions.iter().for_each(|a| collect(a, sounds)),
+                _ => {}
+            }
+        }
+
+        let mut sounds = Vec::new();
+        collect(&self.action, &mut sounds);
+        sounds
+    }
+
     fn execute_action(&self, action: &TriggerAction, commands: &mut Vec<String>) {
         match action {
             TriggerAction::SendCommand(cmd) => {
@@ -207,7 +227,7 @@ impl Trigger {
                 // Display text doesn't generate commands
             }
             TriggerAction::PlaySound(_file) => {
-                // Play sound doesn't generate commands
+                // Sounds are played at Session level, see sounds()
             }
             TriggerAction::ExecuteScript(_script) => {
                 // Script execution handled at Session level via Lua runtime
@@ -249,13 +269,23 @@ impl TriggerManager {
         Ok(())
     }
 
-    /// Find matching triggers for input text
+    /// Find matching triggers for input text (prompt-only triggers are skipped)
     pub fn find_matches(&mut self, text: &str) -> Result<Vec<&Trigger>> {
         debug!("Finding matches for text: {}", text);
+        self.find_matches_where(text, false)
+    }
+
+    /// Find matching prompt-only triggers for a prompt
+    pub fn find_prompt_matches(&mut self, prompt: &str) -> Result<Vec<&Trigger>> {
+        debug!("Finding matches for prompt: {}", prompt);
+        self.find_matches_where(prompt, true)
+    }
+
+    fn find_matches_where(&mut self, text: &str, prompt_only: bool) -> Result<Vec<&Trigger>> {
         let mut matches = Vec::new();
 
         for trigger in &mut self.triggers {
-            if trigger.enabled && trigger.matches(text)? {
+            if trigger.enabled && trigger.prompt_only == prompt_only && trigger.matches(text)? {
                 debug!("Trigger '{}' matched", trigger.name);
                 matches.push(trigger as &Trigger);
             }
@@ -464,6 +494,22 @@ mod tests {
         assert_eq!(commands[1], "attack");
     }
 
+    #[test]
+    fn test_sounds() {
+        let trigger = Trigger::new(
+            "Test",
+            "^Battle!",
+            TriggerAction::Sequence(vec![
+                TriggerAction::PlaySound("battle.wav".to_string()),
+                TriggerAction::SendCommand("attack".to_string()),
+                TriggerAction::Sequence(vec![TriggerAction::PlaySound("horn.wav".to_string())]),
+            ]),
+        ).unwrap();
+
+        assert_eq!(trigger.sounds(), vec!["battle.wav", "horn.wav"]);
+        assert_eq!(trigger.execute().unwrap(), vec!["attack"]);
+    }
+
     #[test]
     fn test_trigger_manager_add() {
         let mut manager = TriggerManager::new();
@@ -508,6 +554,35 @@ mod tests {
         assert_eq!(matches.len(), 0);
     }
 
+    #[test]
+    fn test_trigger_manager_prompt_only() {
+        let mut manager = TriggerManager::new();
+
+        let line = Trigger::new(
+            "Line Trigger",
+            "HP",
+            TriggerAction::SendCommand("score".to_string()),
+        ).unwrap();
+
+        let mut prompt = Trigger::new(
+            "Prompt Trigger",
+            r"^<(\d+)hp>",
+            TriggerAction::SendCommand("heal".to_string()),
+        ).unwrap();
+        prompt.prompt_only = true;
+
+        manager.add_trigger(line).unwrap();
+        manager.add_trigger(prompt).unwrap();
+
+        let matches = manager.find_matches("<100hp> HP").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name, "Line Trigger");
+
+        let matches = manager.find_prompt_matches("<100hp> ").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name, "Prompt Trigger");
+    }
+
     #[test]
     fn test_trigger_manager_get_by_id() {
         let mut manager = TriggerManager::new();
@@ -1,50 +1,182 @@
 /// Connection management with event generation
 ///
-/// Wraps TcpClient and publishes events to EventBus for all connection activities.
+/// Wraps TlsClient (plain TCP or TLS, per `World::use_tls`) and publishes
+/// events to EventBus for all connection activities.
 
-use crate::core::{EventBus, MudEvent, World};
+use crate::core::{CommandOrigin, EventBus, MudEvent, SendQueue, World};
 use crate::error::{MushError, Result};
-use crate::network::{TcpClient, MccpHandler};
-use std::sync::Arc;
+use crate::network::{TlsClient, TlsInfo, CertificateTrust, CompressionStats, MccpHandler, MudCodec, TelnetHandler, TelnetEvent, TtypeHandler, GmcpMessage, MsdpCommand, MsdpValue, MsspData, TextEncoding};
+use crate::network::charset::{self, CharsetOutcome, TELOPT_CHARSET};
+use crate::network::environ::{self, TELOPT_NEW_ENVIRON};
+use crate::network::gmcp::TELOPT_GMCP;
+use crate::network::msdp::{self, TELOPT_MSDP};
+use crate::network::mssp::TELOPT_MSSP;
+use crate::network::mxp::{MxpOutput, MxpParser, TELOPT_MXP};
+use crate::network::msp::{MspParser, MspRequest, TELOPT_MSP};
+use crate::network::mccp::{Decompressed, TELOPT_COMPRESS2, TELOPT_COMPRESS3};
+use crate::network::telnet::{self, QState, EOR, GA, NOP, TELOPT_ECHO, TELOPT_EOR, TELOPT_NAWS};
+use crate::network::ttype::{self, TELOPT_TTYPE};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 
+/// Client name reported in GMCP Core.Hello, NEW-ENVIRON and (upper-cased) TTYPE
+const CLIENT_NAME: &str = "MACMush";
+
+/// Text reported in place of commands sent while input is masked
+const MASKED_COMMAND: &str = "********";
+
+/// Window size reported via NAWS until the frontend provides one
+const DEFAULT_WINDOW_WIDTH: u16 = 80;
+const DEFAULT_WINDOW_HEIGHT: u16 = 24;
+
 /// Connection state management with event publishing
 pub struct Connection {
     world: World,
-    client: TcpClient,
+    client: TlsClient,
     event_bus: Arc<EventBus>,
     mccp: MccpHandler,
-    telnet_buffer: Vec<u8>,
+    telnet: TelnetHandler,
+    ttype: TtypeHandler,
+    /// Text encoder/decoder for the negotiated encoding
+    codec: MudCodec,
+    /// MXP state, active once the server negotiates MXP
+    mxp: MxpParser,
+    /// MSP marker state, used when the world enables MSP
+    msp: MspParser,
+    /// Terminal size (columns, rows) reported via NAWS
+    window_size: (u16, u16),
+    /// GMCP messages received since the last `take_gmcp_messages` call
+    gmcp_messages: Vec<GmcpMessage>,
+    /// MSDP variables received since the last `take_msdp_updates` call
+    msdp_updates: Vec<(String, MsdpValue)>,
+    /// MSSP status received since the last `take_mssp` call
+    mssp: Option<MsspData>,
+    /// Offsets into the last received data where IAC GA / IAC EOR ended a prompt
+    prompt_marks: Vec<usize>,
+    /// When the last command (or anti-idle NOP) was sent, for anti-idle
+    last_sent: Instant,
+    /// Outgoing commands waiting for the rate limit
+    send_queue: Arc<Mutex<SendQueue>>,
 }
 
 impl Connection {
+    /// Build an unconnected client for the world's transport settings
+    fn build_client(world: &World) -> TlsClient {
+        let mut client = TlsClient::builder(&world.host, world.port)
+            .use_tls(world.use_tls)
+            .proxy(world.proxy.clone())
+            .websocket(world.transport.websocket_path().map(str::to_string))
+            .timeout(Duration::from_secs(world.timeout_secs));
+        if world.tcp_keepalive.enabled {
+            client = client.keepalive(
+                Duration::from_secs(world.tcp_keepalive.idle_secs),
+                Duration::from_secs(world.tcp_keepalive.interval_secs),
+            );
+        }
+        if let Some(cert) = &world.client_certificate {
+            client = client.client_certificate(&cert.cert_path, &cert.key_path);
+        }
+        client.build()
+    }
+
     /// Create a new connection for a world
     pub fn new(world: World, event_bus: Arc<EventBus>) -> Self {
         debug!("Creating connection for world '{}'", world.name);
 
-        let client = TcpClient::builder(&world.host, world.port)
-            .timeout(Duration::from_secs(world.timeout_secs))
-            .build();
+        let client = Self::build_client(&world);
+
+        // Options we are willing to let the server enable; everything else is refused
+        let mut telnet = TelnetHandler::new();
+        if world.mccp {
+            telnet.accept_remote(TELOPT_COMPRESS2);
+            telnet.accept_remote(TELOPT_COMPRESS3);
+        }
+        telnet.accept_remote(TELOPT_GMCP);
+        telnet.accept_remote(TELOPT_MSDP);
+        telnet.accept_remote(TELOPT_MSSP);
+        telnet.accept_remote(TELOPT_EOR);
+        telnet.accept_remote(TELOPT_ECHO);
+        telnet.accept_local(TELOPT_NAWS);
+        telnet.accept_local(TELOPT_TTYPE);
+        telnet.accept_remote(TELOPT_CHARSET);
+        telnet.accept_local(TELOPT_CHARSET);
+        telnet.accept_local(TELOPT_NEW_ENVIRON);
+        if world.mxp {
+            // Servers offer MXP with either WILL or DO
+            telnet.accept_remote(TELOPT_MXP);
+            telnet.accept_local(TELOPT_MXP);
+        }
+        if world.msp {
+            telnet.accept_remote(TELOPT_MSP);
+        }
+
+        let ttype = TtypeHandler::new(CLIENT_NAME, Self::terminal_type(&world), Self::mtts_flags(&world));
+        let codec = MudCodec::new(world.encoding);
+        let send_queue = Arc::new(Mutex::new(SendQueue::new(world.rate_limit.clone())));
 
         Self {
             world,
             client,
             event_bus,
             mccp: MccpHandler::new(),
-            telnet_buffer: Vec::new(),
+            telnet,
+            ttype,
+            codec,
+            mxp: MxpParser::new(),
+            msp: MspParser::new(),
+            window_size: (DEFAULT_WINDOW_WIDTH, DEFAULT_WINDOW_HEIGHT),
+            gmcp_messages: Vec::new(),
+            msdp_updates: Vec::new(),
+            mssp: None,
+            prompt_marks: Vec::new(),
+            last_sent: Instant::now(),
+            send_queue,
         }
     }
 
     /// Connect to the MUD server
     ///
-    /// Publishes Connected event on success, ConnectionError event on failure.
+    /// Publishes Connected (and TlsNegotiated for TLS worlds) on success,
+    /// TlsError if the TLS handshake fails and ConnectionError otherwise.
     pub async fn connect(&mut self) -> Result<()> {
         info!("Connecting to {} ({}:{})", self.world.name, self.world.host, self.world.port);
 
-        match self.client.connect().await {
+        let result = self.client.connect().await;
+        self.finish_connect(result).await
+    }
+
+    /// Take the client out to connect it without borrowing the connection
+    ///
+    /// Closes the current stream and leaves an unconnected client in its
+    /// place; hand the client back with `install_client` once its connect
+    /// attempt is done. Used for reconnects, so the session need not be
+    /// locked for the whole DNS lookup, dial and handshake.
+    pub async fn take_client(&mut self) -> TlsClient {
+        if let Err(e) = self.client.disconnect().await {
+            debug!("Error closing previous connection: {}", e);
+        }
+        std::mem::replace(&mut self.client, Self::build_client(&self.world))
+    }
+
+    /// Install a client from `take_client` with fresh protocol state
+    ///
+    /// `result` is the outcome of its connect attempt; events are published
+    /// as for `connect`.
+    pub async fn install_client(&mut self, client: TlsClient, result: Result<()>) -> Result<()> {
+        self.client = client;
+        self.reset_state();
+        self.finish_connect(result).await
+    }
+
+    /// Publish the outcome of a connect attempt and start negotiating
+    async fn finish_connect(&mut self, result: Result<()>) -> Result<()> {
+        match result {
             Ok(()) => {
                 info!("Successfully connected to {}", self.world.name);
+                self.last_sent = Instant::now();
 
                 // Publish Connected event
                 self.event_bus
@@ -52,19 +184,50 @@ impl Connection {
                         world_id: self.world.id,
                         host: self.world.host.clone(),
                         port: self.world.port,
+                        address: self.client.server_addr(),
+                    })?;
+
+                if let Some(tls) = self.client.tls_info() {
+                    self.event_bus.publish(MudEvent::TlsNegotiated {
+                        world_id: self.world.id,
+                        protocol_version: tls.protocol_version,
+                        cipher_suite: tls.cipher_suite,
                     })?;
+                }
+                if let Some(CertificateTrust::FirstUse(fingerprint)) = self.client.certificate_trust() {
+                    // Later connects (e.g. reconnects) must present the same certificate
+                    self.client.set_pinned_fingerprint(Some(fingerprint.clone()));
+                    self.event_bus.publish(MudEvent::CertificatePinned {
+                        world_id: self.world.id,
+                        fingerprint,
+                    })?;
+                }
+
+                // Offer the options we initiate ourselves
+                self.telnet.request_local_enable(TELOPT_NAWS);
+                self.flush_telnet().await?;
 
                 Ok(())
             }
             Err(e) => {
                 error!("Connection failed for {}: {}", self.world.name, e);
 
-                // Publish ConnectionError event
-                self.event_bus
-                    .publish(MudEvent::ConnectionError {
+                // Publish TlsError or ConnectionError event
+                let event = match &e {
+                    MushError::TlsError(reason) => MudEvent::TlsError {
+                        world_id: self.world.id,
+                        error: reason.clone(),
+                    },
+                    MushError::CertificateChanged { .. } | MushError::ClientCertificateError { .. } => MudEvent::TlsError {
                         world_id: self.world.id,
                         error: e.to_string(),
-                    })?;
+                    },
+                    _ => MudEvent::ConnectionError {
+                        world_id: self.world.id,
+                        error: e.to_string(),
+                    },
+                };
+                self.event_bus.publish(event)?;
 
                 Err(e)
             }
@@ -90,15 +253,98 @@ impl Connection {
         Ok(())
     }
 
-    /// Send command to MUD server
+    /// Close the socket (if still open) and reset all protocol state for a fresh connect
     ///
-    /// Publishes CommandSent event on success.
-    /// Handles MCCP3 compression if active.
+    /// Keeps the window size, the certificate pin and the send queue (minus
+    /// any commands meant for the old connection). Publishes no events.
+    pub async fn reset(&mut self) {
+        if let Err(e) = self.client.disconnect().await {
+            debug!("Error closing previous connection: {}", e);
+        }
+        self.reset_state();
+    }
+
+    /// Reset protocol state, keeping the client and its stream as they are
+    fn reset_state(&mut self) {
+        let mut fresh = Self::new(self.world.clone(), self.event_bus.clone());
+        std::mem::swap(&mut fresh.client, &mut self.client);
+        fresh.window_size = self.window_size;
+        self.send_queue.lock().unwrap().clear();
+        fresh.send_queue = Arc::clone(&self.send_queue);
+        *self = fresh;
+    }
+
+    /// Send a command typed by the user (see `send_command_as`)
     pub async fn send_command(&mut self, command: impl AsRef<str>) -> Result<()> {
+        self.send_command_as(command, CommandOrigin::User).await
+    }
+
+    /// Send command to MUD server through the send queue
+    ///
+    /// Commands go out in order as the world's rate limit allows; any held
+    /// back (rate limit, pause or flood guard) are sent by later
+    /// `flush_send_queue` calls. While input is masked (password entry) the
+    /// command skips the queue.
+    ///
+    /// # Errors
+    /// - `MushError::NotConnected`: Not connected to the server
+    pub async fn send_command_as(&mut self, command: impl AsRef<str>, origin: CommandOrigin) -> Result<()> {
         let command = command.as_ref();
-        debug!("Sending command: {}", command);
+        if !self.is_connected() {
+            return Err(MushError::NotConnected);
+        }
+        if self.is_input_masked() {
+            return self.write_command(command).await;
+        }
+
+        self.send_queue.lock().unwrap().push(command, origin, Instant::now());
+        self.flush_send_queue().await
+    }
+
+    /// Send the queued commands the rate limit allows now
+    ///
+    /// Publishes SendThrottled when commands are left waiting (once per
+    /// backlog and reason).
+    pub async fn flush_send_queue(&mut self) -> Result<()> {
+        loop {
+            let next = self.send_queue.lock().unwrap().pop(Instant::now());
+            let Some(command) = next else {
+                break;
+            };
+            self.write_command(&command).await?;
+        }
+
+        let backlog = self.send_queue.lock().unwrap().backlog();
+        if let Some((queued, reason)) = backlog {
+            self.event_bus.publish(MudEvent::SendThrottled {
+                world_id: self.world.id,
+                queued,
+                reason,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Get the send queue (shared with the scripting API)
+    pub fn send_queue(&self) -> Arc<Mutex<SendQueue>> {
+        Arc::clone(&self.send_queue)
+    }
+
+    /// Write one command to the server
+    ///
+    /// Publishes CommandSent event on success.
+    /// Escapes IAC bytes, appends the world's line ending, and handles
+    /// MCCP3 compression if active. While the server is echoing (password
+    /// entry) the command text is kept out of logs and events.
+    async fn write_command(&mut self, command: &str) -> Result<()> {
+        let masked = self.is_input_masked();
+        if masked {
+            debug!("Sending command: <hidden>");
+        } else {
+            debug!("Sending command: {}", command);
+        }
 
-        let mut data = format!("{}\n", command).into_bytes();
+        let mut data = telnet::encode_line(&self.codec.encode(command), self.world.line_ending.as_bytes());
 
         // Compress if MCCP3 is active
         if self.mccp.is_sending_compressed() {
@@ -107,47 +353,55 @@ impl Connection {
         }
 
         self.client.send(&data).await?;
+        self.last_sent = Instant::now();
 
         // Publish CommandSent event
         self.event_bus
             .publish(MudEvent::CommandSent {
                 world_id: self.world.id,
-                command: command.to_string(),
+                command: if masked { MASKED_COMMAND.to_string() } else { command.to_string() },
             })?;
 
         Ok(())
     }
 
+    /// Send a telnet NOP (IAC NOP), which servers ignore but count as activity
+    pub async fn send_nop(&mut self) -> Result<()> {
+        debug!("Sending NOP");
+        self.telnet.send_command(NOP);
+        self.flush_telnet().await?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    /// Time since the last command (or NOP) was sent, or since connecting
+    pub fn idle_time(&self) -> Duration {
+        self.last_sent.elapsed()
+    }
+
+    /// Restart the idle clock without sending anything
+    pub fn reset_idle_time(&mut self) {
+        self.last_sent = Instant::now();
+    }
+
     /// Receive data from MUD server
     ///
     /// Returns raw bytes. Session layer publishes DataReceived event.
     /// Handles MCCP decompression and telnet negotiation.
     pub async fn receive(&mut self) -> Result<Vec<u8>> {
+        let data = self.read().await?;
+        self.process_received(data).await
+    }
+
+    /// Read the next chunk of bytes exactly as they came off the wire
+    ///
+    /// Cancel safe: dropping the future before it completes loses no data,
+    /// so callers may race it against a timeout. Pass the bytes to
+    /// `process_received`, which must not be cancelled.
+    pub async fn read(&mut self) -> Result<Vec<u8>> {
         match self.client.receive().await {
-            Ok(mut data) => {
+            Ok(data) => {
                 debug!("Received {} bytes", data.len());
-
-                // Decompress if MCCP2 is active
-                if self.mccp.is_receiving_compressed() {
-                    match self.mccp.decompress(&data) {
-                        Ok(decompressed) => {
-                            if !decompressed.is_empty() {
-                                debug!("Decompressed {} bytes to {} bytes", data.len(), decompressed.len());
-                                data = decompressed;
-                            }
-                        }
-                        Err(e) => {
-                            warn!("MCCP decompression error: {}", e);
-                            // Disable compression on error
-                            let response = self.mccp.disable_mccp2()?;
-                            self.client.send(&response).await?;
-                        }
-                    }
-                }
-
-                // Process telnet IAC sequences
-                self.process_telnet(&mut data).await?;
-
                 Ok(data)
             }
             Err(e) => {
@@ -177,6 +431,188 @@ impl Connection {
         }
     }
 
+    /// Decompress MCCP data and handle telnet negotiation for bytes from `read`
+    ///
+    /// Returns the remaining text bytes. Negotiation replies are written
+    /// before returning.
+    pub async fn process_received(&mut self, data: Vec<u8>) -> Result<Vec<u8>> {
+        self.process_telnet(data).await
+    }
+
+    /// Send a GMCP message
+    ///
+    /// # Errors
+    /// - `MushError::ValidationError`: The server has not enabled GMCP
+    pub async fn send_gmcp(&mut self, message: &GmcpMessage) -> Result<()> {
+        if !self.telnet.is_remote_enabled(TELOPT_GMCP) {
+            return Err(MushError::ValidationError {
+                field: "gmcp".to_string(),
+                reason: "GMCP is not enabled on this connection".to_string(),
+            });
+        }
+
+        debug!("Sending GMCP: {}", message.package);
+        self.telnet.send_subnegotiation(TELOPT_GMCP, &message.encode());
+        self.flush_telnet().await
+    }
+
+    /// Take the GMCP messages received since the last call
+    pub fn take_gmcp_messages(&mut self) -> Vec<GmcpMessage> {
+        std::mem::take(&mut self.gmcp_messages)
+    }
+
+    /// Send an MSDP command (LIST, REPORT, SEND or UNREPORT)
+    ///
+    /// # Errors
+    /// - `MushError::ValidationError`: The server has not enabled MSDP
+    pub async fn send_msdp(&mut self, command: MsdpCommand, values: &[String]) -> Result<()> {
+        if !self.telnet.is_remote_enabled(TELOPT_MSDP) {
+            return Err(MushError::ValidationError {
+                field: "msdp".to_string(),
+                reason: "MSDP is not enabled on this connection".to_string(),
+            });
+        }
+
+        debug!("Sending MSDP {} {:?}", command, values);
+        self.telnet.send_subnegotiation(TELOPT_MSDP, &command.encode(values));
+        self.flush_telnet().await
+    }
+
+    /// Take the MSDP variables received since the last call
+    pub fn take_msdp_updates(&mut self) -> Vec<(String, MsdpValue)> {
+        std::mem::take(&mut self.msdp_updates)
+    }
+
+    /// Take the prompt markers for the data returned by the last `receive`
+    ///
+    /// Each offset is the position in that data where IAC GA or IAC EOR
+    /// ended a prompt.
+    pub fn take_prompt_marks(&mut self) -> Vec<usize> {
+        std::mem::take(&mut self.prompt_marks)
+    }
+
+    /// Take the MSSP status received since the last call
+    pub fn take_mssp(&mut self) -> Option<MsspData> {
+        self.mssp.take()
+    }
+
+    /// Connect to a world, collect its MSSP status and disconnect
+    ///
+    /// The whole probe (connect, negotiate, collect) must complete within
+    /// the world's timeout. Events go to a private bus, not the session's.
+    ///
+    /// # Errors
+    /// - `MushError::ConnectionTimeout`: No MSSP data within the world's timeout
+    /// - `MushError::ProtocolNotSupported`: Server refused MSSP
+    /// - Any connection error
+    pub async fn probe_mssp(world: World) -> Result<MsspData> {
+        let timeout_secs = world.timeout_secs;
+        info!("Probing MSSP for {} ({}:{})", world.name, world.host, world.port);
+
+        let mut conn = Connection::new(world, Arc::new(EventBus::new()));
+        let result = tokio::time::timeout(Duration::from_secs(timeout_secs), conn.collect_mssp()).await;
+
+        if conn.is_connected() {
+            if let Err(e) = conn.disconnect().await {
+                debug!("Error closing MSSP probe connection: {}", e);
+            }
+        }
+
+        result.unwrap_or(Err(MushError::ConnectionTimeout { timeout_secs }))
+    }
+
+    /// Connect, ask for MSSP and read until the status arrives
+    async fn collect_mssp(&mut self) -> Result<MsspData> {
+        self.connect().await?;
+        self.telnet.request_remote_enable(TELOPT_MSSP);
+        self.flush_telnet().await?;
+
+        loop {
+            self.receive().await?;
+
+            if let Some(data) = self.mssp.take() {
+                return Ok(data);
+            }
+            if self.telnet.remote_state(TELOPT_MSSP) == QState::No {
+                return Err(MushError::ProtocolNotSupported("MSSP".to_string()));
+            }
+        }
+    }
+
+    /// Check whether the server is echoing input (IAC WILL ECHO)
+    ///
+    /// Servers do this at password prompts, so input should be masked and
+    /// kept out of history and logs.
+    pub fn is_input_masked(&self) -> bool {
+        self.telnet.is_remote_enabled(TELOPT_ECHO)
+    }
+
+    /// Get the encoding currently used on the wire
+    ///
+    /// Starts as the world's encoding and may change through CHARSET negotiation.
+    pub fn encoding(&self) -> TextEncoding {
+        self.codec.encoding()
+    }
+
+    /// Decode received data, carrying incomplete characters over to the next call
+    pub fn decode(&mut self, data: &[u8]) -> String {
+        self.codec.decode(data)
+    }
+
+    /// Decode a complete chunk of received data (e.g. a prompt)
+    pub fn decode_complete(&self, data: &[u8]) -> String {
+        self.codec.decode_complete(data)
+    }
+
+    /// Strip MSP markers from decoded text (see `MspParser::process`)
+    ///
+    /// Returns the text unchanged unless the world enables MSP.
+    pub fn process_msp(&mut self, text: &str) -> (String, Vec<MspRequest>) {
+        if self.world.msp {
+            self.msp.process(text)
+        } else {
+            (text.to_string(), Vec::new())
+        }
+    }
+
+    /// Apply MXP to decoded text (see `MxpParser::process`)
+    ///
+    /// Returns the text unchanged unless MXP has been negotiated.
+    pub fn process_mxp(&mut self, text: &str) -> MxpOutput {
+        self.mxp.process(text)
+    }
+
+    /// Text with MXP tags removed, for complete pieces of text like prompts
+    pub fn mxp_visible_text(&self, text: &str) -> String {
+        if self.mxp.is_enabled() {
+            self.mxp.visible_text(text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// Check whether MXP has been negotiated
+    pub fn is_mxp_enabled(&self) -> bool {
+        self.mxp.is_enabled()
+    }
+
+    /// Set the pinned SHA-256 fingerprint the server certificate must match
+    ///
+    /// Takes effect on the next `connect`.
+    pub fn set_certificate_pin(&mut self, fingerprint: Option<String>) {
+        self.client.set_pinned_fingerprint(fingerprint);
+    }
+
+    /// Get how the server certificate was trusted (TLS worlds only)
+    pub fn certificate_trust(&self) -> Option<CertificateTrust> {
+        self.client.certificate_trust()
+    }
+
+    /// Get the negotiated TLS protocol version and cipher suite (TLS worlds only)
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        self.client.tls_info()
+    }
+
     /// Check if connected
     pub fn is_connected(&self) -> bool {
         self.client.is_connected()
@@ -187,69 +623,317 @@ impl Connection {
         &self.world
     }
 
-    /// Process telnet IAC sequences and MCCP negotiation
-    async fn process_telnet(&mut self, data: &mut Vec<u8>) -> Result<()> {
-        const IAC: u8 = 255;   // Interpret As Command
-        const WILL: u8 = 251;
-        const DO: u8 = 253;
-        const DONT: u8 = 254;
-        const SB: u8 = 250;    // Subnegotiation Begin
-        const SE: u8 = 240;    // Subnegotiation End
-
-        let mut i = 0;
-        while i < data.len() {
-            if data[i] == IAC && i + 2 < data.len() {
-                let command = data[i + 1];
-                let option = data[i + 2];
-
-                // Handle MCCP2 negotiation (option 86)
-                if command == WILL && option == 86 {
-                    info!("Server offered MCCP2 compression");
-                    let response = self.mccp.handle_mccp2_will()?;
-                    self.client.send(&response).await?;
-
-                    // Remove IAC WILL COMPRESS2 from data
-                    data.drain(i..i+3);
-                    continue;
-                }
+    /// Get the terminal size (columns, rows) reported via NAWS
+    pub fn window_size(&self) -> (u16, u16) {
+        self.window_size
+    }
+
+    /// Set the terminal size (columns, rows)
+    ///
+    /// Sends IAC SB NAWS immediately if NAWS has been negotiated,
+    /// otherwise the size is reported once the server sends DO NAWS.
+    pub async fn set_window_size(&mut self, width: u16, height: u16) -> Result<()> {
+        if self.window_size == (width, height) {
+            return Ok(());
+        }
 
-                // Handle MCCP2 subnegotiation
-                if command == SB && option == 86 {
-                    // Look for IAC SE
-                    if let Some(se_pos) = data[i+3..].windows(2).position(|w| w == [IAC, SE]) {
-                        let se_idx = i + 3 + se_pos;
+        debug!("Window size changed to {}x{}", width, height);
+        self.window_size = (width, height);
 
-                        // Start compression
-                        self.mccp.handle_mccp2_subnegotiation()?;
-                        info!("MCCP2 compression active");
+        if self.is_connected() && self.telnet.is_local_enabled(TELOPT_NAWS) {
+            self.queue_naws();
+            self.flush_telnet().await?;
+        }
 
-                        // Remove IAC SB COMPRESS2 IAC SE from data
-                        data.drain(i..se_idx+2);
-                        continue;
+        Ok(())
+    }
+
+    /// Terminal type reported via TTYPE, based on the world's colour settings
+    fn terminal_type(world: &World) -> &'static str {
+        if world.truecolor {
+            "XTERM-TRUECOLOR"
+        } else if world.colors_256 {
+            "XTERM-256COLOR"
+        } else {
+            "ANSI"
+        }
+    }
+
+    /// MTTS bitvector describing the features enabled for the world
+    ///
+    /// MTTS has no bits for MXP, MSP or MCCP; support for those is signalled
+    /// by negotiating their telnet options (MXP, MSP, COMPRESS2/3) instead.
+    /// Bit 16 is mouse tracking, which we don't do.
+    fn mtts_flags(world: &World) -> u32 {
+        let mut flags = ttype::MTTS_ANSI | ttype::MTTS_MNES;
+        if world.encoding == TextEncoding::Utf8 {
+            flags |= ttype::MTTS_UTF8;
+        }
+        if world.colors_256 {
+            flags |= ttype::MTTS_256_COLORS;
+        }
+        if world.truecolor {
+            flags |= ttype::MTTS_TRUECOLOR;
+        }
+        if world.use_tls {
+            flags |= ttype::MTTS_SSL;
+        }
+        if Self::screen_reader(world) {
+            flags |= ttype::MTTS_SCREEN_READER;
+        }
+        flags
+    }
+
+    /// Whether the user reports a screen reader (NEW-ENVIRON SCREEN_READER=1)
+    fn screen_reader(world: &World) -> bool {
+        world
+            .new_environ
+            .iter()
+            .rev()
+            .find(|o| o.name.eq_ignore_ascii_case("SCREEN_READER"))
+            .is_some_and(|o| o.value.as_deref() == Some("1"))
+    }
+
+    /// Variables reported via NEW-ENVIRON, after the world's overrides
+    ///
+    /// IPADDRESS is only reported when the user sets it.
+    fn environ_variables(&self) -> BTreeMap<String, String> {
+        let defaults = BTreeMap::from([
+            ("CLIENT_NAME".to_string(), CLIENT_NAME.to_string()),
+            ("CLIENT_VERSION".to_string(), env!("CARGO_PKG_VERSION").to_string()),
+            ("CHARSET".to_string(), self.codec.encoding().name().to_string()),
+            ("MTTS".to_string(), Self::mtts_flags(&self.world).to_string()),
+            ("TERMINAL_TYPE".to_string(), Self::terminal_type(&self.world).to_string()),
+            ("SCREEN_READER".to_string(), u8::from(Self::screen_reader(&self.world)).to_string()),
+        ]);
+        environ::apply_overrides(defaults, &self.world.new_environ)
+    }
+
+    /// Switch the wire encoding after CHARSET negotiation
+    fn set_encoding(&mut self, encoding: TextEncoding) {
+        if encoding != self.codec.encoding() {
+            info!("Switching {} to {} encoding", self.world.name, encoding);
+            self.codec.set_encoding(encoding);
+        }
+    }
+
+    /// Queue IAC SB NAWS <width16> <height16> IAC SE
+    fn queue_naws(&mut self) {
+        let (width, height) = self.window_size;
+        let [w1, w2] = width.to_be_bytes();
+        let [h1, h2] = height.to_be_bytes();
+        self.telnet.send_subnegotiation(TELOPT_NAWS, &[w1, w2, h1, h2]);
+    }
+
+    /// Run incoming bytes through MCCP2 and the telnet engine
+    ///
+    /// Compression may start (after IAC SB COMPRESS2 IAC SE) or end (at the
+    /// end of the zlib stream) anywhere in a read, so the read is split at
+    /// those offsets and each part is decompressed or parsed as it arrived.
+    /// Returns the application data with all telnet commands removed.
+    /// Negotiation replies produced along the way are sent to the server.
+    async fn process_telnet(&mut self, data: Vec<u8>) -> Result<Vec<u8>> {
+        let mut text = Vec::with_capacity(data.len());
+        let mut pending = data;
+        self.prompt_marks.clear();
+
+        while !pending.is_empty() {
+            // Telnet data to parse now, and raw bytes left after it
+            let (plain, rest) = if self.mccp.is_receiving_compressed() {
+                match self.mccp.decompress(&pending) {
+                    Ok(Decompressed { data, trailing: None }) => (data, Vec::new()),
+                    Ok(Decompressed { data, trailing: Some(trailing) }) => {
+                        self.event_bus.publish(MudEvent::CompressionEnded {
+                            world_id: self.world.id,
+                        })?;
+                        (data, trailing)
                     }
+                    Err(e) => {
+                        // Nothing after the corruption can be recovered
+                        warn!("MCCP decompression error: {}", e);
+                        self.telnet.request_remote_disable(TELOPT_COMPRESS2);
+                        self.event_bus.publish(MudEvent::CompressionError {
+                            world_id: self.world.id,
+                            error: e.to_string(),
+                        })?;
+                        break;
+                    }
+                }
+            } else {
+                (std::mem::take(&mut pending), Vec::new())
+            };
+
+            // Stop after IAC SB COMPRESS2 IAC SE: the bytes after it are compressed
+            let (events, consumed) = self.telnet.receive_until_subnegotiation(&plain, TELOPT_COMPRESS2);
+            for event in events {
+                match event {
+                    TelnetEvent::Data(bytes) => text.extend_from_slice(&bytes),
+                    TelnetEvent::Command(GA) | TelnetEvent::Command(EOR) => self.prompt_marks.push(text.len()),
+                    other => self.handle_telnet_event(other).await?,
                 }
+            }
+
+            pending = plain[consumed..].to_vec();
+            pending.extend_from_slice(&rest);
+        }
 
-                // Handle MCCP3 negotiation (option 87)
-                if command == WILL && option == 87 {
-                    info!("Server offered MCCP3 compression");
-                    let response = self.mccp.handle_mccp3_will()?;
-                    self.client.send(&response).await?;
-                    self.mccp.start_mccp3()?;
+        self.flush_telnet().await?;
 
-                    // Remove IAC WILL COMPRESS3 from data
-                    data.drain(i..i+3);
-                    continue;
+        Ok(text)
+    }
+
+    /// Dispatch a negotiation or subnegotiation event to its protocol handler
+    async fn handle_telnet_event(&mut self, event: TelnetEvent) -> Result<()> {
+        match event {
+            // MCCP2 (option 86): compression starts after IAC SB COMPRESS2 IAC SE
+            TelnetEvent::RemoteEnabled(TELOPT_COMPRESS2) => {
+                self.mccp.handle_mccp2_will()?;
+            }
+            TelnetEvent::Subnegotiation { option: TELOPT_COMPRESS2, .. } => {
+                if self.telnet.is_remote_enabled(TELOPT_COMPRESS2) {
+                    self.mccp.handle_mccp2_subnegotiation()?;
+                    info!("MCCP2 compression active");
+                } else {
+                    warn!("Ignoring COMPRESS2 subnegotiation without negotiation");
                 }
             }
+            TelnetEvent::RemoteDisabled(TELOPT_COMPRESS2) => {
+                self.mccp.disable_mccp2()?;
+            }
 
-            i += 1;
+            // MCCP3 (option 87): we start compressing after IAC SB COMPRESS3 IAC SE
+            TelnetEvent::RemoteEnabled(TELOPT_COMPRESS3) => {
+                self.mccp.handle_mccp3_will()?;
+                self.telnet.send_subnegotiation(TELOPT_COMPRESS3, &[]);
+                self.flush_telnet().await?;
+                self.mccp.start_mccp3()?;
+            }
+            TelnetEvent::RemoteDisabled(TELOPT_COMPRESS3) => {
+                self.mccp.disable_mccp3()?;
+            }
+
+            // NAWS (option 31): report our size as soon as it is agreed
+            TelnetEvent::LocalEnabled(TELOPT_NAWS) => {
+                self.queue_naws();
+            }
+
+            // TTYPE (option 24): cycle client name, terminal type, MTTS
+            TelnetEvent::LocalEnabled(TELOPT_TTYPE) | TelnetEvent::LocalDisabled(TELOPT_TTYPE) => {
+                self.ttype.reset();
+            }
+            TelnetEvent::Subnegotiation { option: TELOPT_TTYPE, data } => {
+                if let Some(reply) = self.ttype.handle_subnegotiation(&data) {
+                    self.telnet.send_subnegotiation(TELOPT_TTYPE, &reply);
+                }
+            }
+
+            // GMCP (option 201): identify ourselves, then collect incoming packages
+            TelnetEvent::RemoteEnabled(TELOPT_GMCP) => {
+                info!("GMCP enabled");
+                let hello = GmcpMessage::core_hello(CLIENT_NAME, env!("CARGO_PKG_VERSION"));
+                let supports = GmcpMessage::core_supports_set(&self.world.gmcp_modules);
+                self.telnet.send_subnegotiation(TELOPT_GMCP, &hello.encode());
+                self.telnet.send_subnegotiation(TELOPT_GMCP, &supports.encode());
+            }
+            TelnetEvent::Subnegotiation { option: TELOPT_GMCP, data } => {
+                match GmcpMessage::parse(&data) {
+                    Ok(message) => self.gmcp_messages.push(message),
+                    Err(e) => warn!("Ignoring malformed GMCP message: {}", e),
+                }
+            }
+
+            // MSDP (option 69): collect variables reported by the server
+            TelnetEvent::Subnegotiation { option: TELOPT_MSDP, data } => {
+                match msdp::parse(&data) {
+                    Ok(vars) => self.msdp_updates.extend(vars),
+                    Err(e) => warn!("Ignoring malformed MSDP message: {}", e),
+                }
+            }
+
+            // MSSP (option 70): server status as VAR/VAL pairs
+            TelnetEvent::Subnegotiation { option: TELOPT_MSSP, data } => {
+                match MsspData::parse(&data) {
+                    Ok(status) => self.mssp = Some(status),
+                    Err(e) => warn!("Ignoring malformed MSSP message: {}", e),
+                }
+            }
+
+            // NEW-ENVIRON (option 39): answer MNES variable requests
+            TelnetEvent::Subnegotiation { option: TELOPT_NEW_ENVIRON, data } => {
+                if let Some(reply) = environ::handle_subnegotiation(&data, &self.environ_variables()) {
+                    self.telnet.send_subnegotiation(TELOPT_NEW_ENVIRON, &reply);
+                }
+            }
+
+            // ECHO (option 1): the server echoes input, i.e. a password prompt
+            TelnetEvent::RemoteEnabled(TELOPT_ECHO) | TelnetEvent::RemoteDisabled(TELOPT_ECHO) => {
+                let masked = self.telnet.is_remote_enabled(TELOPT_ECHO);
+                debug!("Server echo {}", if masked { "on" } else { "off" });
+                self.event_bus.publish(MudEvent::InputMaskChanged {
+                    world_id: self.world.id,
+                    masked,
+                })?;
+            }
+
+            // CHARSET (option 42): ask for the world encoding (or UTF-8), answer server requests
+            TelnetEvent::RemoteEnabled(TELOPT_CHARSET) => {
+                self.telnet.send_subnegotiation(TELOPT_CHARSET, &charset::request(self.world.encoding));
+            }
+            TelnetEvent::Subnegotiation { option: TELOPT_CHARSET, data } => {
+                match charset::handle_subnegotiation(&data, self.world.encoding) {
+                    Some(CharsetOutcome::Respond { reply, accepted }) => {
+                        self.telnet.send_subnegotiation(TELOPT_CHARSET, &reply);
+                        if let Some(encoding) = accepted {
+                            self.set_encoding(encoding);
+                        }
+                    }
+                    Some(CharsetOutcome::Accepted(encoding)) => self.set_encoding(encoding),
+                    Some(CharsetOutcome::Rejected) => {
+                        debug!("Server rejected charset request, keeping {}", self.codec.encoding());
+                    }
+                    None => {}
+                }
+            }
+
+            // MXP (option 91): parse tags in received text while either side has it on
+            TelnetEvent::RemoteEnabled(TELOPT_MXP) | TelnetEvent::LocalEnabled(TELOPT_MXP) => {
+                if !self.mxp.is_enabled() {
+                    self.mxp.enable();
+                }
+            }
+            TelnetEvent::RemoteDisabled(TELOPT_MXP) | TelnetEvent::LocalDisabled(TELOPT_MXP) => {
+                if !self.telnet.is_remote_enabled(TELOPT_MXP) && !self.telnet.is_local_enabled(TELOPT_MXP) {
+                    self.mxp.disable();
+                }
+            }
+
+            other => {
+                debug!("Unhandled telnet event: {:?}", other);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send any pending telnet negotiation bytes to the server
+    async fn flush_telnet(&mut self) -> Result<()> {
+        if !self.telnet.has_output() {
+            return Ok(());
+        }
+
+        let mut output = self.telnet.take_output();
+
+        // Everything after IAC SB COMPRESS3 IAC SE goes through MCCP3
+        if self.mccp.is_sending_compressed() {
+            output = self.mccp.compress(&output)?;
         }
 
+        self.client.send(&output).await?;
         Ok(())
     }
 
     /// Get MCCP compression statistics
-    pub fn get_compression_stats(&self) -> crate::network::CompressionStats {
+    pub fn get_compression_stats(&self) -> CompressionStats {
         self.mccp.get_stats()
     }
 }
@@ -257,6 +941,8 @@ impl Connection {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::network::EnvironOverride;
+    use std::net::SocketAddr;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::TcpListener;
 
@@ -306,9 +992,10 @@ mod tests {
             "Should publish Connected event"
         );
 
-        if let MudEvent::Connected { host, port, .. } = event {
+        if let MudEvent::Connected { host, port: connected_port, address, .. } = event {
             assert_eq!(host, "127.0.0.1");
-            assert_eq!(port, port);
+            assert_eq!(connected_port, port);
+            assert_eq!(address, Some(SocketAddr::from(([127, 0, 0, 1], port))));
         }
     }
 
@@ -377,6 +1064,35 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_send_command_uses_world_line_ending() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // Skip the IAC WILL NAWS offer sent on connect
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let mut buf = vec![0u8; 8];
+                let _ = socket.read_exact(&mut buf).await;
+                let _ = tx.send(buf);
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus);
+        conn.connect().await.unwrap();
+        conn.send_command("say \u{ff}").await.unwrap();
+
+        // "say " + U+00FF encoded as UTF-8 (0xC3 0xBF) + CR LF
+        let sent = rx.await.unwrap();
+        assert_eq!(sent, b"say \xc3\xbf\r\n");
+    }
+
     #[tokio::test]
     async fn test_receive_data_publishes_event() {
         let (listener, port) = start_mock_server().await;
@@ -433,6 +1149,555 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_refuses_unknown_telnet_option() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // IAC WILL 200 split across two writes, followed by text
+                let _ = socket.write_all(&[255, 251]).await;
+                let _ = socket.flush().await;
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let _ = socket.write_all(&[200, b'h', b'i']).await;
+
+                // Skip the IAC WILL NAWS offer sent on connect
+                let mut buf = [0u8; 3];
+                let _ = socket.read_exact(&mut buf).await;
+                let _ = socket.read_exact(&mut buf).await;
+                let _ = tx.send(buf);
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus);
+        conn.connect().await.unwrap();
+
+        let mut text = Vec::new();
+        while text.len() < 2 {
+            text.extend(conn.receive().await.unwrap());
+        }
+        assert_eq!(text, b"hi");
+
+        // Client must answer IAC DONT 200
+        let reply = rx.await.unwrap();
+        assert_eq!(reply, [255, 254, 200]);
+    }
+
+    #[tokio::test]
+    async fn test_naws_sent_on_negotiation_and_resize() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // Client offers IAC WILL NAWS on connect
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+                let _ = socket.write_all(&[255, 253, 31]).await;
+
+                // Initial size, then the resized one (255 columns must be escaped)
+                let mut reports = vec![0u8; 9 + 10];
+                let _ = socket.read_exact(&mut reports).await;
+                let _ = tx.send((offer, reports));
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus);
+        conn.set_window_size(100, 40).await.unwrap();
+        conn.connect().await.unwrap();
+
+        // Process the server's DO NAWS
+        let _ = tokio::time::timeout(Duration::from_millis(200), conn.receive()).await;
+        conn.set_window_size(255, 50).await.unwrap();
+
+        let (offer, reports) = rx.await.unwrap();
+        assert_eq!(offer, [255, 251, 31]);
+        assert_eq!(&reports[..9], &[255, 250, 31, 0, 100, 0, 40, 255, 240]);
+        assert_eq!(&reports[9..], &[255, 250, 31, 0, 255, 255, 0, 50, 255, 240]);
+    }
+
+    #[tokio::test]
+    async fn test_ttype_reports_mtts() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // Skip the IAC WILL NAWS offer sent on connect
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let send = [255, 250, 24, 1, 255, 240];
+                let mut request = vec![255, 253, 24];
+                request.extend_from_slice(&send);
+                request.extend_from_slice(&send);
+                request.extend_from_slice(&send);
+                let _ = socket.write_all(&request).await;
+
+                let mut buf = vec![0u8; 256];
+                let mut received = Vec::new();
+                while !received.ends_with(b"MTTS 525\xff\xf0") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .truecolor(false)
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus);
+        conn.connect().await.unwrap();
+        let _ = tokio::time::timeout(Duration::from_millis(200), conn.receive()).await;
+
+        // WILL TTYPE, then name, terminal type and MTTS: ANSI (1) + UTF-8 (4) + 256 colours (8) + MNES (512)
+        let received = rx.await.unwrap();
+        let mut expected = vec![255, 251, 24];
+        for name in [&b"MACMUSH"[..], b"XTERM-256COLOR", b"MTTS 525"] {
+            expected.extend_from_slice(&[255, 250, 24, 0]);
+            expected.extend_from_slice(name);
+            expected.extend_from_slice(&[255, 240]);
+        }
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn test_new_environ_reports_mnes_variables() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                // DO NEW-ENVIRON, then SEND for five variables
+                let mut request = vec![255, 253, 39, 255, 250, 39, 1];
+                for name in ["CLIENT_NAME", "CLIENT_VERSION", "CHARSET", "IPADDRESS", "MTTS"] {
+                    request.push(0);
+                    request.extend_from_slice(name.as_bytes());
+                }
+                request.extend_from_slice(&[255, 240]);
+                let _ = socket.write_all(&request).await;
+
+                let mut buf = vec![0u8; 256];
+                let mut received = Vec::new();
+                while !received.ends_with(&[255, 240]) {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .new_environ(vec![
+                EnvironOverride::suppress("CLIENT_VERSION"),
+                EnvironOverride::set("SCREEN_READER", "1"),
+            ])
+            .build()
+            .unwrap();
+        let mut conn = Connection::new(world, Arc::new(EventBus::new()));
+        conn.connect().await.unwrap();
+        let _ = tokio::time::timeout(Duration::from_millis(200), conn.receive()).await;
+
+        // Suppressed and unset variables are sent without a value;
+        // MTTS: ANSI + UTF-8 + 256 colours + truecolor + MNES + screen reader
+        let mut expected = vec![255, 251, 39, 255, 250, 39, 0];
+        expected.extend_from_slice(b"\x00CLIENT_NAME\x01MACMush\x00CLIENT_VERSION\x00CHARSET\x01UTF-8");
+        expected.extend_from_slice(b"\x00IPADDRESS\x00MTTS\x01845");
+        expected.extend_from_slice(&[255, 240]);
+        assert_eq!(rx.await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_gmcp_hello_and_messages() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // Skip the IAC WILL NAWS offer sent on connect
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let mut request = vec![255, 251, 201];
+                request.extend_from_slice(&[255, 250, 201]);
+                request.extend_from_slice(br#"Char.Vitals {"hp": 42}"#);
+                request.extend_from_slice(&[255, 240]);
+                request.extend_from_slice(b"You feel better.\r\n");
+                let _ = socket.write_all(&request).await;
+
+                let mut buf = vec![0u8; 256];
+                let mut received = Vec::new();
+                while !received.ends_with(b"]\xff\xf0") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .gmcp_modules(vec!["Char 1".to_string()])
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus);
+        conn.connect().await.unwrap();
+        let data = conn.receive().await.unwrap();
+        assert_eq!(data, b"You feel better.\r\n".to_vec());
+
+        let messages = conn.take_gmcp_messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].package, "Char.Vitals");
+        assert_eq!(messages[0].data["hp"], 42);
+        assert!(conn.take_gmcp_messages().is_empty());
+
+        // DO GMCP, then Core.Hello and Core.Supports.Set
+        let received = rx.await.unwrap();
+        let mut expected = vec![255, 253, 201];
+        let hello = format!(r#"Core.Hello {{"client":"MACMush","version":"{}"}}"#, env!("CARGO_PKG_VERSION"));
+        for payload in [hello.as_bytes(), br#"Core.Supports.Set ["Char 1"]"#] {
+            expected.extend_from_slice(&[255, 250, 201]);
+            expected.extend_from_slice(payload);
+            expected.extend_from_slice(&[255, 240]);
+        }
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn test_tls_world_publishes_tls_error() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.write_all(b"Welcome to a plain-text MUD!\r\n").await;
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+            }
+        });
+
+        let world = World::builder("Test MUD", "localhost", port)
+            .use_tls(true)
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx = event_bus.subscribe();
+
+        let mut conn = Connection::new(world, event_bus);
+        let result = conn.connect().await;
+        assert!(matches!(result, Err(MushError::TlsError(_))));
+        assert!(conn.tls_info().is_none());
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event, MudEvent::TlsError { .. }));
+        assert!(event.is_error());
+    }
+
+    /// Helper: zlib-compress a complete stream
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        use flate2::write::ZlibEncoder;
+        use std::io::Write;
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_mccp2_starts_and_ends_mid_read() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // WILL COMPRESS2, plain text, SB COMPRESS2, a full zlib stream, then plain text again
+                let mut data = vec![255, 251, 86];
+                data.extend_from_slice(b"plain ");
+                data.extend_from_slice(&[255, 250, 86, 255, 240]);
+                data.extend_from_slice(&zlib(b"compressed \xff\xf9"));
+                data.extend_from_slice(b"after\r\n");
+                let _ = socket.write_all(&data).await;
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port).build().unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus.clone());
+        conn.connect().await.unwrap();
+        let mut rx = event_bus.subscribe();
+
+        let data = conn.receive().await.unwrap();
+        assert_eq!(data, b"plain compressed after\r\n".to_vec());
+        assert_eq!(conn.take_prompt_marks(), vec![17]);
+        assert!(!conn.mccp.is_receiving_compressed());
+
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event, MudEvent::CompressionEnded { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_telnet_and_mccp_over_websocket() {
+        use crate::core::Transport;
+        use futures_util::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx_server) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((socket, _)) = listener.accept().await {
+                let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+
+                // Telnet sequences and the zlib stream split across messages
+                let compressed = zlib(b"compressed ");
+                let (head, tail) = compressed.split_at(compressed.len() / 2);
+                let mut second = vec![86, 255, 240];
+                second.extend_from_slice(head);
+                let mut third = tail.to_vec();
+                third.extend_from_slice(b"after\r\n");
+                for frame in [b"\xff\xfb\x56plain \xff\xfa".to_vec(), second, third] {
+                    ws.send(Message::Binary(frame.into())).await.unwrap();
+                }
+
+                // NAWS offer on connect, then DO COMPRESS2
+                let mut received = Vec::new();
+                while received.len() < 6 {
+                    match ws.next().await {
+                        Some(Ok(Message::Binary(data))) => received.extend_from_slice(&data),
+                        _ => break,
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Web MUD", "127.0.0.1", port)
+            .transport(Transport::WebSocket { path: "/".to_string() })
+            .build()
+            .unwrap();
+        let mut conn = Connection::new(world, Arc::new(EventBus::new()));
+        conn.connect().await.unwrap();
+        assert!(!conn.client.is_tls());
+
+        let mut text = Vec::new();
+        while !text.ends_with(b"\r\n") {
+            text.extend_from_slice(&conn.receive().await.unwrap());
+        }
+        assert_eq!(text, b"plain compressed after\r\n".to_vec());
+        assert_eq!(rx_server.await.unwrap(), vec![255, 251, 31, 255, 253, 86]);
+    }
+
+    #[tokio::test]
+    async fn test_mccp2_corrupt_stream() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx_server) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let mut data = vec![255, 251, 86, 255, 250, 86, 255, 240];
+                data.extend_from_slice(b"\x78\x9c\xff\xff\xff\xff");
+                let _ = socket.write_all(&data).await;
+
+                // DO COMPRESS2, then DONT COMPRESS2 once the stream fails
+                let mut reply = [0u8; 6];
+                let _ = socket.read_exact(&mut reply).await;
+                let _ = tx.send(reply);
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port).build().unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus.clone());
+        conn.connect().await.unwrap();
+        let mut rx = event_bus.subscribe();
+
+        let data = conn.receive().await.unwrap();
+        assert!(data.is_empty());
+        assert!(!conn.mccp.is_receiving_compressed());
+
+        let event = rx.recv().await.unwrap();
+        assert!(event.is_error());
+        assert!(matches!(event, MudEvent::CompressionError { .. }));
+
+        assert_eq!(rx_server.await.unwrap(), [255, 253, 86, 255, 254, 86]);
+    }
+
+    #[tokio::test]
+    async fn test_charset_negotiation() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                // DO CHARSET, then REQUEST offering KOI8-R and UTF-8
+                let mut request = vec![255, 253, 42];
+                request.extend_from_slice(&[255, 250, 42, 1]);
+                request.extend_from_slice(b";KOI8-R;UTF-8");
+                request.extend_from_slice(&[255, 240]);
+                request.extend_from_slice(b"caf\xc3\xa9\r\n");
+                let _ = socket.write_all(&request).await;
+
+                let mut buf = vec![0u8; 256];
+                let mut received = Vec::new();
+                while !received.ends_with(b"\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .encoding(TextEncoding::Cp437)
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+
+        let mut conn = Connection::new(world, event_bus);
+        assert_eq!(conn.encoding(), TextEncoding::Cp437);
+        conn.connect().await.unwrap();
+
+        let data = conn.receive().await.unwrap();
+        assert_eq!(conn.encoding(), TextEncoding::Utf8);
+        assert_eq!(conn.decode(&data), "café\r\n");
+
+        conn.send_command("café").await.unwrap();
+
+        // WILL CHARSET, ACCEPTED UTF-8, then the command encoded as UTF-8
+        let received = rx.await.unwrap();
+        let mut expected = vec![255, 251, 42, 255, 250, 42, 2];
+        expected.extend_from_slice(b"UTF-8");
+        expected.extend_from_slice(&[255, 240]);
+        expected.extend_from_slice(b"caf\xc3\xa9\r\n");
+        assert_eq!(received, expected);
+    }
+
+    #[tokio::test]
+    async fn test_mxp_negotiation() {
+        for mxp in [true, false] {
+            let (listener, port) = start_mock_server().await;
+
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            tokio::spawn(async move {
+                if let Ok((mut socket, _)) = listener.accept().await {
+                    let mut offer = [0u8; 3];
+                    let _ = socket.read_exact(&mut offer).await;
+
+                    // WILL MXP, then a secure line with a tag
+                    let _ = socket.write_all(b"\xff\xfb\x5b\x1b[1z<b>hi</b>\r\n").await;
+
+                    let mut reply = [0u8; 3];
+                    let _ = socket.read_exact(&mut reply).await;
+                    let _ = tx.send(reply);
+                }
+            });
+
+            let world = World::builder("Test MUD", "127.0.0.1", port).mxp(mxp).build().unwrap();
+            let mut conn = Connection::new(world, Arc::new(EventBus::new()));
+            conn.connect().await.unwrap();
+
+            let data = conn.receive().await.unwrap();
+            let text = conn.decode(&data);
+            let output = conn.process_mxp(&text);
+            assert_eq!(conn.is_mxp_enabled(), mxp);
+
+            if mxp {
+                assert_eq!(rx.await.unwrap(), [255, 253, TELOPT_MXP]);
+                assert_eq!(output.text, "hi\r\n");
+                assert_eq!(output.spans.len(), 1);
+                assert_eq!(conn.mxp_visible_text("<b>&gt;</b>"), ">");
+            } else {
+                assert_eq!(rx.await.unwrap(), [255, 254, TELOPT_MXP]);
+                assert_eq!(output.text, text);
+                assert!(output.spans.is_empty());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_probe_mssp() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // IAC WILL NAWS, then IAC DO MSSP
+                let mut request = [0u8; 6];
+                let _ = socket.read_exact(&mut request).await;
+                assert_eq!(request, [255, 251, 31, 255, 253, 70]);
+
+                let mut reply = vec![255, 251, 70, 255, 250, 70];
+                reply.extend_from_slice(b"\x01NAME\x02Test MUD\x01PLAYERS\x0217");
+                reply.extend_from_slice(&[255, 240]);
+                let _ = socket.write_all(&reply).await;
+
+                // Hold the connection open until the client disconnects
+                let mut buf = [0u8; 16];
+                while let Ok(n) = socket.read(&mut buf).await {
+                    if n == 0 {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let mssp = Connection::probe_mssp(world).await.unwrap();
+
+        assert_eq!(mssp.name(), Some("Test MUD".to_string()));
+        assert_eq!(mssp.players(), Some(17));
+    }
+
+    #[tokio::test]
+    async fn test_probe_mssp_refused() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 6];
+                let _ = socket.read_exact(&mut request).await;
+                let _ = socket.write_all(&[255, 252, 70]).await;
+
+                let mut buf = [0u8; 16];
+                let _ = socket.read(&mut buf).await;
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let result = Connection::probe_mssp(world).await;
+
+        assert!(matches!(result, Err(MushError::ProtocolNotSupported(_))));
+    }
+
     #[tokio::test]
     async fn test_world_reference() {
         let world = World::new("Test MUD", "mud.example.com", 4000).unwrap();
@@ -7,8 +7,11 @@
 /// - UI subscribes to all events for display
 
 use crate::automation::HighlightStyle;
+use crate::core::ThrottleReason;
 use crate::error::Result;
+use crate::network::{MspRequest, MsdpValue, MsspData, MxpLink, MxpSpan, SoundKind};
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
 use tokio::sync::broadcast;
 use uuid::Uuid;
 
@@ -23,6 +26,8 @@ pub enum MudEvent {
         world_id: Uuid,
         host: String,
         port: u16,
+        /// Resolved address connected to (`None` through a proxy)
+        address: Option<SocketAddr>,
     },
 
     /// Connection closed
@@ -31,6 +36,20 @@ pub enum MudEvent {
         reason: String,
     },
 
+    /// Connection dropped; reconnect attempt `attempt` starts after `delay_ms`
+    Reconnecting {
+        world_id: Uuid,
+        attempt: u32,
+        max_attempts: u32,
+        delay_ms: u64,
+    },
+
+    /// Connection re-established after `attempts` reconnect attempts
+    Reconnected {
+        world_id: Uuid,
+        attempts: u32,
+    },
+
     /// Data received from MUD server
     DataReceived {
         world_id: Uuid,
@@ -44,6 +63,20 @@ pub enum MudEvent {
         command: String,
     },
 
+    /// Outgoing commands are waiting in the send queue
+    SendThrottled {
+        world_id: Uuid,
+        queued: usize,
+        reason: ThrottleReason,
+    },
+
+    /// Anti-idle fired after no commands were sent for a while;
+    /// `command` is `None` when a telnet NOP was sent
+    AntiIdleSent {
+        world_id: Uuid,
+        command: Option<String>,
+    },
+
     /// Trigger matched incoming text
     TriggerMatched {
         world_id: Uuid,
@@ -59,6 +92,25 @@ pub enum MudEvent {
         commands: Vec<String>,
     },
 
+    /// TLS handshake completed
+    TlsNegotiated {
+        world_id: Uuid,
+        protocol_version: String,
+        cipher_suite: String,
+    },
+
+    /// Self-signed certificate accepted on first use; it is pinned for later connections
+    CertificatePinned {
+        world_id: Uuid,
+        fingerprint: String,
+    },
+
+    /// TLS handshake failed (including a changed pinned certificate)
+    TlsError {
+        world_id: Uuid,
+        error: String,
+    },
+
     /// Connection error occurred
     ConnectionError {
         world_id: Uuid,
@@ -108,11 +160,82 @@ pub enum MudEvent {
         error: String,
     },
 
+    /// MXP styles and links for the text of the DataReceived event that follows
+    MxpStyled {
+        world_id: Uuid,
+        spans: Vec<MxpSpan>,
+        links: Vec<MxpLink>,
+    },
+
+    /// Play a sound requested by MSP or a trigger
+    PlaySound {
+        world_id: Uuid,
+        /// File resolved against the world's sounds directory
+        path: String,
+        sound: MspRequest,
+    },
+
+    /// MSP asked to stop the sound or music playing
+    StopSound {
+        world_id: Uuid,
+        kind: SoundKind,
+    },
+
+    /// Sound file could not be found or is not allowed
+    SoundError {
+        world_id: Uuid,
+        file: String,
+        error: String,
+    },
+
     /// Highlight matched incoming text
     HighlightMatched {
         world_id: Uuid,
         matches: Vec<(usize, usize, HighlightStyle)>,
     },
+
+    /// Prompt received (partial line ended by IAC GA / IAC EOR)
+    PromptReceived {
+        world_id: Uuid,
+        prompt: String,
+    },
+
+    /// Server toggled echo: mask the input box and keep input out of history and logs
+    InputMaskChanged {
+        world_id: Uuid,
+        masked: bool,
+    },
+
+    /// GMCP package received from MUD server
+    GmcpReceived {
+        world_id: Uuid,
+        package: String,
+        data: serde_json::Value,
+    },
+
+    /// MSDP variable received from MUD server
+    MsdpReceived {
+        world_id: Uuid,
+        variable: String,
+        value: MsdpValue,
+    },
+
+    /// MSSP server status received from MUD server
+    MsspReceived {
+        world_id: Uuid,
+        data: MsspData,
+    },
+
+    /// Server ended the MCCP2 stream; data continues uncompressed
+    CompressionEnded {
+        world_id: Uuid,
+    },
+
+    /// MCCP2 stream was corrupt; compression has been turned off
+    CompressionError {
+        world_id: Uuid,
+        error: String,
+    },
 }
 
 impl MudEvent {
@@ -121,18 +244,36 @@ impl MudEvent {
         match self {
             MudEvent::Connected { world_id, .. }
             | MudEvent::Disconnected { world_id, .. }
+            | MudEvent::Reconnecting { world_id, .. }
+            | MudEvent::Reconnected { world_id, .. }
             | MudEvent::DataReceived { world_id, .. }
             | MudEvent::CommandSent { world_id, .. }
+            | MudEvent::AntiIdleSent { world_id, .. }
+            | MudEvent::SendThrottled { world_id, .. }
             | MudEvent::TriggerMatched { world_id, .. }
             | MudEvent::TriggerExecuted { world_id, .. }
             | MudEvent::ConnectionError { world_id, .. }
+            | MudEvent::TlsNegotiated { world_id, .. }
+            | MudEvent::CertificatePinned { world_id, .. }
+            | MudEvent::TlsError { world_id, .. }
             | MudEvent::TriggerError { world_id, .. }
             | MudEvent::AliasMatched { world_id, .. }
             | MudEvent::AliasExecuted { world_id, .. }
             | MudEvent::AliasError { world_id, .. }
             | MudEvent::TimerExecuted { world_id, .. }
             | MudEvent::TimerError { world_id, .. }
-            | MudEvent::HighlightMatched { world_id, .. } => *world_id,
+            | MudEvent::HighlightMatched { world_id, .. }
+            | MudEvent::MxpStyled { world_id, .. }
+            | MudEvent::PlaySound { world_id, .. }
+            | MudEvent::StopSound { world_id, .. }
+            | MudEvent::SoundError { world_id, .. }
+            | MudEvent::PromptReceived { world_id, .. }
+            | MudEvent::InputMaskChanged { world_id, .. }
+            | MudEvent::GmcpReceived { world_id, .. }
+            | MudEvent::MsdpReceived { world_id, .. }
+            | MudEvent::MsspReceived { world_id, .. }
+            | MudEvent::CompressionEnded { world_id, .. }
+            | MudEvent::CompressionError { world_id, .. } => *world_id,
         }
     }
 
@@ -141,9 +282,12 @@ impl MudEvent {
         matches!(
             self,
             MudEvent::ConnectionError { .. }
+            | MudEvent::TlsError { .. }
             | MudEvent::TriggerError { .. }
             | MudEvent::AliasError { .. }
             | MudEvent::TimerError { .. }
+            | MudEvent::SoundError { .. }
+            | MudEvent::CompressionError { .. }
         )
     }
 }
@@ -217,6 +361,7 @@ mod tests {
             world_id,
             host: "mud.example.com".to_string(),
             port: 4000,
+            address: None,
         };
 
         bus.publish(event.clone()).unwrap();
@@ -257,6 +402,7 @@ mod tests {
                 world_id,
                 host: "test.mud".to_string(),
                 port: 4000,
+                address: None,
             },
             MudEvent::Disconnected {
                 world_id,
@@ -305,7 +451,8 @@ mod tests {
             !MudEvent::Connected {
                 world_id,
                 host: "test".to_string(),
-                port: 4000
+                port: 4000,
+                address: None,
             }
             .is_error()
         );
@@ -335,6 +482,7 @@ mod tests {
             world_id,
             host: "test.mud".to_string(),
             port: 4000,
+            address: None,
         };
 
         let result = bus.publish(event);
@@ -350,6 +498,7 @@ mod tests {
             world_id,
             host: "test.mud".to_string(),
             port: 4000,
+            address: None,
         };
 
         // Publish before subscribing
@@ -378,6 +527,7 @@ mod tests {
             world_id,
             host: "mud.example.com".to_string(),
             port: 4000,
+            address: None,
         };
 
         let json = serde_json::to_string(&event);
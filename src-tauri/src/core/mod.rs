@@ -4,12 +4,14 @@
 /// world configuration, and session management.
 
 pub mod connection;
+pub mod send_queue;
 pub mod world;
 pub mod session;
 pub mod events;
 
 // Re-export commonly used types
 pub use connection::Connection;
-pub use world::{World, WorldBuilder};
+pub use send_queue::{CommandOrigin, QueuedCommand, SendQueue, SendQueueStatus, ThrottleReason};
+pub use world::{World, WorldBuilder, LineEnding, ClientCertificate, ReconnectPolicy, Transport, TcpKeepalive, AntiIdle, RateLimit};
 pub use session::Session;
 pub use events::{MudEvent, EventBus};
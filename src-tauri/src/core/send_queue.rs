@@ -0,0 +1,328 @@
+/// Outgoing command queue with rate limiting and a flood guard
+///
+/// Every command for the server passes through the queue. With rate limiting
+/// enabled a token bucket releases them: `burst` commands may go out at
+/// once, then the bucket refills at `commands_per_second`. Commands that
+/// have to wait are kept in order and released as tokens return.
+///
+/// The flood guard counts automation commands (triggers, timers, scripts).
+/// Once more than `flood_threshold` arrive within one second it trips and
+/// holds automation commands until the queue is resumed, while the user's
+/// own commands keep flowing. A runaway trigger loop thus stalls instead of
+/// getting us kicked for spamming.
+
+use crate::core::RateLimit;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+/// Window the flood guard counts automation commands in
+const FLOOD_WINDOW: Duration = Duration::from_secs(1);
+
+/// Where a command came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandOrigin {
+    /// Typed by the user (including aliases and speedwalks it expands to)
+    User,
+    /// Sent by a trigger, timer or script
+    Automation,
+}
+
+/// Why commands are waiting in the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ThrottleReason {
+    /// Sending faster than the rate limit allows
+    RateLimit,
+    /// Paused by the user or a script
+    Paused,
+    /// Automation commands held by the flood guard
+    FloodGuard,
+}
+
+/// Command waiting to be sent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueuedCommand {
+    pub command: String,
+    pub origin: CommandOrigin,
+}
+
+/// Snapshot of the queue for the UI and scripts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SendQueueStatus {
+    pub commands: Vec<QueuedCommand>,
+    pub paused: bool,
+    pub flood_guard: bool,
+}
+
+/// Per-world outgoing command queue
+#[derive(Debug)]
+pub struct SendQueue {
+    limit: RateLimit,
+    entries: VecDeque<QueuedCommand>,
+    /// Commands that may be sent right now (token bucket)
+    tokens: f64,
+    last_refill: Instant,
+    paused: bool,
+    flood_guard: bool,
+    /// When recent automation commands arrived, for the flood guard
+    recent_automation: VecDeque<Instant>,
+    /// Reason last announced for the current backlog
+    announced: Option<ThrottleReason>,
+}
+
+impl SendQueue {
+    /// Create an empty queue with a full token bucket
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            limit,
+            entries: VecDeque::new(),
+            last_refill: Instant::now(),
+            paused: false,
+            flood_guard: false,
+            recent_automation: VecDeque::new(),
+            announced: None,
+        }
+    }
+
+    /// Add a command to the back of the queue
+    pub fn push(&mut self, command: impl Into<String>, origin: CommandOrigin, now: Instant) {
+        if origin == CommandOrigin::Automation && self.limit.flood_threshold > 0 {
+            while self
+                .recent_automation
+                .front()
+                .is_some_and(|&at| now.saturating_duration_since(at) >= FLOOD_WINDOW)
+            {
+                self.recent_automation.pop_front();
+            }
+            self.recent_automation.push_back(now);
+
+            if !self.flood_guard && self.recent_automation.len() > self.limit.flood_threshold as usize {
+                warn!(
+                    "Flood guard tripped: {} automation commands within {:?}",
+                    self.recent_automation.len(),
+                    FLOOD_WINDOW
+                );
+                self.flood_guard = true;
+            }
+        }
+
+        self.entries.push_back(QueuedCommand {
+            command: command.into(),
+            origin,
+        });
+    }
+
+    /// Take the next command that may be sent now, if any
+    ///
+    /// Skips automation commands held by the flood guard.
+    pub fn pop(&mut self, now: Instant) -> Option<String> {
+        if self.paused {
+            return None;
+        }
+
+        if self.limit.enabled {
+            let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+            self.tokens = (self.tokens + elapsed * self.limit.commands_per_second).min(f64::from(self.limit.burst));
+            self.last_refill = now;
+            if self.tokens < 1.0 {
+                return None;
+            }
+        }
+
+        let flood_guard = self.flood_guard;
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| !(flood_guard && entry.origin == CommandOrigin::Automation))?;
+        let entry = self.entries.remove(index)?;
+
+        if self.limit.enabled {
+            self.tokens -= 1.0;
+        }
+        Some(entry.command)
+    }
+
+    /// Report a backlog once per reason
+    ///
+    /// Returns the number of waiting commands and why they wait the first
+    /// time commands are left waiting, and again whenever the reason
+    /// changes; `None` otherwise.
+    pub fn backlog(&mut self) -> Option<(usize, ThrottleReason)> {
+        if self.entries.is_empty() {
+            self.announced = None;
+            return None;
+        }
+
+        let reason = if self.paused {
+            ThrottleReason::Paused
+        } else if self.flood_guard && self.entries.iter().all(|e| e.origin == CommandOrigin::Automation) {
+            ThrottleReason::FloodGuard
+        } else {
+            ThrottleReason::RateLimit
+        };
+
+        if self.announced == Some(reason) {
+            return None;
+        }
+        self.announced = Some(reason);
+        debug!("{} command(s) waiting ({:?})", self.entries.len(), reason);
+        Some((self.entries.len(), reason))
+    }
+
+    /// Pause or resume sending; resuming also resets the flood guard
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            self.flood_guard = false;
+            self.recent_automation.clear();
+        }
+    }
+
+    /// Discard all waiting commands, returning how many there were
+    pub fn clear(&mut self) -> usize {
+        let discarded = self.entries.len();
+        self.entries.clear();
+        self.announced = None;
+        discarded
+    }
+
+    /// Number of waiting commands
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether no commands are waiting
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Check whether the queue is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Check whether the flood guard is holding automation commands
+    pub fn is_flood_guarded(&self) -> bool {
+        self.flood_guard
+    }
+
+    /// Snapshot of the waiting commands and queue state
+    pub fn status(&self) -> SendQueueStatus {
+        SendQueueStatus {
+            commands: self.entries.iter().cloned().collect(),
+            paused: self.paused,
+            flood_guard: self.flood_guard,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limited(commands_per_second: f64, burst: u32) -> SendQueue {
+        SendQueue::new(RateLimit {
+            enabled: true,
+            commands_per_second,
+            burst,
+            ..RateLimit::default()
+        })
+    }
+
+    fn drain(queue: &mut SendQueue, now: Instant) -> Vec<String> {
+        std::iter::from_fn(|| queue.pop(now)).collect()
+    }
+
+    #[test]
+    fn test_unlimited_queue_passes_through() {
+        let mut queue = SendQueue::new(RateLimit::default());
+        let now = Instant::now();
+        for i in 0..100 {
+            queue.push(format!("cmd{}", i), CommandOrigin::Automation, now);
+        }
+        assert_eq!(drain(&mut queue, now).len(), 100);
+        assert_eq!(queue.backlog(), None);
+    }
+
+    #[test]
+    fn test_burst_then_rate() {
+        let mut queue = limited(2.0, 3);
+        let start = Instant::now();
+        for command in ["n", "e", "s", "w", "u"] {
+            queue.push(command, CommandOrigin::User, start);
+        }
+
+        assert_eq!(drain(&mut queue, start), vec!["n", "e", "s"]);
+        assert_eq!(queue.backlog(), Some((2, ThrottleReason::RateLimit)));
+        assert_eq!(queue.backlog(), None, "Announced once per backlog");
+
+        assert_eq!(drain(&mut queue, start + Duration::from_millis(400)), Vec::<String>::new());
+        assert_eq!(drain(&mut queue, start + Duration::from_millis(500)), vec!["w"]);
+        assert_eq!(drain(&mut queue, start + Duration::from_millis(1000)), vec!["u"]);
+        assert_eq!(queue.backlog(), None);
+
+        // Bucket refills up to the burst size only
+        let later = start + Duration::from_secs(60);
+        for _ in 0..5 {
+            queue.push("look", CommandOrigin::User, later);
+        }
+        assert_eq!(drain(&mut queue, later).len(), 3);
+    }
+
+    #[test]
+    fn test_pause_and_clear() {
+        let mut queue = SendQueue::new(RateLimit::default());
+        let now = Instant::now();
+        queue.set_paused(true);
+        queue.push("kill rat", CommandOrigin::User, now);
+        queue.push("get all", CommandOrigin::Automation, now);
+
+        assert_eq!(queue.pop(now), None);
+        assert_eq!(queue.backlog(), Some((2, ThrottleReason::Paused)));
+        assert_eq!(
+            queue.status().commands[1],
+            QueuedCommand { command: "get all".to_string(), origin: CommandOrigin::Automation }
+        );
+
+        assert_eq!(queue.clear(), 2);
+        assert!(queue.is_empty());
+        queue.set_paused(false);
+        queue.push("look", CommandOrigin::User, now);
+        assert_eq!(drain(&mut queue, now), vec!["look"]);
+    }
+
+    #[test]
+    fn test_flood_guard_holds_automation_only() {
+        let mut queue = SendQueue::new(RateLimit {
+            flood_threshold: 3,
+            ..RateLimit::default()
+        });
+        let start = Instant::now();
+
+        // Spread out: never more than 3 per second
+        for i in 0..6 {
+            queue.push("heal", CommandOrigin::Automation, start + Duration::from_millis(400 * i));
+        }
+        assert!(!queue.is_flood_guarded());
+        assert_eq!(drain(&mut queue, start).len(), 6);
+
+        let burst = start + Duration::from_secs(10);
+        for _ in 0..4 {
+            queue.push("heal", CommandOrigin::Automation, burst);
+        }
+        assert!(queue.is_flood_guarded());
+        queue.push("flee", CommandOrigin::User, burst);
+
+        assert_eq!(drain(&mut queue, burst), vec!["flee"]);
+        assert_eq!(queue.backlog(), Some((4, ThrottleReason::FloodGuard)));
+
+        queue.set_paused(false);
+        assert!(!queue.is_flood_guarded());
+        assert_eq!(drain(&mut queue, burst).len(), 4);
+    }
+}
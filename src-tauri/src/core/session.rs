@@ -5,11 +5,17 @@
 
 use crate::automation::triggers::{Trigger, TriggerManager, TriggerAction};
 use crate::automation::{Alias, AliasManager, Timer, TimerManager, Highlight, HighlightManager, VariableManager, CommandHistory, TabCompletion, KeypadMapping, Speedwalk};
-use crate::core::{Connection, EventBus, MudEvent, World};
-use crate::error::Result;
+use crate::core::{CommandOrigin, Connection, EventBus, MudEvent, SendQueueStatus, World};
+use crate::error::{MushError, Result};
+use crate::network::{msp, CertificateTrust, CompressionStats, GmcpMessage, GmcpStore, MsdpCommand, MspRequest, MsspData, MxpOutput, SoundKind, TlsClient};
 use crate::scripting::{LuaRuntime, WorldApi};
-use std::sync::Arc;
-use tracing::{debug, error, info};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tracing::{debug, error, info, warn};
+
+/// Maximum text kept after the last newline; older bytes are dropped first
+const MAX_PARTIAL_LINE: usize = 64 * 1024;
 
 /// MUD session orchestrator
 pub struct Session {
@@ -26,6 +32,16 @@ pub struct Session {
     event_bus: Arc<EventBus>,
     lua_runtime: LuaRuntime,
     world_api: WorldApi,
+    /// Latest GMCP data per package (shared with the Lua world API)
+    gmcp: Arc<Mutex<GmcpStore>>,
+    /// Latest MSSP server status
+    mssp: Option<MsspData>,
+    /// Text received after the last newline, flushed as a prompt on IAC GA / IAC EOR
+    partial_line: Vec<u8>,
+    /// Reconnect attempts made since the connection dropped
+    reconnect_attempt: u32,
+    /// Set to true by `stop` to cancel pending reconnects
+    reconnect_cancel: watch::Sender<bool>,
 }
 
 impl Session {
@@ -47,8 +63,9 @@ impl Session {
 
         // Initialize Lua runtime and World API
         let lua_runtime = LuaRuntime::new(&world_id)?;
-        let world_api = WorldApi::new(&world_id);
+        let world_api = WorldApi::new(&world_id).with_send_queue(connection.send_queue());
         world_api.register_functions(lua_runtime.lua())?;
+        let gmcp = world_api.gmcp_store();
 
         Ok(Self {
             connection,
@@ -64,32 +81,185 @@ impl Session {
             event_bus,
             lua_runtime,
             world_api,
+            gmcp,
+            mssp: None,
+            partial_line: Vec::new(),
+            reconnect_attempt: 0,
+            reconnect_cancel: watch::Sender::new(false),
         })
     }
 
     /// Start the session (connect to MUD)
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting session for world '{}'", self.connection.world().name);
-        self.connection.connect().await
+        self.gmcp.lock().unwrap().clear();
+        self.partial_line.clear();
+        self.reconnect_attempt = 0;
+        self.reconnect_cancel.send_replace(false);
+        self.connection.connect().await?;
+        self.send_connect_commands().await
     }
 
     /// Stop the session (disconnect from MUD)
+    ///
+    /// Also cancels any pending reconnect.
     pub async fn stop(&mut self) -> Result<()> {
         info!("Stopping session for world '{}'", self.connection.world().name);
 
         // Stop all timers
         self.timer_manager.stop_all();
 
+        self.reconnect_cancel.send_replace(true);
+        self.reconnect_attempt = 0;
+
         self.connection.disconnect().await
     }
 
+    /// Schedule the next reconnect attempt after the connection dropped
+    ///
+    /// Returns the delay to wait before calling `reconnect` and publishes
+    /// `Reconnecting`, or `None` when reconnecting is disabled, cancelled by
+    /// `stop`, or out of attempts (publishing a `ConnectionError`).
+    pub fn schedule_reconnect(&mut self) -> Result<Option<Duration>> {
+        let world = self.connection.world();
+        let policy = &world.reconnect;
+        if !policy.enabled || *self.reconnect_cancel.borrow() {
+            return Ok(None);
+        }
+
+        if self.reconnect_attempt >= policy.max_attempts {
+            warn!("Giving up reconnecting to '{}' after {} attempts", world.name, self.reconnect_attempt);
+            self.event_bus.publish(MudEvent::ConnectionError {
+                world_id: world.id,
+                error: format!("Gave up reconnecting after {} attempts", self.reconnect_attempt),
+            })?;
+            self.reconnect_attempt = 0;
+            return Ok(None);
+        }
+
+        self.reconnect_attempt += 1;
+        let delay = policy.delay(self.reconnect_attempt);
+        info!(
+            "Reconnecting to '{}' in {:?} (attempt {}/{})",
+            world.name, delay, self.reconnect_attempt, policy.max_attempts
+        );
+
+        self.event_bus.publish(MudEvent::Reconnecting {
+            world_id: world.id,
+            attempt: self.reconnect_attempt,
+            max_attempts: policy.max_attempts,
+            delay_ms: delay.as_millis() as u64,
+        })?;
+        Ok(Some(delay))
+    }
+
+    /// Watch that turns true when `stop` cancels pending reconnects
+    pub fn reconnect_cancellation(&self) -> watch::Receiver<bool> {
+        self.reconnect_cancel.subscribe()
+    }
+
+    /// Make the reconnect attempt scheduled by `schedule_reconnect`
+    ///
+    /// Triggers, aliases, timers and variables are kept; protocol state
+    /// starts fresh and the world's connect commands are sent again.
+    /// Publishes `Reconnected` on success.
+    ///
+    /// The receive loop makes the same attempt through
+    /// `take_reconnect_client` and `finish_reconnect` instead, so the
+    /// session need not stay locked while connecting.
+    ///
+    /// # Errors
+    /// - `MushError::NotConnected`: Reconnect was cancelled by `stop`
+    /// - Any error from `Connection::connect`
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let mut client = self.take_reconnect_client().await?;
+        let result = client.connect().await;
+        self.finish_reconnect(client, result).await
+    }
+
+    /// Take the connection's client out for a reconnect attempt
+    ///
+    /// Connect the client, then pass it and the outcome to `finish_reconnect`.
+    ///
+    /// # Errors
+    /// - `MushError::NotConnected`: Reconnect was cancelled by `stop`
+    pub async fn take_reconnect_client(&mut self) -> Result<TlsClient> {
+        if *self.reconnect_cancel.borrow() {
+            return Err(MushError::NotConnected);
+        }
+        Ok(self.connection.take_client().await)
+    }
+
+    /// Install a client connected for `take_reconnect_client`
+    ///
+    /// # Errors
+    /// - `MushError::NotConnected`: `stop` cancelled the reconnect meanwhile
+    /// - The connect error in `result`
+    pub async fn finish_reconnect(&mut self, mut client: TlsClient, result: Result<()>) -> Result<()> {
+        if *self.reconnect_cancel.borrow() {
+            if let Err(e) = client.disconnect().await {
+                debug!("Error closing cancelled reconnect: {}", e);
+            }
+            return Err(MushError::NotConnected);
+        }
+
+        self.gmcp.lock().unwrap().clear();
+        self.mssp = None;
+        self.partial_line.clear();
+        self.connection.install_client(client, result).await?;
+        self.send_connect_commands().await?;
+
+        info!(
+            "Reconnected to '{}' after {} attempts",
+            self.connection.world().name,
+            self.reconnect_attempt
+        );
+        self.event_bus.publish(MudEvent::Reconnected {
+            world_id: self.connection.world().id,
+            attempts: self.reconnect_attempt,
+        })?;
+        self.reconnect_attempt = 0;
+        Ok(())
+    }
+
+    /// Send the world's connect commands (login) as-is, without history or aliases
+    async fn send_connect_commands(&mut self) -> Result<()> {
+        let commands = self.connection.world().connect_commands.clone();
+        for command in commands {
+            self.connection.send_command(&command).await?;
+        }
+        Ok(())
+    }
+
     /// Send command to MUD server (processes speedwalk and aliases)
+    ///
+    /// While input is masked (server echo, e.g. a password prompt) the
+    /// command is sent as-is: it is not recorded in history and not
+    /// expanded by speedwalk or aliases.
     pub async fn send_command(&mut self, command: impl AsRef<str>) -> Result<()> {
         let input = command.as_ref();
 
+        if self.connection.is_input_masked() {
+            return self.connection.send_command(input).await;
+        }
+
         // Add to command history
         self.command_history.add_command(input);
 
+        self.expand_and_send(input).await
+    }
+
+    /// Run the command of a clicked MXP link
+    ///
+    /// Goes through speedwalk and alias expansion like typed input, but is
+    /// not added to the command history.
+    pub async fn send_link_command(&mut self, command: &str) -> Result<()> {
+        debug!("MXP link command: {}", command);
+        self.expand_and_send(command).await
+    }
+
+    /// Expand a speedwalk or alias and send the resulting commands
+    async fn expand_and_send(&mut self, input: &str) -> Result<()> {
         // Try speedwalk expansion first
         if let Some(expanded_commands) = self.speedwalk.try_expand(input) {
             debug!("Speedwalk expanded '{}' into {} commands", input, expanded_commands.len());
@@ -98,19 +268,17 @@ impl Session {
             for cmd in expanded_commands {
                 // Recursively process each command (without adding to history)
                 // This allows speedwalk commands to trigger aliases
-                self.process_command_internal(&cmd).await?;
+                self.process_command_internal(&cmd, CommandOrigin::User).await?;
             }
             return Ok(());
         }
 
         // Process the actual command (may trigger alias or send directly)
-        self.process_command_internal(input).await?;
-
-        Ok(())
+        self.process_command_internal(input, CommandOrigin::User).await
     }
 
     /// Internal command processing (without history addition)
-    async fn process_command_internal(&mut self, input: &str) -> Result<()> {
+    async fn process_command_internal(&mut self, input: &str, origin: CommandOrigin) -> Result<()> {
         // Check if input matches an alias and extract all needed data
         let alias_data = if let Some(alias) = self.alias_manager.find_match(input)? {
             debug!("Alias '{}' matched for input: {}", alias.name, input);
@@ -163,11 +331,14 @@ impl Session {
                     Ok(result) => {
                         debug!("Alias script executed successfully: {:?}", result);
 
+                        // Send any GMCP/MSDP messages queued by world.SendGMCP()/SendMSDP()
+                        self.send_queued_oob().await?;
+
                         // Send any commands queued by world.Send()
                         let queued_commands = self.world_api.drain_command_queue();
                         for cmd in queued_commands {
                             debug!("Sending queued command from alias script: {}", cmd);
-                            Box::pin(self.process_command_internal(&cmd)).await?;
+                            Box::pin(self.process_command_internal(&cmd, origin)).await?;
                         }
                     }
                     Err(e) => {
@@ -191,7 +362,7 @@ impl Session {
                 })?;
 
                 for cmd in commands {
-                    if let Err(e) = self.connection.send_command(&cmd).await {
+                    if let Err(e) = self.connection.send_command_as(&cmd, origin).await {
                         error!("Failed to send alias command '{}': {}", cmd, e);
                         self.event_bus.publish(MudEvent::AliasError {
                             world_id: self.connection.world().id,
@@ -203,7 +374,7 @@ impl Session {
             }
         } else {
             // No alias matched, send command directly
-            self.connection.send_command(input).await?;
+            self.connection.send_command_as(input, origin).await?;
         }
 
         Ok(())
@@ -349,11 +520,14 @@ impl Session {
                     Ok(result) => {
                         debug!("Timer script executed successfully: {:?}", result);
 
+                        // Send any GMCP/MSDP messages queued by world.SendGMCP()/SendMSDP()
+                        self.send_queued_oob().await?;
+
                         // Send any commands queued by world.Send()
                         let queued_commands = self.world_api.drain_command_queue();
                         for cmd in queued_commands {
                             debug!("Sending queued command from timer script: {}", cmd);
-                            Box::pin(self.process_command_internal(&cmd)).await?;
+                            Box::pin(self.process_command_internal(&cmd, CommandOrigin::Automation)).await?;
                         }
                     }
                     Err(e) => {
@@ -378,7 +552,7 @@ impl Session {
 
                 // Send each command
                 for command in commands {
-                    if let Err(e) = self.connection.send_command(&command).await {
+                    if let Err(e) = self.connection.send_command_as(&command, CommandOrigin::Automation).await {
                         error!("Failed to send timer command '{}': {}", command, e);
                         self.event_bus.publish(MudEvent::TimerError {
                             world_id: self.connection.world().id,
@@ -393,11 +567,107 @@ impl Session {
         Ok(())
     }
 
+    /// Send the world's anti-idle command (or a telnet NOP) once no command
+    /// has been sent for its configured time, publishing AntiIdleSent
+    pub async fn process_anti_idle(&mut self) -> Result<()> {
+        let anti_idle = &self.connection.world().anti_idle;
+        if !anti_idle.enabled || !self.connection.is_connected() || self.connection.idle_time() < anti_idle.idle_time() {
+            return Ok(());
+        }
+
+        let command = anti_idle.command.clone();
+        debug!("Idle for {:?}, sending anti-idle {:?}", self.connection.idle_time(), command);
+        match &command {
+            Some(command) => {
+                self.connection.send_command_as(command, CommandOrigin::Automation).await?;
+                // Count it once queued, so a paused or throttled queue doesn't get one per check
+                self.connection.reset_idle_time();
+            }
+            None => self.connection.send_nop().await?,
+        }
+
+        self.event_bus.publish(MudEvent::AntiIdleSent {
+            world_id: self.connection.world().id,
+            command,
+        })?;
+        Ok(())
+    }
+
     /// Process incoming data: receive, match triggers, execute actions
     pub async fn process_incoming_data(&mut self) -> Result<()> {
-        // Receive data from connection
-        let data = self.connection.receive().await?;
-        let text = String::from_utf8_lossy(&data).to_string();
+        let data = self.read_incoming().await?;
+        self.process_received_data(data).await
+    }
+
+    /// Wait for the next chunk of raw bytes from the server
+    ///
+    /// Cancel safe, unlike `process_incoming_data`: the receive loop races
+    /// this against a timeout, then hands the bytes to
+    /// `process_received_data`.
+    pub async fn read_incoming(&mut self) -> Result<Vec<u8>> {
+        self.connection.read().await
+    }
+
+    /// Process bytes from `read_incoming`: telnet, MCCP, triggers, events
+    pub async fn process_received_data(&mut self, data: Vec<u8>) -> Result<()> {
+        // Decompress and strip telnet negotiation
+        let data = self.connection.process_received(data).await?;
+
+        // Store and publish GMCP packages received alongside the text
+        for message in self.connection.take_gmcp_messages() {
+            self.gmcp.lock().unwrap().update(&message.package, message.data.clone());
+            self.event_bus.publish(MudEvent::GmcpReceived {
+                world_id: self.connection.world().id,
+                package: message.package,
+                data: message.data,
+            })?;
+        }
+
+        // Mirror MSDP variables into the variable manager and publish them
+        let prefix = self.connection.world().msdp_variable_prefix.clone();
+        for (variable, value) in self.connection.take_msdp_updates() {
+            for (name, text) in value.flatten(&format!("{}{}", prefix, variable)) {
+                self.world_api.set_variable(&name, &text);
+                self.variable_manager.set_variable(name, text);
+            }
+            self.event_bus.publish(MudEvent::MsdpReceived {
+                world_id: self.connection.world().id,
+                variable,
+                value,
+            })?;
+        }
+
+        // Keep the latest MSSP status
+        if let Some(data) = self.connection.take_mssp() {
+            self.event_bus.publish(MudEvent::MsspReceived {
+                world_id: self.connection.world().id,
+                data: data.clone(),
+            })?;
+            self.mssp = Some(data);
+        }
+
+        // Flush partial lines ended by IAC GA / IAC EOR as prompts
+        let prompt_marks = self.connection.take_prompt_marks();
+        let prompts = self.split_prompts(&data, &prompt_marks);
+
+        // Nothing left to display once telnet negotiation is stripped
+        if data.is_empty() {
+            return self.process_prompts(prompts).await;
+        }
+
+        let text = self.connection.decode(&data);
+
+        // Strip MSP sound markers and play what they request
+        let (text, sounds) = self.connection.process_msp(&text);
+        for sound in sounds {
+            self.play_sound(sound)?;
+        }
+
+        // Strip MXP tags, keeping the styles and links they applied as spans
+        let MxpOutput { text, spans, links, .. } = self.connection.process_mxp(&text);
+        if text.is_empty() {
+            return self.process_prompts(prompts).await;
+        }
 
         debug!("Processing {} bytes of data", data.len());
 
@@ -407,6 +677,14 @@ impl Session {
         // Process highlights and get styled text segments
         let highlight_matches = self.highlight_manager.get_all_matches(&text)?;
 
+        if !spans.is_empty() || !links.is_empty() {
+            self.event_bus.publish(MudEvent::MxpStyled {
+                world_id: self.connection.world().id,
+                spans,
+                links,
+            })?;
+        }
+
         // Publish DataReceived event for frontend display (with highlight info)
         self.event_bus.publish(MudEvent::DataReceived {
             world_id: self.connection.world().id,
@@ -423,8 +701,76 @@ impl Session {
             })?;
         }
 
+        // Run line triggers on the received text
+        self.run_triggers(&text, false).await?;
+
+        self.process_prompts(prompts).await
+    }
+
+    /// Split flushed prompts out of received data
+    ///
+    /// `marks` are the offsets where IAC GA / IAC EOR arrived. The partial
+    /// line pending at each mark (which may have started in an earlier
+    /// read) is returned as a prompt.
+    fn split_prompts(&mut self, data: &[u8], marks: &[usize]) -> Vec<String> {
+        let mut prompts = Vec::new();
+        let mut start = 0;
+
+        for &mark in marks {
+            self.track_partial_line(&data[start..mark]);
+            let prompt = std::mem::take(&mut self.partial_line);
+            let prompt = self.connection.decode_complete(&prompt);
+            let mut prompt = self.connection.mxp_visible_text(&prompt).trim_end_matches('\r').to_string();
+            if self.connection.world().msp {
+                prompt = msp::extract(&prompt).0;
+            }
+            if !prompt.is_empty() {
+                prompts.push(prompt);
+            }
+            start = mark;
+        }
+
+        self.track_partial_line(&data[start..]);
+        prompts
+    }
+
+    /// Remember the text after the last newline (at most `MAX_PARTIAL_LINE` bytes)
+    fn track_partial_line(&mut self, data: &[u8]) {
+        match data.iter().rposition(|&b| b == b'\n') {
+            Some(pos) => self.partial_line = data[pos + 1..].to_vec(),
+            None => self.partial_line.extend_from_slice(data),
+        }
+        if let Some(excess) = self.partial_line.len().checked_sub(MAX_PARTIAL_LINE) {
+            self.partial_line.drain(..excess);
+        }
+    }
+
+    /// Publish prompts and run prompt-only triggers on them
+    async fn process_prompts(&mut self, prompts: Vec<String>) -> Result<()> {
+        for prompt in prompts {
+            debug!("Prompt received: {}", prompt);
+            self.event_bus.publish(MudEvent::PromptReceived {
+                world_id: self.connection.world().id,
+                prompt: prompt.clone(),
+            })?;
+
+            self.run_triggers(&prompt, true).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Match triggers against text and execute their actions
+    ///
+    /// With `prompt` set only prompt-only triggers are considered, otherwise
+    /// only line triggers.
+    async fn run_triggers(&mut self, text: &str, prompt: bool) -> Result<()> {
         // Find matching triggers
-        let matches = self.trigger_manager.find_matches(&text)?;
+        let matches = if prompt {
+            self.trigger_manager.find_prompt_matches(text)?
+        } else {
+            self.trigger_manager.find_matches(text)?
+        };
 
         if !matches.is_empty() {
             info!("Found {} matching trigger(s)", matches.len());
@@ -441,7 +787,7 @@ impl Session {
                     debug!("Executing trigger '{}'", trigger.name);
 
                     // Extract capture groups for script access
-                    let captures = trigger.extract_captures(&text)?;
+                    let captures = trigger.extract_captures(text)?;
 
                     // Check if trigger has ExecuteScript action
                     let script_opt = match &trigger.action {
@@ -483,11 +829,14 @@ impl Session {
                     Ok(result) => {
                         debug!("Trigger script executed successfully: {:?}", result);
 
+                        // Send any GMCP/MSDP messages queued by world.SendGMCP()/SendMSDP()
+                        self.send_queued_oob().await?;
+
                         // Send any commands queued by world.Send()
                         let queued_commands = self.world_api.drain_command_queue();
                         for cmd in queued_commands {
                             debug!("Sending queued command from trigger script: {}", cmd);
-                            Box::pin(self.process_command_internal(&cmd)).await?;
+                            Box::pin(self.process_command_internal(&cmd, CommandOrigin::Automation)).await?;
                         }
                     }
                     Err(e) => {
@@ -501,6 +850,12 @@ impl Session {
                 }
             }
 
+            // Play the trigger's sounds
+            let sounds = self.trigger_manager.get_trigger(trigger_id).map(|t| t.sounds()).unwrap_or_default();
+            for file in sounds {
+                self.play_sound(MspRequest::new(SoundKind::Sound, file))?;
+            }
+
             // Execute trigger and get commands (get trigger reference again)
             if let Some(trigger) = self.trigger_manager.get_trigger(trigger_id) {
                 match trigger.execute() {
@@ -515,7 +870,7 @@ impl Session {
 
                             // Send each command
                             for command in commands {
-                                if let Err(e) = self.connection.send_command(&command).await {
+                                if let Err(e) = self.connection.send_command_as(&command, CommandOrigin::Automation).await {
                                     error!("Failed to send trigger command '{}': {}", command, e);
                                     // Publish TriggerError event
                                     self.event_bus.publish(MudEvent::TriggerError {
@@ -543,6 +898,44 @@ impl Session {
         Ok(())
     }
 
+    /// Publish a sound for the frontend to play
+    ///
+    /// The file is resolved against the world's sounds directory; a file
+    /// that can't be found is reported with a SoundError event. `Off`
+    /// requests stop the sound or music instead.
+    fn play_sound(&self, sound: MspRequest) -> Result<()> {
+        let world_id = self.connection.world().id;
+
+        if sound.is_off() {
+            debug!("Stopping {:?}", sound.kind);
+            return self.event_bus.publish(MudEvent::StopSound { world_id, kind: sound.kind });
+        }
+
+        let resolved = match &self.connection.world().sounds_dir {
+            Some(dir) => msp::resolve(dir, &sound),
+            None => Err(MushError::FileNotFound(format!("{} (no sounds directory)", sound.file))),
+        };
+
+        match resolved {
+            Ok(path) => {
+                debug!("Playing {:?} {}", sound.kind, path.display());
+                self.event_bus.publish(MudEvent::PlaySound {
+                    world_id,
+                    path: path.display().to_string(),
+                    sound,
+                })
+            }
+            Err(e) => {
+                warn!("Cannot play sound '{}': {}", sound.file, e);
+                self.event_bus.publish(MudEvent::SoundError {
+                    world_id,
+                    file: sound.file,
+                    error: e.to_string(),
+                })
+            }
+        }
+    }
+
     /// Check if session is connected
     pub fn is_connected(&self) -> bool {
         self.connection.is_connected()
@@ -553,6 +946,97 @@ impl Session {
         self.connection.world()
     }
 
+    /// Check whether input is masked because the server is echoing (password entry)
+    pub fn is_input_masked(&self) -> bool {
+        self.connection.is_input_masked()
+    }
+
+    /// Set the pinned certificate fingerprint for the next connect
+    pub fn set_certificate_pin(&mut self, fingerprint: Option<String>) {
+        self.connection.set_certificate_pin(fingerprint);
+    }
+
+    /// Get how the server certificate was trusted (TLS worlds only)
+    pub fn certificate_trust(&self) -> Option<CertificateTrust> {
+        self.connection.certificate_trust()
+    }
+
+    /// Set the terminal size (columns, rows) reported to the server via NAWS
+    pub async fn set_window_size(&mut self, width: u16, height: u16) -> Result<()> {
+        self.connection.set_window_size(width, height).await
+    }
+
+    /// Get the terminal size (columns, rows)
+    pub fn window_size(&self) -> (u16, u16) {
+        self.connection.window_size()
+    }
+
+    /// Send a GMCP message to the server
+    pub async fn send_gmcp(&mut self, package: impl Into<String>, data: serde_json::Value) -> Result<()> {
+        self.connection.send_gmcp(&GmcpMessage::new(package, data)).await
+    }
+
+    /// Get the latest GMCP data for a package (or the whole tree for "")
+    pub fn get_gmcp(&self, package: &str) -> Option<serde_json::Value> {
+        self.gmcp.lock().unwrap().get(package).cloned()
+    }
+
+    /// Get the latest MSSP server status
+    pub fn mssp(&self) -> Option<&MsspData> {
+        self.mssp.as_ref()
+    }
+
+    /// Send queued commands the world's rate limit allows now
+    pub async fn flush_send_queue(&mut self) -> Result<()> {
+        if !self.connection.is_connected() {
+            return Ok(());
+        }
+        self.connection.flush_send_queue().await
+    }
+
+    /// Get the commands waiting in the send queue and its state
+    pub fn send_queue_status(&self) -> SendQueueStatus {
+        self.connection.send_queue().lock().unwrap().status()
+    }
+
+    /// Discard the commands waiting in the send queue, returning how many there were
+    pub fn clear_send_queue(&mut self) -> usize {
+        self.connection.send_queue().lock().unwrap().clear()
+    }
+
+    /// Pause or resume the send queue
+    ///
+    /// Resuming also resets the flood guard and sends what the rate limit allows.
+    pub async fn set_send_queue_paused(&mut self, paused: bool) -> Result<()> {
+        self.connection.send_queue().lock().unwrap().set_paused(paused);
+        self.flush_send_queue().await
+    }
+
+    /// Get MCCP traffic statistics for the current connection
+    pub fn get_compression_stats(&self) -> CompressionStats {
+        self.connection.get_compression_stats()
+    }
+
+    /// Send an MSDP command (LIST, REPORT, SEND or UNREPORT) to the server
+    pub async fn send_msdp(&mut self, command: MsdpCommand, values: &[String]) -> Result<()> {
+        self.connection.send_msdp(command, values).await
+    }
+
+    /// Send GMCP and MSDP messages queued by scripts
+    async fn send_queued_oob(&mut self) -> Result<()> {
+        for message in self.world_api.drain_gmcp_queue() {
+            if let Err(e) = self.connection.send_gmcp(&message).await {
+                error!("Failed to send GMCP '{}' from script: {}", message.package, e);
+            }
+        }
+        for (command, values) in self.world_api.drain_msdp_queue() {
+            if let Err(e) = self.connection.send_msdp(command, &values).await {
+                error!("Failed to send MSDP {} from script: {}", command, e);
+            }
+        }
+        Ok(())
+    }
+
     /// Get tab-completion matches for a partial word
     pub fn get_completions(&self, partial: &str) -> Vec<String> {
         self.tab_completion.get_completions(partial)
@@ -914,6 +1398,584 @@ mod tests {
         assert!(!trigger_matched, "Should not publish TriggerMatched event");
     }
 
+    #[tokio::test]
+    async fn test_session_prompt_triggers() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // Skip the IAC WILL NAWS offer sent on connect
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let _ = socket.write_all(b"Welcome!\r\n<42hp> \xff\xf9").await;
+
+                let mut buf = [0u8; 64];
+                if let Ok(n) = socket.read(&mut buf).await {
+                    let _ = tx.send(buf[..n].to_vec());
+                }
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+
+        let mut prompt_trigger = Trigger::new(
+            "Prompt HP",
+            r"^<(\d+)hp>",
+            TriggerAction::SendCommand("heal".to_string()),
+        )
+        .unwrap();
+        prompt_trigger.prompt_only = true;
+        session.add_trigger(prompt_trigger).unwrap();
+
+        session.process_incoming_data().await.unwrap();
+
+        // The prompt-only trigger fires on the flushed prompt
+        assert_eq!(rx.await.unwrap(), b"heal\r\n".to_vec());
+
+        let mut prompts = Vec::new();
+        while let Ok(event) = rx_events.try_recv() {
+            if let MudEvent::PromptReceived { prompt, .. } = event {
+                prompts.push(prompt);
+            }
+        }
+        assert_eq!(prompts, vec!["<42hp> ".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_session_mxp_text() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                // WILL MXP, a styled room name and a prompt with entities
+                let _ = socket
+                    .write_all(b"\xff\xfb\x5b\x1b[1z<color red>Town Square</color>\r\n&lt;42hp&gt; \xff\xf9")
+                    .await;
+
+                // IAC DO MXP, then the trigger's command
+                let mut buf = [0u8; 64];
+                let mut received = Vec::new();
+                while !received.ends_with(b"\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+        session
+            .add_trigger(Trigger::new("Room", r"^Town Square", TriggerAction::SendCommand("look".to_string())).unwrap())
+            .unwrap();
+
+        session.process_incoming_data().await.unwrap();
+
+        // Triggers see the visible text
+        assert_eq!(rx.await.unwrap(), b"\xff\xfd\x5blook\r\n".to_vec());
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx_events.try_recv() {
+            match event {
+                MudEvent::MxpStyled { spans, links, .. } => {
+                    events.push(format!("spans {:?} links {}", (spans[0].start, spans[0].end), links.len()))
+                }
+                MudEvent::DataReceived { text, .. } => events.push(format!("data {}", text)),
+                MudEvent::PromptReceived { prompt, .. } => events.push(format!("prompt {}", prompt)),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            events,
+            vec!["spans (0, 11) links 0", "data Town Square\r\n<42hp> ", "prompt <42hp> "]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_msp_sounds() {
+        let sounds_dir = std::env::temp_dir().join(format!("macmush-sounds-{}", fastrand::u64(..)));
+        std::fs::create_dir_all(&sounds_dir).unwrap();
+        std::fs::write(sounds_dir.join("thunder.wav"), b"RIFF").unwrap();
+        std::fs::write(sounds_dir.join("bell.wav"), b"RIFF").unwrap();
+
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                // WILL MSP, then markers for a present and a missing file
+                let _ = socket
+                    .write_all(b"\xff\xfb\x5a!!SOUND(thunder V=40)\r\nA storm!\r\n!!MUSIC(missing.mid)\r\n!!MUSIC(Off)")
+                    .await;
+
+                let mut reply = [0u8; 3];
+                let _ = socket.read_exact(&mut reply).await;
+                let _ = tx.send(reply);
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .sounds_dir(Some(sounds_dir.clone()))
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+        session
+            .add_trigger(Trigger::new("Storm", r"^A storm", TriggerAction::PlaySound("bell.wav".to_string())).unwrap())
+            .unwrap();
+
+        session.process_incoming_data().await.unwrap();
+        assert_eq!(rx.await.unwrap(), [255, 253, 90]);
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx_events.try_recv() {
+            match event {
+                MudEvent::PlaySound { path, sound, .. } => {
+                    assert!(path.starts_with(&sounds_dir.display().to_string()));
+                    events.push(format!("play {} {}", sound.file, sound.volume))
+                }
+                MudEvent::StopSound { kind, .. } => events.push(format!("stop {:?}", kind)),
+                MudEvent::SoundError { file, .. } => events.push(format!("error {}", file)),
+                MudEvent::DataReceived { text, .. } => events.push(format!("data {}", text)),
+                _ => {}
+            }
+        }
+        assert_eq!(
+            events,
+            vec![
+                "play thunder 40",
+                "error missing.mid",
+                "stop Music",
+                "data A storm!\r\n",
+                "play bell.wav 100",
+            ]
+        );
+
+        std::fs::remove_dir_all(&sounds_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_session_server_echo_masks_input() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                // Skip the IAC WILL NAWS offer sent on connect
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let _ = socket.write_all(b"Password: \xff\xfb\x01").await;
+
+                // IAC DO ECHO, then the password
+                let mut received = Vec::new();
+                let mut buf = [0u8; 64];
+                while !received.ends_with(b"\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+        session.process_incoming_data().await.unwrap();
+        assert!(session.is_input_masked());
+
+        session.send_command("hunter2").await.unwrap();
+        assert_eq!(rx.await.unwrap(), b"\xff\xfd\x01hunter2\r\n".to_vec());
+        assert_eq!(session.command_history_count(), 0);
+
+        let mut masked_events = Vec::new();
+        while let Ok(event) = rx_events.try_recv() {
+            match event {
+                MudEvent::InputMaskChanged { masked, .. } => masked_events.push(masked),
+                MudEvent::CommandSent { command, .. } => assert_ne!(command, "hunter2"),
+                _ => {}
+            }
+        }
+        assert_eq!(masked_events, vec![true]);
+    }
+
+    /// Helper: world that reconnects right away and logs in on connect
+    fn reconnecting_world(port: u16, max_attempts: u32) -> World {
+        use crate::core::ReconnectPolicy;
+
+        World::builder("Test MUD", "127.0.0.1", port)
+            .reconnect(ReconnectPolicy {
+                enabled: true,
+                max_attempts,
+                initial_delay_ms: 10,
+                jitter: 0.0,
+                ..ReconnectPolicy::default()
+            })
+            .connect_commands(vec!["connect bob secret".to_string()])
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_session_reconnect_after_drop() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for greeting in [None, Some(b"Welcome back!\r\n")] {
+                if let Ok((mut socket, _)) = listener.accept().await {
+                    // NAWS offer, then the login
+                    let mut received = Vec::new();
+                    let mut buf = [0u8; 64];
+                    while !received.ends_with(b"\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => received.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let _ = tx.send(received);
+
+                    // First connection drops right after login
+                    if let Some(greeting) = greeting {
+                        let _ = socket.write_all(greeting).await;
+                        tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+                    }
+                }
+            }
+        });
+
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+        let mut session = Session::new(reconnecting_world(port, 3), event_bus).unwrap();
+        session.set_variable("hp", "100");
+
+        session.start().await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), b"\xff\xfb\x1fconnect bob secret\r\n".to_vec());
+        assert!(session.process_incoming_data().await.is_err());
+
+        let delay = session.schedule_reconnect().unwrap().expect("Should schedule a reconnect");
+        assert_eq!(delay, Duration::from_millis(10));
+        tokio::time::sleep(delay).await;
+        session.reconnect().await.unwrap();
+
+        // Login re-run, automation state kept
+        assert_eq!(rx.recv().await.unwrap(), b"\xff\xfb\x1fconnect bob secret\r\n".to_vec());
+        session.process_incoming_data().await.unwrap();
+        assert_eq!(session.get_variable("hp"), Some("100".to_string()));
+
+        let mut reconnect_events = Vec::new();
+        while let Ok(event) = rx_events.try_recv() {
+            if matches!(event, MudEvent::Reconnecting { .. } | MudEvent::Reconnected { .. }) {
+                reconnect_events.push(event);
+            }
+        }
+        let world_id = session.world().id;
+        assert_eq!(
+            reconnect_events,
+            vec![
+                MudEvent::Reconnecting { world_id, attempt: 1, max_attempts: 3, delay_ms: 10 },
+                MudEvent::Reconnected { world_id, attempts: 1 },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_reconnect_gives_up_and_cancels() {
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+        let mut session = Session::new(reconnecting_world(4000, 1), event_bus).unwrap();
+
+        assert!(session.schedule_reconnect().unwrap().is_some());
+        assert!(session.schedule_reconnect().unwrap().is_none(), "Should give up after max attempts");
+        let mut gave_up = false;
+        while let Ok(event) = rx_events.try_recv() {
+            gave_up |= matches!(event, MudEvent::ConnectionError { .. });
+        }
+        assert!(gave_up);
+
+        // User disconnect cancels any pending retry
+        let cancellation = session.reconnect_cancellation();
+        session.stop().await.unwrap();
+        assert!(*cancellation.borrow());
+        assert!(session.schedule_reconnect().unwrap().is_none());
+        assert!(matches!(session.reconnect().await, Err(MushError::NotConnected)));
+    }
+
+    #[tokio::test]
+    async fn test_session_reconnect_cancelled_while_connecting() {
+        let (listener, port) = start_mock_server().await;
+        tokio::spawn(async move {
+            // Accept and hold the connection the abandoned attempt made
+            let _socket = listener.accept().await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        });
+
+        let mut session = Session::new(reconnecting_world(port, 3), Arc::new(EventBus::new())).unwrap();
+        assert!(session.schedule_reconnect().unwrap().is_some());
+
+        // The client is connected without the session; stop() lands meanwhile
+        let mut client = session.take_reconnect_client().await.unwrap();
+        let result = client.connect().await;
+        assert!(result.is_ok());
+        session.stop().await.unwrap();
+
+        assert!(matches!(session.finish_reconnect(client, result).await, Err(MushError::NotConnected)));
+        assert!(!session.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_session_anti_idle() {
+        use crate::core::AntiIdle;
+
+        for (command, expected) in [(None, b"\xff\xf1".to_vec()), (Some("score"), b"score\r\n".to_vec())] {
+            let (listener, port) = start_mock_server().await;
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                if let Ok((mut socket, _)) = listener.accept().await {
+                    let mut buf = [0u8; 64];
+                    while let Ok(n @ 1..) = socket.read(&mut buf).await {
+                        let _ = tx.send(buf[..n].to_vec());
+                    }
+                }
+            });
+
+            let world = World::builder("Test MUD", "127.0.0.1", port)
+                .anti_idle(AntiIdle {
+                    enabled: true,
+                    idle_minutes: 5,
+                    command: command.map(str::to_string),
+                })
+                .build()
+                .unwrap();
+            let event_bus = Arc::new(EventBus::new());
+            let mut rx_events = event_bus.subscribe();
+            let mut session = Session::new(world, event_bus).unwrap();
+            session.start().await.unwrap();
+            assert_eq!(rx.recv().await.unwrap(), b"\xff\xfb\x1f".to_vec());
+
+            tokio::time::pause();
+
+            // A user command restarts the idle clock
+            tokio::time::advance(Duration::from_secs(4 * 60)).await;
+            session.send_command("look").await.unwrap();
+            assert_eq!(rx.recv().await.unwrap(), b"look\r\n".to_vec());
+            tokio::time::advance(Duration::from_secs(4 * 60)).await;
+            session.process_anti_idle().await.unwrap();
+
+            tokio::time::advance(Duration::from_secs(60)).await;
+            session.process_anti_idle().await.unwrap();
+            assert_eq!(rx.recv().await.unwrap(), expected);
+
+            // Fires once per idle period
+            session.process_anti_idle().await.unwrap();
+            tokio::time::resume();
+
+            let mut fired = Vec::new();
+            while let Ok(event) = rx_events.try_recv() {
+                if let MudEvent::AntiIdleSent { command, .. } = event {
+                    fired.push(command);
+                }
+            }
+            assert_eq!(fired, vec![command.map(str::to_string)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_session_anti_idle_with_paused_queue() {
+        use crate::core::{AntiIdle, CommandOrigin, QueuedCommand};
+
+        let (listener, port) = start_mock_server().await;
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 64];
+                while let Ok(1..) = socket.read(&mut buf).await {}
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .anti_idle(AntiIdle {
+                enabled: true,
+                idle_minutes: 5,
+                command: Some("score".to_string()),
+            })
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+        session.set_send_queue_paused(true).await.unwrap();
+
+        tokio::time::pause();
+        tokio::time::advance(Duration::from_secs(5 * 60)).await;
+        for _ in 0..3 {
+            session.process_anti_idle().await.unwrap();
+            tokio::time::advance(Duration::from_millis(110)).await;
+        }
+        tokio::time::resume();
+
+        // Queued once, not once per check while the queue holds it
+        let queued = QueuedCommand { command: "score".to_string(), origin: CommandOrigin::Automation };
+        assert_eq!(session.send_queue_status().commands, vec![queued]);
+        let fired = std::iter::from_fn(|| rx_events.try_recv().ok())
+            .filter(|event| matches!(event, MudEvent::AntiIdleSent { .. }))
+            .count();
+        assert_eq!(fired, 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_rate_limited_send_queue() {
+        use crate::core::{RateLimit, ThrottleReason};
+
+        let (listener, port) = start_mock_server().await;
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut received = Vec::new();
+                let mut buf = [0u8; 64];
+                while let Ok(n @ 1..) = socket.read(&mut buf).await {
+                    received.extend_from_slice(&buf[..n]);
+                    // Skip the NAWS offer, then report each line
+                    if received.starts_with(b"\xff\xfb\x1f") {
+                        received.drain(..3);
+                    }
+                    while let Some(end) = received.windows(2).position(|w| w == b"\r\n") {
+                        let line: Vec<u8> = received.drain(..end + 2).collect();
+                        let _ = tx.send(String::from_utf8_lossy(&line[..end]).to_string());
+                    }
+                }
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .rate_limit(RateLimit {
+                enabled: true,
+                commands_per_second: 2.0,
+                burst: 2,
+                ..RateLimit::default()
+            })
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx_events = event_bus.subscribe();
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+        tokio::time::pause();
+
+        // Speedwalk burst: two go out, the rest wait
+        session.send_command("4n").await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "north");
+        assert_eq!(rx.recv().await.unwrap(), "north");
+        assert_eq!(session.send_queue_status().commands.len(), 2);
+
+        session.set_send_queue_paused(true).await.unwrap();
+        tokio::time::advance(Duration::from_secs(1)).await;
+        session.flush_send_queue().await.unwrap();
+        assert_eq!(session.send_queue_status().commands.len(), 2, "Paused queue holds commands");
+
+        session.set_send_queue_paused(false).await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), "north");
+        assert_eq!(rx.recv().await.unwrap(), "north");
+        assert!(session.send_queue_status().commands.is_empty());
+        tokio::time::resume();
+
+        let mut throttled = Vec::new();
+        while let Ok(event) = rx_events.try_recv() {
+            if let MudEvent::SendThrottled { queued, reason, .. } = event {
+                throttled.push((queued, reason));
+            }
+        }
+        assert_eq!(throttled, vec![(1, ThrottleReason::RateLimit), (2, ThrottleReason::Paused)]);
+    }
+
+    #[tokio::test]
+    async fn test_session_prompt_spans_reads() {
+        let world = World::new("Test MUD", "mud.example.com", 4000).unwrap();
+        let mut session = Session::new(world, Arc::new(EventBus::new())).unwrap();
+
+        assert!(session.split_prompts(b"line one\r\n<10", &[]).is_empty());
+        assert_eq!(session.split_prompts(b"0hp> ", &[5]), vec!["<100hp> ".to_string()]);
+
+        // GA right after a complete line is not a prompt
+        assert!(session.split_prompts(b"done\r\n", &[6]).is_empty());
+
+        // Without newlines only the newest text is kept
+        let flood = vec![b'x'; MAX_PARTIAL_LINE];
+        assert!(session.split_prompts(&flood, &[]).is_empty());
+        assert!(session.split_prompts(&flood, &[]).is_empty());
+        let prompts = session.split_prompts(b"end> ", &[5]);
+        assert_eq!(prompts[0].len(), MAX_PARTIAL_LINE);
+        assert!(prompts[0].ends_with("xend> "));
+    }
+
+    #[tokio::test]
+    async fn test_session_msdp_mirrored_into_variables() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut msg = vec![255, 251, 69, 255, 250, 69];
+                msg.extend_from_slice(b"\x01HEALTH\x02100\x01ROOM\x02\x03\x01VNUM\x026008\x04");
+                msg.extend_from_slice(&[255, 240]);
+                let _ = socket.write_all(&msg).await;
+
+                let mut buf = [0u8; 64];
+                let _ = socket.read(&mut buf).await;
+            }
+        });
+
+        let world = World::builder("Test MUD", "127.0.0.1", port)
+            .msdp_variable_prefix("m_")
+            .build()
+            .unwrap();
+        let event_bus = Arc::new(EventBus::new());
+        let mut rx = event_bus.subscribe();
+
+        let mut session = Session::new(world, event_bus).unwrap();
+        session.start().await.unwrap();
+        session.process_incoming_data().await.unwrap();
+
+        assert_eq!(session.get_variable("m_HEALTH"), Some("100".to_string()));
+        assert_eq!(session.get_variable("m_ROOM.VNUM"), Some("6008".to_string()));
+
+        let mut variables = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            assert!(!matches!(event, MudEvent::DataReceived { .. }), "Negotiation only, no text");
+            if let MudEvent::MsdpReceived { variable, .. } = event {
+                variables.push(variable);
+            }
+        }
+        assert_eq!(variables, vec!["HEALTH".to_string(), "ROOM".to_string()]);
+    }
+
     #[tokio::test]
     async fn test_session_alias_matching() {
         let (listener, port) = start_mock_server().await;
@@ -964,6 +2026,49 @@ mod tests {
         assert!(alias_executed, "Should publish AliasExecuted event");
     }
 
+    #[tokio::test]
+    async fn test_session_link_command() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut offer = [0u8; 3];
+                let _ = socket.read_exact(&mut offer).await;
+
+                let mut buf = [0u8; 64];
+                let mut received = Vec::new();
+                while !received.ends_with(b"get gold\r\n") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::new("Test MUD", "127.0.0.1", port).unwrap();
+        let mut session = Session::new(world, Arc::new(EventBus::new())).unwrap();
+        session.start().await.unwrap();
+        session
+            .add_alias(
+                Alias::new(
+                    "Get Gold",
+                    "^gg$",
+                    crate::automation::aliases::AliasAction::SendCommand("get gold".to_string()),
+                )
+                .unwrap(),
+            )
+            .unwrap();
+
+        // Link commands expand speedwalks and aliases but stay out of the history
+        session.send_link_command("2n").await.unwrap();
+        session.send_link_command("gg").await.unwrap();
+        assert_eq!(rx.await.unwrap(), b"north\r\nnorth\r\nget gold\r\n".to_vec());
+        assert_eq!(session.command_history_count(), 0);
+    }
+
     #[tokio::test]
     async fn test_session_alias_with_wildcards() {
         let (listener, port) = start_mock_server().await;
@@ -4,7 +4,10 @@
 /// including connection details, automation, and preferences.
 
 use crate::error::{MushError, Result};
+use crate::network::{EnvironOverride, ProxyConfig, TextEncoding};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
@@ -38,12 +41,278 @@ pub struct World {
     /// Enable TLS/SSL
     #[serde(default)]
     pub use_tls: bool,
+
+    /// Line terminator appended to outgoing commands
+    #[serde(default)]
+    pub line_ending: LineEnding,
+
+    /// Report 256-colour support to the server (MTTS)
+    #[serde(default = "default_true")]
+    pub colors_256: bool,
+
+    /// Report 24-bit truecolor support to the server (MTTS)
+    #[serde(default = "default_true")]
+    pub truecolor: bool,
+
+    /// Accept MCCP compression offered by the server
+    #[serde(default = "default_true")]
+    pub mccp: bool,
+
+    /// GMCP modules announced with Core.Supports.Set
+    #[serde(default = "default_gmcp_modules")]
+    pub gmcp_modules: Vec<String>,
+
+    /// Prefix for MSDP variables mirrored into the variable manager
+    #[serde(default = "default_msdp_variable_prefix")]
+    pub msdp_variable_prefix: String,
+
+    /// Text encoding used on the wire
+    #[serde(default)]
+    pub encoding: TextEncoding,
+
+    /// Client certificate presented for mutual TLS
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_certificate: Option<ClientCertificate>,
+
+    /// Automatic reconnect after the connection drops
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+
+    /// Commands sent after every successful connect (e.g. to log in)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connect_commands: Vec<String>,
+
+    /// Proxy the connection is tunnelled through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<ProxyConfig>,
+
+    /// How the telnet stream reaches the server (raw TCP or WebSocket)
+    #[serde(default)]
+    pub transport: Transport,
+
+    /// OS-level TCP keepalive probes
+    #[serde(default)]
+    pub tcp_keepalive: TcpKeepalive,
+
+    /// Anti-idle command (or NOP) after a period without commands
+    #[serde(default)]
+    pub anti_idle: AntiIdle,
+
+    /// Rate limit and flood guard for outgoing commands
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+
+    /// Accept MXP offered by the server and render its tags
+    #[serde(default = "default_true")]
+    pub mxp: bool,
+
+    /// Accept MSP offered by the server and play the sounds it requests
+    #[serde(default = "default_true")]
+    pub msp: bool,
+
+    /// Directory MSP and trigger sounds are looked up in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sounds_dir: Option<PathBuf>,
+
+    /// Overrides for the variables reported via NEW-ENVIRON (MNES); a variable without a value is never sent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub new_environ: Vec<EnvironOverride>,
 }
 
 fn default_timeout() -> u64 {
     30
 }
 
+fn default_true() -> bool {
+    true
+}
+
+pub(crate) fn default_gmcp_modules() -> Vec<String> {
+    ["Char 1", "Char.Skills 1", "Char.Items 1", "Room 1", "Comm.Channel 1"]
+        .iter()
+        .map(|m| m.to_string())
+        .collect()
+}
+
+pub(crate) fn default_msdp_variable_prefix() -> String {
+    "msdp_".to_string()
+}
+
+/// PEM certificate chain and private key for client certificate authentication
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClientCertificate {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Automatic reconnect with exponential backoff
+///
+/// Attempt `n` (starting at 1) waits `initial_delay_ms * backoff_factor^(n-1)`,
+/// capped at `max_delay_ms`, then randomly adjusted by up to `jitter`
+/// (a fraction, so 0.25 means ±25%) to keep many clients from retrying in step.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ReconnectPolicy {
+    pub enabled: bool,
+    pub max_attempts: u32,
+    pub initial_delay_ms: u64,
+    pub backoff_factor: f64,
+    pub max_delay_ms: u64,
+    pub jitter: f64,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_attempts: 10,
+            initial_delay_ms: 2_000,
+            backoff_factor: 2.0,
+            max_delay_ms: 120_000,
+            jitter: 0.25,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Delay before attempt `attempt` (1-based), without jitter
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay_ms = self.initial_delay_ms as f64 * self.backoff_factor.powi(exponent);
+        Duration::from_millis(delay_ms.min(self.max_delay_ms as f64) as u64)
+    }
+
+    /// Delay before attempt `attempt` (1-based), with jitter applied
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let spread = self.jitter * (2.0 * fastrand::f64() - 1.0);
+        self.base_delay(attempt).mul_f64(1.0 + spread)
+    }
+}
+
+/// OS-level TCP keepalive on the socket
+///
+/// Probes an otherwise idle connection so NATs and firewalls keep the flow
+/// open: the first probe after `idle_secs` of silence, then every
+/// `interval_secs`. Applies to our own socket, so with a proxy it covers
+/// the hop to the proxy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct TcpKeepalive {
+    pub enabled: bool,
+    pub idle_secs: u64,
+    pub interval_secs: u64,
+}
+
+impl Default for TcpKeepalive {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_secs: 60,
+            interval_secs: 15,
+        }
+    }
+}
+
+/// Anti-idle: keep the server from disconnecting an idle player
+///
+/// After `idle_minutes` without a command sent, sends `command`, or a
+/// telnet NOP when no command is set.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct AntiIdle {
+    pub enabled: bool,
+    pub idle_minutes: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+impl Default for AntiIdle {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_minutes: 10,
+            command: None,
+        }
+    }
+}
+
+impl AntiIdle {
+    /// Time without commands after which anti-idle fires
+    pub fn idle_time(&self) -> Duration {
+        Duration::from_secs(u64::from(self.idle_minutes) * 60)
+    }
+}
+
+/// Rate limit and flood guard for outgoing commands
+///
+/// With `enabled` set, up to `burst` commands go out back to back, after
+/// which commands are released at `commands_per_second`. Independently,
+/// more than `flood_threshold` automation commands (triggers, timers,
+/// scripts) within one second trip the flood guard; 0 disables it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct RateLimit {
+    pub enabled: bool,
+    pub commands_per_second: f64,
+    pub burst: u32,
+    pub flood_threshold: u32,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            commands_per_second: 4.0,
+            burst: 10,
+            flood_threshold: 0,
+        }
+    }
+}
+
+/// How the telnet byte stream reaches the server
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Transport {
+    /// Raw TCP (the classic telnet port)
+    #[default]
+    Tcp,
+    /// WebSocket at `path`: ws://, or wss:// when `use_tls` is set
+    WebSocket { path: String },
+}
+
+impl Transport {
+    /// WebSocket path, if this is a WebSocket transport
+    pub fn websocket_path(&self) -> Option<&str> {
+        match self {
+            Transport::Tcp => None,
+            Transport::WebSocket { path } => Some(path),
+        }
+    }
+}
+
+/// Line terminator for commands sent to the server
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// CR LF (telnet standard, RFC 854)
+    #[default]
+    CrLf,
+    /// Bare LF
+    Lf,
+    /// Bare CR
+    Cr,
+}
+
+impl LineEnding {
+    /// Get the bytes written at the end of each line
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Lf => b"\n",
+            LineEnding::Cr => b"\r",
+        }
+    }
+}
+
 impl World {
     /// Create a new World with required fields
     ///
@@ -52,6 +321,7 @@ impl World {
     /// - 30-second connection timeout
     /// - No auto-connect
     /// - No TLS/SSL encryption
+    /// - CR LF line endings
     ///
     /// # Arguments
     /// * `name` - User-friendly world name (cannot be empty/whitespace)
@@ -84,6 +354,25 @@ impl World {
             auto_connect: false,
             timeout_secs: 30,
             use_tls: false,
+            line_ending: LineEnding::default(),
+            colors_256: true,
+            truecolor: true,
+            mccp: true,
+            gmcp_modules: default_gmcp_modules(),
+            msdp_variable_prefix: default_msdp_variable_prefix(),
+            encoding: TextEncoding::default(),
+            client_certificate: None,
+            reconnect: ReconnectPolicy::default(),
+            connect_commands: Vec::new(),
+            proxy: None,
+            transport: Transport::default(),
+            tcp_keepalive: TcpKeepalive::default(),
+            anti_idle: AntiIdle::default(),
+            rate_limit: RateLimit::default(),
+            mxp: true,
+            msp: true,
+            sounds_dir: None,
+            new_environ: Vec::new(),
         };
 
         world.validate()?;
@@ -152,6 +441,81 @@ impl World {
             return Err(MushError::InvalidPort(self.port));
         }
 
+        // Validate reconnect policy
+        if self.reconnect.backoff_factor.is_nan() || self.reconnect.backoff_factor < 1.0 {
+            warn!("Validation failed: reconnect backoff factor {}", self.reconnect.backoff_factor);
+            return Err(MushError::ValidationError {
+                field: "reconnect.backoff_factor".to_string(),
+                reason: "Backoff factor must be at least 1.0".to_string(),
+            });
+        }
+        if !(0.0..=1.0).contains(&self.reconnect.jitter) {
+            warn!("Validation failed: reconnect jitter {}", self.reconnect.jitter);
+            return Err(MushError::ValidationError {
+                field: "reconnect.jitter".to_string(),
+                reason: "Jitter must be between 0.0 and 1.0".to_string(),
+            });
+        }
+
+        // Validate keepalive settings
+        if self.tcp_keepalive.enabled && (self.tcp_keepalive.idle_secs == 0 || self.tcp_keepalive.interval_secs == 0) {
+            warn!("Validation failed: TCP keepalive times must be positive");
+            return Err(MushError::ValidationError {
+                field: "tcp_keepalive".to_string(),
+                reason: "Keepalive idle time and interval must be at least 1 second".to_string(),
+            });
+        }
+        if self.anti_idle.enabled && self.anti_idle.idle_minutes == 0 {
+            warn!("Validation failed: anti-idle minutes must be positive");
+            return Err(MushError::ValidationError {
+                field: "anti_idle.idle_minutes".to_string(),
+                reason: "Anti-idle interval must be at least 1 minute".to_string(),
+            });
+        }
+
+        // Validate rate limit
+        let rate = self.rate_limit.commands_per_second;
+        if self.rate_limit.enabled && (rate.is_nan() || rate <= 0.0 || self.rate_limit.burst == 0) {
+            warn!("Validation failed: rate limit {:?}", self.rate_limit);
+            return Err(MushError::ValidationError {
+                field: "rate_limit".to_string(),
+                reason: "Rate must be positive and burst at least 1".to_string(),
+            });
+        }
+
+        // Validate WebSocket path
+        if let Some(path) = self.transport.websocket_path() {
+            if !path.starts_with('/') || path.chars().any(char::is_whitespace) {
+                warn!("Validation failed: invalid WebSocket path '{}'", path);
+                return Err(MushError::ValidationError {
+                    field: "transport".to_string(),
+                    reason: "WebSocket path must start with '/' and contain no spaces".to_string(),
+                });
+            }
+        }
+
+        // Validate proxy
+        if let Some(proxy) = &self.proxy {
+            if proxy.host.trim().is_empty() || proxy.port == 0 {
+                warn!("Validation failed: invalid proxy {}", proxy.url());
+                return Err(MushError::ValidationError {
+                    field: "proxy".to_string(),
+                    reason: "Proxy needs a host and a non-zero port".to_string(),
+                });
+            }
+        }
+
+        // Validate client certificate paths (the files are read when connecting)
+        if let Some(cert) = &self.client_certificate {
+            if cert.cert_path.as_os_str().is_empty() || cert.key_path.as_os_str().is_empty() {
+                warn!("Validation failed: incomplete client certificate");
+                return Err(MushError::ValidationError {
+                    field: "client_certificate".to_string(),
+                    reason: "Both a certificate and a private key file are required".to_string(),
+                });
+            }
+        }
+
         debug!("Validation passed for world '{}'", self.name);
         Ok(())
     }
@@ -225,6 +589,25 @@ pub struct WorldBuilder {
     auto_connect: bool,
     timeout_secs: u64,
     use_tls: bool,
+    line_ending: LineEnding,
+    colors_256: bool,
+    truecolor: bool,
+    mccp: bool,
+    gmcp_modules: Vec<String>,
+    msdp_variable_prefix: String,
+    encoding: TextEncoding,
+    client_certificate: Option<ClientCertificate>,
+    reconnect: ReconnectPolicy,
+    connect_commands: Vec<String>,
+    proxy: Option<ProxyConfig>,
+    transport: Transport,
+    tcp_keepalive: TcpKeepalive,
+    anti_idle: AntiIdle,
+    rate_limit: RateLimit,
+    mxp: bool,
+    msp: bool,
+    sounds_dir: Option<PathBuf>,
+    new_environ: Vec<EnvironOverride>,
 }
 
 impl WorldBuilder {
@@ -238,6 +621,25 @@ impl WorldBuilder {
             auto_connect: false,
             timeout_secs: 30,
             use_tls: false,
+            line_ending: LineEnding::default(),
+            colors_256: true,
+            truecolor: true,
+            mccp: true,
+            gmcp_modules: default_gmcp_modules(),
+            msdp_variable_prefix: default_msdp_variable_prefix(),
+            encoding: TextEncoding::default(),
+            client_certificate: None,
+            reconnect: ReconnectPolicy::default(),
+            connect_commands: Vec::new(),
+            proxy: None,
+            transport: Transport::default(),
+            tcp_keepalive: TcpKeepalive::default(),
+            anti_idle: AntiIdle::default(),
+            rate_limit: RateLimit::default(),
+            mxp: true,
+            msp: true,
+            sounds_dir: None,
+            new_environ: Vec::new(),
         }
     }
 
@@ -266,6 +668,101 @@ impl WorldBuilder {
         self
     }
 
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    pub fn colors_256(mut self, colors_256: bool) -> Self {
+        self.colors_256 = colors_256;
+        self
+    }
+
+    pub fn truecolor(mut self, truecolor: bool) -> Self {
+        self.truecolor = truecolor;
+        self
+    }
+
+    pub fn mccp(mut self, mccp: bool) -> Self {
+        self.mccp = mccp;
+        self
+    }
+
+    pub fn gmcp_modules(mut self, gmcp_modules: Vec<String>) -> Self {
+        self.gmcp_modules = gmcp_modules;
+        self
+    }
+
+    pub fn msdp_variable_prefix(mut self, msdp_variable_prefix: impl Into<String>) -> Self {
+        self.msdp_variable_prefix = msdp_variable_prefix.into();
+        self
+    }
+
+    pub fn encoding(mut self, encoding: TextEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn client_certificate(mut self, client_certificate: Option<ClientCertificate>) -> Self {
+        self.client_certificate = client_certificate;
+        self
+    }
+
+    pub fn reconnect(mut self, reconnect: ReconnectPolicy) -> Self {
+        self.reconnect = reconnect;
+        self
+    }
+
+    pub fn connect_commands(mut self, connect_commands: Vec<String>) -> Self {
+        self.connect_commands = connect_commands;
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    pub fn transport(mut self, transport: Transport) -> Self {
+        self.transport = transport;
+        self
+    }
+
+    pub fn tcp_keepalive(mut self, tcp_keepalive: TcpKeepalive) -> Self {
+        self.tcp_keepalive = tcp_keepalive;
+        self
+    }
+
+    pub fn anti_idle(mut self, anti_idle: AntiIdle) -> Self {
+        self.anti_idle = anti_idle;
+        self
+    }
+
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = rate_limit;
+        self
+    }
+
+    pub fn mxp(mut self, mxp: bool) -> Self {
+        self.mxp = mxp;
+        self
+    }
+
+    pub fn msp(mut self, msp: bool) -> Self {
+        self.msp = msp;
+        self
+    }
+
+    pub fn sounds_dir(mut self, sounds_dir: Option<PathBuf>) -> Self {
+        self.sounds_dir = sounds_dir;
+        self
+    }
+
+    pub fn new_environ(mut self, new_environ: Vec<EnvironOverride>) -> Self {
+        self.new_environ = new_environ;
+        self
+    }
+
     pub fn build(self) -> Result<World> {
         let world = World {
             id: self.id,
@@ -276,6 +773,25 @@ impl WorldBuilder {
             auto_connect: self.auto_connect,
             timeout_secs: self.timeout_secs,
             use_tls: self.use_tls,
+            line_ending: self.line_ending,
+            colors_256: self.colors_256,
+            truecolor: self.truecolor,
+            mccp: self.mccp,
+            gmcp_modules: self.gmcp_modules,
+            msdp_variable_prefix: self.msdp_variable_prefix,
+            encoding: self.encoding,
+            client_certificate: self.client_certificate,
+            reconnect: self.reconnect,
+            connect_commands: self.connect_commands,
+            proxy: self.proxy,
+            transport: self.transport,
+            tcp_keepalive: self.tcp_keepalive,
+            anti_idle: self.anti_idle,
+            rate_limit: self.rate_limit,
+            mxp: self.mxp,
+            msp: self.msp,
+            sounds_dir: self.sounds_dir,
+            new_environ: self.new_environ,
         };
 
         world.validate()?;
@@ -299,6 +815,7 @@ mod tests {
         assert_eq!(world.timeout_secs, 30);
         assert!(!world.auto_connect);
         assert!(!world.use_tls);
+        assert_eq!(world.line_ending, LineEnding::CrLf);
     }
 
     #[test]
@@ -435,6 +952,7 @@ mod tests {
             .description("Test description")
             .auto_connect(true)
             .use_tls(true)
+            .new_environ(vec![EnvironOverride::set("IPADDRESS", "10.0.0.1"), EnvironOverride::suppress("CLIENT_NAME")])
             .build()
             .unwrap();
 
@@ -445,6 +963,149 @@ mod tests {
         assert_eq!(deserialized.description, world.description);
         assert_eq!(deserialized.auto_connect, world.auto_connect);
         assert_eq!(deserialized.use_tls, world.use_tls);
+        assert_eq!(deserialized.new_environ, world.new_environ);
+    }
+
+    #[test]
+    fn test_line_ending_roundtrip() {
+        let world = World::builder("Test MUD", "mud.example.com", 4000)
+            .line_ending(LineEnding::Lf)
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&world).unwrap();
+        let deserialized: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.line_ending, LineEnding::Lf);
+
+        let xml = world.to_xml().unwrap();
+        let deserialized = World::from_xml(&xml).unwrap();
+        assert_eq!(deserialized.line_ending, LineEnding::Lf);
+        assert_eq!(LineEnding::Cr.as_bytes(), b"\r");
+    }
+
+    #[test]
+    fn test_encoding_roundtrip() {
+        let world = World::builder("Test MUD", "mud.example.com", 4000)
+            .encoding(TextEncoding::Cp437)
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&world).unwrap();
+        let deserialized: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.encoding, TextEncoding::Cp437);
+
+        let xml = world.to_xml().unwrap();
+        let deserialized = World::from_xml(&xml).unwrap();
+        assert_eq!(deserialized.encoding, TextEncoding::Cp437);
+    }
+
+    #[test]
+    fn test_client_certificate() {
+        let cert = ClientCertificate {
+            cert_path: PathBuf::from("/certs/builder.pem"),
+            key_path: PathBuf::from("/certs/builder.key"),
+        };
+        let world = World::builder("Builder Port", "mud.example.com", 4443)
+            .use_tls(true)
+            .client_certificate(Some(cert.clone()))
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&world).unwrap();
+        let deserialized: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.client_certificate, Some(cert.clone()));
+
+        let result = World::builder("Builder Port", "mud.example.com", 4443)
+            .client_certificate(Some(ClientCertificate {
+                key_path: PathBuf::new(),
+                ..cert
+            }))
+            .build();
+        assert!(matches!(result, Err(MushError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_transport_roundtrip() {
+        let world = World::builder("Web MUD", "mud.example.com", 4443)
+            .use_tls(true)
+            .transport(Transport::WebSocket { path: "/ws".to_string() })
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&world).unwrap();
+        assert!(json.contains(r#""transport":{"type":"websocket","path":"/ws"}"#));
+        let deserialized: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.transport.websocket_path(), Some("/ws"));
+
+        let result = World::builder("Web MUD", "mud.example.com", 4443)
+            .transport(Transport::WebSocket { path: "ws".to_string() })
+            .build();
+        assert!(matches!(result, Err(MushError::ValidationError { .. })));
+        assert_eq!(World::new("Test MUD", "mud.example.com", 4000).unwrap().transport, Transport::Tcp);
+    }
+
+    #[test]
+    fn test_proxy_roundtrip() {
+        let proxy = ProxyConfig::socks5("proxy.corp.example", 1080).credentials("alice", "s3cret");
+        let world = World::builder("Test MUD", "mud.example.com", 4000)
+            .proxy(Some(proxy.clone()))
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&world).unwrap();
+        assert!(json.contains(r#""kind":"socks5""#));
+        let deserialized: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.proxy, Some(proxy));
+
+        let result = World::builder("Test MUD", "mud.example.com", 4000)
+            .proxy(Some(ProxyConfig::http("", 3128)))
+            .build();
+        assert!(matches!(result, Err(MushError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_reconnect_policy_delays() {
+        let policy = ReconnectPolicy {
+            enabled: true,
+            initial_delay_ms: 1_000,
+            backoff_factor: 2.0,
+            max_delay_ms: 5_000,
+            jitter: 0.5,
+            ..ReconnectPolicy::default()
+        };
+
+        assert_eq!(policy.base_delay(1), Duration::from_secs(1));
+        assert_eq!(policy.base_delay(2), Duration::from_secs(2));
+        assert_eq!(policy.base_delay(3), Duration::from_secs(4));
+        assert_eq!(policy.base_delay(4), Duration::from_secs(5));
+        assert_eq!(policy.base_delay(u32::MAX), Duration::from_secs(5));
+
+        for _ in 0..100 {
+            let delay = policy.delay(2);
+            assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(3), "{:?}", delay);
+        }
+
+        let result = World::builder("Test MUD", "mud.example.com", 4000)
+            .reconnect(ReconnectPolicy { jitter: 1.5, ..policy })
+            .build();
+        assert!(matches!(result, Err(MushError::ValidationError { .. })));
+    }
+
+    #[test]
+    fn test_keepalive_and_anti_idle_validation() {
+        let anti_idle: AntiIdle = serde_json::from_str(r#"{"enabled": true, "idle_minutes": 15}"#).unwrap();
+        assert_eq!(anti_idle.command, None);
+        assert_eq!(anti_idle.idle_time(), Duration::from_secs(15 * 60));
+
+        let result = World::builder("Test MUD", "mud.example.com", 4000)
+            .anti_idle(AntiIdle { idle_minutes: 0, ..anti_idle })
+            .build();
+        assert!(matches!(result, Err(MushError::ValidationError { .. })));
+
+        let result = World::builder("Test MUD", "mud.example.com", 4000)
+            .tcp_keepalive(TcpKeepalive { enabled: true, interval_secs: 0, ..TcpKeepalive::default() })
+            .build();
+        assert!(matches!(result, Err(MushError::ValidationError { .. })));
     }
 
     #[test]
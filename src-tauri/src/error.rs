@@ -31,9 +31,42 @@ pub enum MushError {
     #[error("Not connected to server")]
     NotConnected,
 
+    #[error("Proxy authentication failed for {proxy}: {reason}")]
+    ProxyAuthFailed {
+        proxy: String,
+        reason: String,
+    },
+
+    #[error("Proxy {proxy} could not connect to {target}: {reason}")]
+    ProxyConnectFailed {
+        proxy: String,
+        target: String,
+        reason: String,
+    },
+
     #[error("TLS handshake failed: {0}")]
     TlsError(String),
 
+    #[error("Certificate for {host}:{port} has changed (pinned {expected}, server sent {actual})")]
+    CertificateChanged {
+        host: String,
+        port: u16,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Client certificate {path}: {reason}")]
+    ClientCertificateError {
+        path: String,
+        reason: String,
+    },
+
+    #[error("WebSocket error: {0}")]
+    WebSocketError(String),
+
+    #[error("Server does not support {0}")]
+    ProtocolNotSupported(String),
+
     #[error("Compression error: {0}")]
     CompressionError(String),
 
@@ -47,6 +47,9 @@ pub fn run() {
             connect_to_world,
             disconnect,
             send_command,
+            send_mxp_link,
+            read_sound_file,
+            set_window_size,
             add_trigger,
             get_connection_status,
             start_logging,
@@ -63,6 +66,15 @@ pub fn run() {
             create_world,
             list_worlds,
             get_world,
+            probe_world,
+            get_mssp_status,
+            get_compression_stats,
+            get_send_queue,
+            clear_send_queue,
+            set_send_queue_paused,
+            list_certificate_pins,
+            accept_certificate_pin,
+            revoke_certificate_pin,
             update_world,
             delete_world,
             create_timer,
@@ -0,0 +1,188 @@
+/// CHARSET (option 42) negotiation, RFC 2066
+///
+/// Either side may send a REQUEST listing acceptable character sets once
+/// the option is enabled:
+///
+/// `IAC SB CHARSET REQUEST ";UTF-8;ISO-8859-1" IAC SE`
+///
+/// and the other side answers ACCEPTED with one of them, or REJECTED.
+/// Translation tables (TTABLE-*) are not supported and are rejected.
+///
+/// References:
+/// - https://www.rfc-editor.org/rfc/rfc2066
+/// - https://tintin.mudhalla.net/protocols/charset/
+
+use crate::network::codec::TextEncoding;
+use tracing::debug;
+
+/// CHARSET telnet option number
+pub const TELOPT_CHARSET: u8 = 42;
+
+/// CHARSET subnegotiation commands
+pub const CHARSET_REQUEST: u8 = 1;
+pub const CHARSET_ACCEPTED: u8 = 2;
+pub const CHARSET_REJECTED: u8 = 3;
+pub const CHARSET_TTABLE_IS: u8 = 4;
+pub const CHARSET_TTABLE_REJECTED: u8 = 5;
+
+/// Prefix of a REQUEST that offers translation tables
+const TTABLE_PREFIX: &[u8] = b"[TTABLE]";
+
+/// Separator used in our own requests
+const SEPARATOR: u8 = b';';
+
+/// Result of handling a CHARSET subnegotiation
+#[derive(Debug, Clone, PartialEq)]
+pub enum CharsetOutcome {
+    /// The server sent a REQUEST: send `reply`, then switch to `accepted` if any
+    Respond {
+        reply: Vec<u8>,
+        accepted: Option<TextEncoding>,
+    },
+    /// The server accepted our REQUEST
+    Accepted(TextEncoding),
+    /// The server rejected our REQUEST
+    Rejected,
+}
+
+/// Build a REQUEST payload for `preferred`, falling back to UTF-8
+pub fn request(preferred: TextEncoding) -> Vec<u8> {
+    let mut payload = vec![CHARSET_REQUEST];
+    let mut offered = vec![preferred];
+    if preferred != TextEncoding::Utf8 {
+        offered.push(TextEncoding::Utf8);
+    }
+
+    for encoding in offered {
+        payload.push(SEPARATOR);
+        payload.extend_from_slice(encoding.name().as_bytes());
+    }
+    payload
+}
+
+/// Handle a CHARSET subnegotiation payload
+///
+/// For a server REQUEST, `preferred` is accepted when offered, otherwise
+/// the first offered charset we support. Returns `None` for payloads that
+/// need no action.
+pub fn handle_subnegotiation(data: &[u8], preferred: TextEncoding) -> Option<CharsetOutcome> {
+    let (&command, body) = data.split_first()?;
+
+    match command {
+        CHARSET_REQUEST => {
+            let accepted = choose(body, preferred);
+            let reply = match accepted {
+                Some(encoding) => {
+                    let mut reply = vec![CHARSET_ACCEPTED];
+                    reply.extend_from_slice(encoding.name().as_bytes());
+                    reply
+                }
+                None => vec![CHARSET_REJECTED],
+            };
+            Some(CharsetOutcome::Respond { reply, accepted })
+        }
+        CHARSET_ACCEPTED => {
+            let name = String::from_utf8_lossy(body);
+            match TextEncoding::from_name(&name) {
+                Some(encoding) => Some(CharsetOutcome::Accepted(encoding)),
+                None => {
+                    debug!("Server accepted unsupported charset '{}'", name);
+                    Some(CharsetOutcome::Rejected)
+                }
+            }
+        }
+        CHARSET_REJECTED => Some(CharsetOutcome::Rejected),
+        CHARSET_TTABLE_IS => Some(CharsetOutcome::Respond {
+            reply: vec![CHARSET_TTABLE_REJECTED],
+            accepted: None,
+        }),
+        _ => None,
+    }
+}
+
+/// Pick a charset from a REQUEST body (`<sep>name<sep>name...`)
+fn choose(body: &[u8], preferred: TextEncoding) -> Option<TextEncoding> {
+    // Skip "[TTABLE]" and its version byte; we only use the charset list
+    let body = match body.strip_prefix(TTABLE_PREFIX) {
+        Some(rest) => rest.get(1..).unwrap_or_default(),
+        None => body,
+    };
+
+    let (&separator, list) = body.split_first()?;
+    let offered: Vec<TextEncoding> = list
+        .split(|&b| b == separator)
+        .filter_map(|name| TextEncoding::from_name(&String::from_utf8_lossy(name)))
+        .collect();
+
+    debug!("Server offered charsets: {:?}", offered);
+
+    if offered.contains(&preferred) {
+        Some(preferred)
+    } else {
+        offered.first().copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request() {
+        assert_eq!(request(TextEncoding::Utf8), b"\x01;UTF-8".to_vec());
+        assert_eq!(request(TextEncoding::Cp437), b"\x01;IBM437;UTF-8".to_vec());
+    }
+
+    #[test]
+    fn test_server_request_prefers_world_encoding() {
+        let outcome = handle_subnegotiation(b"\x01 KOI8-R ISO-8859-1 UTF-8", TextEncoding::Utf8);
+        assert_eq!(
+            outcome,
+            Some(CharsetOutcome::Respond {
+                reply: b"\x02UTF-8".to_vec(),
+                accepted: Some(TextEncoding::Utf8),
+            })
+        );
+
+        // Preferred not offered: first supported one wins
+        let outcome = handle_subnegotiation(b"\x01;KOI8-R;latin1", TextEncoding::Utf8);
+        assert_eq!(
+            outcome,
+            Some(CharsetOutcome::Respond {
+                reply: b"\x02ISO-8859-1".to_vec(),
+                accepted: Some(TextEncoding::Latin1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_server_request_rejected() {
+        let outcome = handle_subnegotiation(b"\x01;KOI8-R;BIG5", TextEncoding::Utf8);
+        assert_eq!(
+            outcome,
+            Some(CharsetOutcome::Respond {
+                reply: vec![CHARSET_REJECTED],
+                accepted: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_ttable_request() {
+        let outcome = handle_subnegotiation(b"\x01[TTABLE]\x01;UTF-8", TextEncoding::Utf8);
+        assert!(matches!(
+            outcome,
+            Some(CharsetOutcome::Respond { accepted: Some(TextEncoding::Utf8), .. })
+        ));
+    }
+
+    #[test]
+    fn test_replies_to_our_request() {
+        assert_eq!(
+            handle_subnegotiation(b"\x02UTF-8", TextEncoding::Cp437),
+            Some(CharsetOutcome::Accepted(TextEncoding::Utf8))
+        );
+        assert_eq!(handle_subnegotiation(b"\x03", TextEncoding::Utf8), Some(CharsetOutcome::Rejected));
+        assert_eq!(handle_subnegotiation(b"", TextEncoding::Utf8), None);
+    }
+}
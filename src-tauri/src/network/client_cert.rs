@@ -0,0 +1,120 @@
+/// Client certificates for mutual TLS
+///
+/// Some servers (private builder ports, for example) require the client to
+/// authenticate with a certificate. The world references a PEM certificate
+/// chain and a PEM private key; both are loaded when connecting and checked
+/// against each other, so a wrong key is reported before the handshake
+/// instead of as an opaque alert from the server.
+
+use crate::error::{MushError, Result};
+use rustls::client::ResolvesClientCert;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::pem::{self, PemObject};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::sign::CertifiedKey;
+use rustls::{InconsistentKeys, SignatureScheme};
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+fn error(path: &Path, reason: impl Into<String>) -> MushError {
+    MushError::ClientCertificateError {
+        path: path.display().to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// Load a PEM certificate chain and its PEM private key
+///
+/// # Errors
+/// - `MushError::ClientCertificateError`: A file cannot be read, holds no
+///   certificate or key, uses an unsupported key type, or the key does not
+///   belong to the certificate
+pub fn load_client_certificate(
+    cert_path: &Path,
+    key_path: &Path,
+    provider: &CryptoProvider,
+) -> Result<Arc<CertifiedKey>> {
+    let cert_pem = fs::read(cert_path).map_err(|e| error(cert_path, format!("cannot be read ({})", e)))?;
+    let certs = CertificateDer::pem_slice_iter(&cert_pem)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| error(cert_path, format!("is not a valid PEM certificate ({})", e)))?;
+    if certs.is_empty() {
+        return Err(error(cert_path, "contains no certificate"));
+    }
+
+    let key_pem = fs::read(key_path).map_err(|e| error(key_path, format!("cannot be read ({})", e)))?;
+    let key = PrivateKeyDer::from_pem_slice(&key_pem).map_err(|e| match e {
+        pem::Error::NoItemsFound => error(key_path, "contains no private key"),
+        e => error(key_path, format!("is not a valid PEM private key ({})", e)),
+    })?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(key)
+        .map_err(|e| error(key_path, format!("unsupported private key ({})", e)))?;
+
+    let certified = CertifiedKey::new(certs, signing_key);
+    match certified.keys_match() {
+        // Some key types cannot report their public key; let the server decide
+        Ok(()) | Err(rustls::Error::InconsistentKeys(InconsistentKeys::Unknown)) => {}
+        Err(rustls::Error::InconsistentKeys(InconsistentKeys::KeyMismatch)) => {
+            return Err(error(
+                key_path,
+                format!("does not match the certificate in {}", cert_path.display()),
+            ));
+        }
+        Err(e) => return Err(error(cert_path, format!("is not a valid certificate ({})", e))),
+    }
+
+    info!("Loaded client certificate {}", cert_path.display());
+    Ok(Arc::new(certified))
+}
+
+/// Presents the same client certificate whenever the server asks for one
+#[derive(Debug)]
+pub(crate) struct ClientCertResolver(pub(crate) Arc<CertifiedKey>);
+
+impl ResolvesClientCert for ClientCertResolver {
+    fn resolve(&self, _root_hint_subjects: &[&[u8]], _sigschemes: &[SignatureScheme]) -> Option<Arc<CertifiedKey>> {
+        debug!("Server requested a client certificate");
+        Some(self.0.clone())
+    }
+
+    fn has_certs(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    fn load(cert: &str, key: &str) -> Result<Arc<CertifiedKey>> {
+        load_client_certificate(&fixture(cert), &fixture(key), &rustls::crypto::ring::default_provider())
+    }
+
+    #[test]
+    fn test_load_client_certificate() {
+        let certified = load("client.pem", "client.key").unwrap();
+        assert_eq!(certified.cert.len(), 1);
+    }
+
+    #[test]
+    fn test_client_certificate_errors() {
+        let reason = |result: Result<Arc<CertifiedKey>>| match result {
+            Err(MushError::ClientCertificateError { reason, .. }) => reason,
+            other => panic!("expected ClientCertificateError, got {:?}", other.map(|_| ())),
+        };
+
+        assert!(reason(load("missing.pem", "client.key")).starts_with("cannot be read"));
+        assert_eq!(reason(load("client.key", "client.key")), "contains no certificate");
+        assert_eq!(reason(load("client.pem", "client.pem")), "contains no private key");
+        assert!(reason(load("client.pem", "server_a.key")).starts_with("does not match the certificate"));
+    }
+}
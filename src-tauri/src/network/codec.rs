@@ -1,2 +1,280 @@
-/// MUD codec
-pub struct MudCodec;
+/// MUD text codec
+///
+/// Converts between the bytes on the wire and Rust strings using the
+/// world's text encoding. Decoding is stateful: a multi-byte UTF-8 sequence
+/// split across two reads is carried over instead of being replaced with
+/// U+FFFD.
+///
+/// Supported encodings: UTF-8, ISO-8859-1 (Latin-1), Windows-1252 and
+/// CP437 (IBM PC, common on older Diku-derived servers).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Character substituted for characters the encoding cannot represent
+const UNMAPPABLE: u8 = b'?';
+
+/// Windows-1252 characters for bytes 0x80-0x9F (undefined bytes map to C1 controls)
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// CP437 characters for bytes 0x80-0xFF
+const CP437_HIGH: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{00A0}',
+];
+
+/// Text encoding used by a world
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum TextEncoding {
+    /// UTF-8
+    #[default]
+    Utf8,
+    /// ISO-8859-1 (Latin-1)
+    Latin1,
+    /// Windows-1252 (Latin-1 superset with typographic characters)
+    Windows1252,
+    /// IBM code page 437
+    Cp437,
+}
+
+impl TextEncoding {
+    /// All supported encodings
+    pub const ALL: [TextEncoding; 4] = [
+        TextEncoding::Utf8,
+        TextEncoding::Latin1,
+        TextEncoding::Windows1252,
+        TextEncoding::Cp437,
+    ];
+
+    /// Get the IANA charset name
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Latin1 => "ISO-8859-1",
+            TextEncoding::Windows1252 => "WINDOWS-1252",
+            TextEncoding::Cp437 => "IBM437",
+        }
+    }
+
+    /// Look up an encoding by charset name or common alias (case-insensitive)
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_uppercase().as_str() {
+            "UTF-8" | "UTF8" => Some(TextEncoding::Utf8),
+            "ISO-8859-1" | "ISO_8859-1" | "ISO8859-1" | "LATIN1" | "LATIN-1" | "L1" => Some(TextEncoding::Latin1),
+            "WINDOWS-1252" | "CP1252" => Some(TextEncoding::Windows1252),
+            "IBM437" | "CP437" | "437" => Some(TextEncoding::Cp437),
+            _ => None,
+        }
+    }
+
+    /// Decode bytes that form complete characters
+    fn decode_single_byte(&self, bytes: &[u8], out: &mut String) {
+        for &byte in bytes {
+            out.push(self.decode_byte(byte));
+        }
+    }
+
+    /// Decode one byte of a single-byte encoding
+    fn decode_byte(&self, byte: u8) -> char {
+        match (self, byte) {
+            (_, 0x00..=0x7F) | (TextEncoding::Latin1, _) | (TextEncoding::Utf8, _) => byte as char,
+            (TextEncoding::Windows1252, 0x80..=0x9F) => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+            (TextEncoding::Windows1252, _) => byte as char,
+            (TextEncoding::Cp437, _) => CP437_HIGH[(byte - 0x80) as usize],
+        }
+    }
+
+    /// Encode one character of a single-byte encoding
+    fn encode_char(&self, c: char) -> u8 {
+        if c.is_ascii() {
+            return c as u8;
+        }
+
+        let high = match self {
+            TextEncoding::Latin1 => u8::try_from(u32::from(c)).ok(),
+            TextEncoding::Windows1252 => WINDOWS_1252_HIGH
+                .iter()
+                .position(|&h| h == c)
+                .map(|i| 0x80 + i as u8)
+                .or_else(|| u8::try_from(u32::from(c)).ok().filter(|&b| b >= 0xA0)),
+            TextEncoding::Cp437 => CP437_HIGH.iter().position(|&h| h == c).map(|i| 0x80 + i as u8),
+            TextEncoding::Utf8 => None,
+        };
+
+        high.unwrap_or(UNMAPPABLE)
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Stateful encoder/decoder for a connection
+#[derive(Debug, Clone, Default)]
+pub struct MudCodec {
+    encoding: TextEncoding,
+    /// Incomplete UTF-8 sequence carried over from the previous read
+    pending: Vec<u8>,
+}
+
+impl MudCodec {
+    /// Create a codec for an encoding
+    pub fn new(encoding: TextEncoding) -> Self {
+        Self {
+            encoding,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Get the current encoding
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    /// Switch encoding (e.g. after CHARSET negotiation)
+    ///
+    /// Any carried-over partial sequence is dropped.
+    pub fn set_encoding(&mut self, encoding: TextEncoding) {
+        self.encoding = encoding;
+        self.pending.clear();
+    }
+
+    /// Decode received bytes
+    ///
+    /// An incomplete UTF-8 sequence at the end of `data` is kept and
+    /// completed by the next call. Invalid sequences become U+FFFD.
+    pub fn decode(&mut self, data: &[u8]) -> String {
+        if self.encoding != TextEncoding::Utf8 {
+            let mut out = String::with_capacity(data.len());
+            self.encoding.decode_single_byte(data, &mut out);
+            return out;
+        }
+
+        let mut bytes = std::mem::take(&mut self.pending);
+        bytes.extend_from_slice(data);
+
+        let mut out = String::with_capacity(bytes.len());
+        let mut rest = &bytes[..];
+
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    out.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    // `valid_up_to` marks a prefix that is valid UTF-8
+                    out.push_str(std::str::from_utf8(valid).unwrap_or_default());
+
+                    match e.error_len() {
+                        Some(len) => {
+                            out.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            // Incomplete sequence at the end: wait for more bytes
+                            self.pending = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        out
+    }
+
+    /// Decode a complete chunk without touching the streaming state
+    pub fn decode_complete(&self, data: &[u8]) -> String {
+        match self.encoding {
+            TextEncoding::Utf8 => String::from_utf8_lossy(data).into_owned(),
+            encoding => {
+                let mut out = String::with_capacity(data.len());
+                encoding.decode_single_byte(data, &mut out);
+                out
+            }
+        }
+    }
+
+    /// Encode text for sending; unrepresentable characters become '?'
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self.encoding {
+            TextEncoding::Utf8 => text.as_bytes().to_vec(),
+            encoding => text.chars().map(|c| encoding.encode_char(c)).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_split_across_reads() {
+        let mut codec = MudCodec::new(TextEncoding::Utf8);
+        // "é€" = C3 A9 E2 82 AC, split inside both characters
+        assert_eq!(codec.decode(b"caf\xc3"), "caf");
+        assert_eq!(codec.decode(b"\xa9 \xe2\x82"), "é ");
+        assert_eq!(codec.decode(b"\xac!"), "€!");
+    }
+
+    #[test]
+    fn test_utf8_invalid_bytes() {
+        let mut codec = MudCodec::new(TextEncoding::Utf8);
+        assert_eq!(codec.decode(b"a\xffb\xc3(c"), "a\u{FFFD}b\u{FFFD}(c");
+    }
+
+    #[test]
+    fn test_single_byte_decoding() {
+        let mut latin1 = MudCodec::new(TextEncoding::Latin1);
+        assert_eq!(latin1.decode(b"caf\xe9"), "café");
+
+        let mut cp1252 = MudCodec::new(TextEncoding::Windows1252);
+        assert_eq!(cp1252.decode(b"\x93hi\x94 \x80 \xe9"), "\u{201C}hi\u{201D} € é");
+
+        let mut cp437 = MudCodec::new(TextEncoding::Cp437);
+        assert_eq!(cp437.decode(b"\xc9\xcd\xbb \x82"), "╔═╗ é");
+    }
+
+    #[test]
+    fn test_encoding() {
+        assert_eq!(MudCodec::new(TextEncoding::Utf8).encode("café"), b"caf\xc3\xa9".to_vec());
+        assert_eq!(MudCodec::new(TextEncoding::Latin1).encode("café €"), b"caf\xe9 ?".to_vec());
+        assert_eq!(MudCodec::new(TextEncoding::Windows1252).encode("café €"), b"caf\xe9 \x80".to_vec());
+        assert_eq!(MudCodec::new(TextEncoding::Cp437).encode("╔é✓"), b"\xc9\x82?".to_vec());
+    }
+
+    #[test]
+    fn test_roundtrip_single_byte() {
+        for encoding in [TextEncoding::Latin1, TextEncoding::Windows1252, TextEncoding::Cp437] {
+            let mut codec = MudCodec::new(encoding);
+            let bytes: Vec<u8> = (0x20..=0xFF).filter(|b| !(0x7F..0xA0).contains(b)).collect();
+            let text = codec.decode(&bytes);
+            assert_eq!(codec.encode(&text), bytes, "{} roundtrip", encoding);
+        }
+    }
+
+    #[test]
+    fn test_encoding_names() {
+        for encoding in TextEncoding::ALL {
+            assert_eq!(TextEncoding::from_name(encoding.name()), Some(encoding));
+        }
+        assert_eq!(TextEncoding::from_name("utf8"), Some(TextEncoding::Utf8));
+        assert_eq!(TextEncoding::from_name("cp437"), Some(TextEncoding::Cp437));
+        assert_eq!(TextEncoding::from_name("KOI8-R"), None);
+    }
+}
@@ -0,0 +1,150 @@
+/// Dual-stack TCP connect ("Happy Eyeballs", RFC 8305)
+///
+/// Resolves the host up front, then tries its addresses in turn,
+/// alternating IPv6 and IPv4. A new attempt starts whenever the previous
+/// one fails or has been pending for `ATTEMPT_DELAY`; earlier attempts keep
+/// running, the first to connect wins and the rest are dropped. Each
+/// attempt has its own timeout, so a dead address only delays the others
+/// by `ATTEMPT_DELAY` instead of using up the whole connect timeout.
+
+use crate::error::{MushError, Result};
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::{lookup_host, TcpStream};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, timeout};
+use tracing::{debug, info};
+
+/// Head start each attempt gets before the next address is tried
+pub const ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Resolve `host:port` and order the addresses for connecting: IPv6 first,
+/// then alternating families, keeping the resolver's order within each
+///
+/// # Errors
+/// - `MushError::ConnectionFailed`: The host does not resolve
+pub async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>> {
+    let failed = |source| MushError::ConnectionFailed {
+        host: host.to_string(),
+        port,
+        source,
+    };
+
+    let addrs: Vec<SocketAddr> = lookup_host((host, port)).await.map_err(failed)?.collect();
+    if addrs.is_empty() {
+        return Err(failed(io::Error::new(io::ErrorKind::NotFound, "host has no addresses")));
+    }
+
+    let (v6, v4): (Vec<_>, Vec<_>) = addrs.into_iter().partition(SocketAddr::is_ipv6);
+    let mut ordered = Vec::with_capacity(v6.len() + v4.len());
+    let (mut v6, mut v4) = (v6.into_iter(), v4.into_iter());
+    loop {
+        match (v6.next(), v4.next()) {
+            (None, None) => break,
+            (a, b) => ordered.extend(a.into_iter().chain(b)),
+        }
+    }
+
+    debug!("Resolved {} to {:?}", host, ordered);
+    Ok(ordered)
+}
+
+/// Connect to `host:port`, racing its addresses as described above
+///
+/// # Errors
+/// - `MushError::ConnectionFailed`: Resolution failed or no address accepted
+///   the connection (the last attempt's error is reported)
+pub async fn connect(host: &str, port: u16, attempt_timeout: Duration) -> Result<TcpStream> {
+    let addrs = resolve(host, port).await?;
+    let mut remaining = addrs.into_iter();
+    let mut attempts = JoinSet::new();
+    let mut last_error = None;
+
+    loop {
+        if let Some(addr) = remaining.next() {
+            debug!("Trying {}", addr);
+            attempts.spawn(async move {
+                let result = match timeout(attempt_timeout, TcpStream::connect(addr)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(io::Error::new(io::ErrorKind::TimedOut, "connect attempt timed out")),
+                };
+                (addr, result)
+            });
+        }
+        let more = remaining.len() > 0;
+
+        // Wait for an attempt to finish; with addresses left, only until the head start runs out
+        let finished = if more {
+            tokio::select! {
+                finished = attempts.join_next() => finished,
+                _ = sleep(ATTEMPT_DELAY) => continue,
+            }
+        } else {
+            attempts.join_next().await
+        };
+
+        match finished {
+            Some(Ok((addr, Ok(stream)))) => {
+                info!("Connected to {} via {}", host, addr);
+                return Ok(stream);
+            }
+            Some(Ok((addr, Err(e)))) => {
+                debug!("Connect to {} failed: {}", addr, e);
+                last_error = Some(e);
+            }
+            Some(Err(e)) => last_error = Some(io::Error::other(e)),
+            None if !more => break,
+            None => {}
+        }
+    }
+
+    Err(MushError::ConnectionFailed {
+        host: host.to_string(),
+        port,
+        source: last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "host has no addresses")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_resolve_orders_ipv6_first() {
+        let addrs = resolve("127.0.0.1", 4000).await.unwrap();
+        assert_eq!(addrs, vec!["127.0.0.1:4000".parse().unwrap()]);
+
+        assert!(matches!(
+            resolve("no-such-host.invalid", 4000).await,
+            Err(MushError::ConnectionFailed { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_connect_reports_last_error() {
+        // Bind then drop to find a closed port
+        let port = TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap().port();
+
+        match connect("127.0.0.1", port, Duration::from_secs(5)).await {
+            Err(MushError::ConnectionFailed { source, .. }) => {
+                assert_eq!(source.kind(), io::ErrorKind::ConnectionRefused)
+            }
+            other => panic!("Expected ConnectionFailed, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_falls_through_to_working_address() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let _ = listener.accept().await;
+        });
+
+        // "localhost" may resolve to ::1 (nothing listening) before 127.0.0.1
+        let stream = connect("localhost", port, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(stream.peer_addr().unwrap(), SocketAddr::from(([127, 0, 0, 1], port)));
+    }
+}
@@ -0,0 +1,195 @@
+/// NEW-ENVIRON (option 39) support for MNES
+///
+/// The server asks for variables and the client answers with their values:
+///
+/// `IAC SB NEW-ENVIRON SEND VAR "CLIENT_NAME" VAR "CHARSET" IAC SE`
+/// `IAC SB NEW-ENVIRON IS VAR "CLIENT_NAME" VALUE "MACMush" VAR "CHARSET" VALUE "UTF-8" IAC SE`
+///
+/// A SEND without variable names asks for everything. Variables we don't
+/// have (or that the user suppressed) are answered without a VALUE, which
+/// marks them as undefined.
+///
+/// References:
+/// - https://tintin.mudhalla.net/protocols/mnes/
+/// - RFC 1572
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tracing::debug;
+
+/// NEW-ENVIRON telnet option number
+pub const TELOPT_NEW_ENVIRON: u8 = 39;
+
+/// NEW-ENVIRON commands
+pub const ENVIRON_IS: u8 = 0;
+pub const ENVIRON_SEND: u8 = 1;
+pub const ENVIRON_INFO: u8 = 2;
+
+/// NEW-ENVIRON delimiters
+pub const ENVIRON_VAR: u8 = 0;
+pub const ENVIRON_VALUE: u8 = 1;
+pub const ENVIRON_ESC: u8 = 2;
+pub const ENVIRON_USERVAR: u8 = 3;
+
+/// Per-world replacement for a variable reported via NEW-ENVIRON
+///
+/// With `value` set it replaces the client's value (or adds a variable);
+/// without one the variable is never reported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnvironOverride {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl EnvironOverride {
+    /// Report `value` for `name`
+    pub fn set(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self { name: name.into(), value: Some(value.into()) }
+    }
+
+    /// Never report `name`
+    pub fn suppress(name: impl Into<String>) -> Self {
+        Self { name: name.into(), value: None }
+    }
+}
+
+/// Apply a world's overrides to the client's variables
+///
+/// Names are compared case-insensitively and reported in upper case.
+pub fn apply_overrides(mut variables: BTreeMap<String, String>, overrides: &[EnvironOverride]) -> BTreeMap<String, String> {
+    for entry in overrides {
+        let name = entry.name.to_ascii_uppercase();
+        match &entry.value {
+            Some(value) => variables.insert(name, value.clone()),
+            None => variables.remove(&name),
+        };
+    }
+    variables
+}
+
+/// Answer a NEW-ENVIRON subnegotiation
+///
+/// Returns the IS reply for a SEND request, `None` for anything else.
+pub fn handle_subnegotiation(data: &[u8], variables: &BTreeMap<String, String>) -> Option<Vec<u8>> {
+    let (&command, rest) = data.split_first()?;
+    if command != ENVIRON_SEND {
+        debug!("Ignoring NEW-ENVIRON command {}", command);
+        return None;
+    }
+
+    let requested = parse_names(rest);
+    let mut reply = vec![ENVIRON_IS];
+
+    if requested.is_empty() {
+        for (name, value) in variables {
+            push_variable(&mut reply, ENVIRON_VAR, name, Some(value));
+        }
+    } else {
+        for (kind, name) in requested {
+            let value = variables.get(&name.to_ascii_uppercase());
+            debug!("NEW-ENVIRON {} requested: {:?}", name, value);
+            push_variable(&mut reply, kind, &name, value);
+        }
+    }
+
+    Some(reply)
+}
+
+/// Split a SEND request into (VAR or USERVAR, name) pairs
+///
+/// A bare VAR or USERVAR (no name) asks for all variables and yields nothing.
+fn parse_names(data: &[u8]) -> Vec<(u8, String)> {
+    let mut names = Vec::new();
+    let mut current: Option<(u8, Vec<u8>)> = None;
+    let mut bytes = data.iter();
+
+    while let Some(&byte) = bytes.next() {
+        match byte {
+            ENVIRON_VAR | ENVIRON_USERVAR => {
+                if let Some((kind, name)) = current.take().filter(|(_, name)| !name.is_empty()) {
+                    names.push((kind, String::from_utf8_lossy(&name).into_owned()));
+                }
+                current = Some((byte, Vec::new()));
+            }
+            ENVIRON_ESC => {
+                if let (Some((_, name)), Some(&escaped)) = (current.as_mut(), bytes.next()) {
+                    name.push(escaped);
+                }
+            }
+            _ => {
+                if let Some((_, name)) = current.as_mut() {
+                    name.push(byte);
+                }
+            }
+        }
+    }
+
+    if let Some((kind, name)) = current.filter(|(_, name)| !name.is_empty()) {
+        names.push((kind, String::from_utf8_lossy(&name).into_owned()));
+    }
+    names
+}
+
+/// Append `kind name [VALUE value]`, escaping delimiter bytes
+fn push_variable(reply: &mut Vec<u8>, kind: u8, name: &str, value: Option<&String>) {
+    reply.push(kind);
+    push_escaped(reply, name.as_bytes());
+    if let Some(value) = value {
+        reply.push(ENVIRON_VALUE);
+        push_escaped(reply, value.as_bytes());
+    }
+}
+
+fn push_escaped(reply: &mut Vec<u8>, bytes: &[u8]) {
+    for &byte in bytes {
+        if matches!(byte, ENVIRON_VAR | ENVIRON_VALUE | ENVIRON_ESC | ENVIRON_USERVAR) {
+            reply.push(ENVIRON_ESC);
+        }
+        reply.push(byte);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("CLIENT_NAME".to_string(), "MACMush".to_string()),
+            ("CHARSET".to_string(), "UTF-8".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_send_requested_variables() {
+        let reply = handle_subnegotiation(b"\x01\x00CLIENT_NAME\x03IPADDRESS\x00charset", &variables()).unwrap();
+        assert_eq!(reply, b"\x00\x00CLIENT_NAME\x01MACMush\x03IPADDRESS\x00charset\x01UTF-8".to_vec());
+
+        // Only SEND is answered
+        assert_eq!(handle_subnegotiation(b"\x00\x00CHARSET\x01UTF-8", &variables()), None);
+    }
+
+    #[test]
+    fn test_send_all_variables() {
+        let expected = b"\x00\x00CHARSET\x01UTF-8\x00CLIENT_NAME\x01MACMush".to_vec();
+        assert_eq!(handle_subnegotiation(b"\x01", &variables()).unwrap(), expected);
+        assert_eq!(handle_subnegotiation(b"\x01\x00\x03", &variables()).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_overrides_and_escaping() {
+        let variables = apply_overrides(
+            variables(),
+            &[
+                EnvironOverride::suppress("client_name"),
+                EnvironOverride::set("IPADDRESS", "10.0.0.1"),
+                EnvironOverride::set("ODD", "a\x01b"),
+            ],
+        );
+
+        assert_eq!(variables.get("CLIENT_NAME"), None);
+        let reply = handle_subnegotiation(b"\x01\x00CLIENT_NAME\x00IPADDRESS\x00OD\x02D", &variables).unwrap();
+        assert_eq!(reply, b"\x00\x00CLIENT_NAME\x00IPADDRESS\x0110.0.0.1\x00ODD\x01a\x02\x01b".to_vec());
+    }
+}
@@ -0,0 +1,222 @@
+/// GMCP (Generic MUD Communication Protocol, option 201) support
+///
+/// GMCP messages are telnet subnegotiations carrying a package name
+/// followed by an optional JSON payload:
+///
+/// `IAC SB GMCP "Char.Vitals {"hp": 100}" IAC SE`
+///
+/// References:
+/// - https://tintin.mudhalla.net/protocols/gmcp/
+/// - https://www.gammon.com.au/gmcp
+
+use crate::error::{MushError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use tracing::{debug, warn};
+
+/// GMCP telnet option number
+pub const TELOPT_GMCP: u8 = 201;
+
+/// A single GMCP message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GmcpMessage {
+    /// Package name, e.g. "Char.Vitals"
+    pub package: String,
+    /// JSON payload (`Null` when the message carries no data)
+    pub data: Value,
+}
+
+impl GmcpMessage {
+    /// Create a new GMCP message
+    pub fn new(package: impl Into<String>, data: Value) -> Self {
+        Self {
+            package: package.into(),
+            data,
+        }
+    }
+
+    /// Parse a GMCP subnegotiation payload
+    ///
+    /// Payloads that are not valid JSON are kept as a JSON string so that
+    /// non-conforming servers still reach scripts.
+    ///
+    /// # Errors
+    /// - `MushError::ValidationError`: Payload is not UTF-8 or has no package name
+    pub fn parse(payload: &[u8]) -> Result<Self> {
+        let text = std::str::from_utf8(payload).map_err(|e| MushError::ValidationError {
+            field: "gmcp".to_string(),
+            reason: format!("Payload is not valid UTF-8: {}", e),
+        })?;
+
+        let text = text.trim();
+        let (package, raw) = match text.find(char::is_whitespace) {
+            Some(pos) => (&text[..pos], text[pos..].trim()),
+            None => (text, ""),
+        };
+
+        if package.is_empty() {
+            return Err(MushError::ValidationError {
+                field: "gmcp".to_string(),
+                reason: "Missing package name".to_string(),
+            });
+        }
+
+        let data = if raw.is_empty() {
+            Value::Null
+        } else {
+            serde_json::from_str(raw).unwrap_or_else(|e| {
+                warn!("GMCP {} carries invalid JSON ({}), keeping raw text", package, e);
+                Value::String(raw.to_string())
+            })
+        };
+
+        debug!("GMCP received: {}", package);
+        Ok(Self::new(package, data))
+    }
+
+    /// Encode the message as a subnegotiation payload
+    pub fn encode(&self) -> Vec<u8> {
+        if self.data.is_null() {
+            self.package.clone().into_bytes()
+        } else {
+            format!("{} {}", self.package, self.data).into_bytes()
+        }
+    }
+
+    /// Build the Core.Hello message identifying the client
+    pub fn core_hello(client: &str, version: &str) -> Self {
+        Self::new("Core.Hello", json!({ "client": client, "version": version }))
+    }
+
+    /// Build the Core.Supports.Set message listing enabled modules
+    pub fn core_supports_set(modules: &[String]) -> Self {
+        Self::new("Core.Supports.Set", json!(modules))
+    }
+}
+
+/// Latest GMCP data per package, stored as a tree keyed by package path
+///
+/// "Char.Vitals" is stored under `tree["Char"]["Vitals"]`.
+#[derive(Debug, Clone)]
+pub struct GmcpStore {
+    tree: Value,
+}
+
+impl GmcpStore {
+    /// Create an empty store
+    pub fn new() -> Self {
+        Self {
+            tree: Value::Object(Map::new()),
+        }
+    }
+
+    /// Store the latest data for a package
+    pub fn update(&mut self, package: &str, data: Value) {
+        let mut node = &mut self.tree;
+        let mut segments = package.split('.').filter(|s| !s.is_empty()).peekable();
+
+        while let Some(segment) = segments.next() {
+            if !node.is_object() {
+                *node = Value::Object(Map::new());
+            }
+            let map = node.as_object_mut().expect("node is an object");
+
+            if segments.peek().is_none() {
+                map.insert(segment.to_string(), data);
+                return;
+            }
+
+            node = map
+                .entry(segment.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+        }
+    }
+
+    /// Get the data stored for a package (or a package prefix)
+    pub fn get(&self, package: &str) -> Option<&Value> {
+        package
+            .split('.')
+            .filter(|s| !s.is_empty())
+            .try_fold(&self.tree, |node, segment| node.get(segment))
+    }
+
+    /// Get the whole package tree
+    pub fn tree(&self) -> &Value {
+        &self.tree
+    }
+
+    /// Remove all stored data
+    pub fn clear(&mut self) {
+        self.tree = Value::Object(Map::new());
+    }
+}
+
+impl Default for GmcpStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_with_json() {
+        let msg = GmcpMessage::parse(br#"Char.Vitals {"hp": 100, "maxhp": 120}"#).unwrap();
+        assert_eq!(msg.package, "Char.Vitals");
+        assert_eq!(msg.data["hp"], 100);
+        assert_eq!(msg.data["maxhp"], 120);
+    }
+
+    #[test]
+    fn test_parse_without_data() {
+        let msg = GmcpMessage::parse(b"Core.Goodbye").unwrap();
+        assert_eq!(msg.package, "Core.Goodbye");
+        assert!(msg.data.is_null());
+    }
+
+    #[test]
+    fn test_parse_invalid_json_kept_as_string() {
+        let msg = GmcpMessage::parse(b"Comm.Channel hello there").unwrap();
+        assert_eq!(msg.data, Value::String("hello there".to_string()));
+    }
+
+    #[test]
+    fn test_parse_empty_payload() {
+        assert!(GmcpMessage::parse(b"").is_err());
+        assert!(GmcpMessage::parse(&[0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn test_encode() {
+        let hello = GmcpMessage::core_hello("MACMush", "0.1.1");
+        assert_eq!(hello.encode(), br#"Core.Hello {"client":"MACMush","version":"0.1.1"}"#.to_vec());
+
+        let supports = GmcpMessage::core_supports_set(&["Char 1".to_string(), "Room 1".to_string()]);
+        assert_eq!(supports.encode(), br#"Core.Supports.Set ["Char 1","Room 1"]"#.to_vec());
+
+        let ping = GmcpMessage::new("Core.Ping", Value::Null);
+        assert_eq!(ping.encode(), b"Core.Ping".to_vec());
+    }
+
+    #[test]
+    fn test_store_tree() {
+        let mut store = GmcpStore::new();
+        store.update("Char.Vitals", json!({"hp": 50}));
+        store.update("Char.Status", json!({"level": 10}));
+        store.update("Room.Info", json!({"num": 1234}));
+
+        assert_eq!(store.get("Char.Vitals").unwrap()["hp"], 50);
+        assert_eq!(store.get("Char").unwrap()["Status"]["level"], 10);
+        assert_eq!(store.tree()["Room"]["Info"]["num"], 1234);
+        assert!(store.get("Char.Missing").is_none());
+
+        // Latest value wins
+        store.update("Char.Vitals", json!({"hp": 75}));
+        assert_eq!(store.get("Char.Vitals").unwrap()["hp"], 75);
+
+        store.clear();
+        assert!(store.get("Char").is_none());
+    }
+}
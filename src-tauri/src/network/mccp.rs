@@ -3,13 +3,19 @@
 /// Implements MCCP2 (option 86) for server-to-client compression
 /// and MCCP3 (option 87) for client-to-server compression.
 ///
+/// The MCCP2 stream starts right after IAC SB COMPRESS2 IAC SE and may end
+/// at any point; whatever follows the end of the zlib stream is plain
+/// telnet data again, and the server may later start a new stream.
+///
 /// References:
 /// - https://tintin.mudhalla.net/protocols/mccp/
 /// - https://www.gammon.com.au/mccp/protocol.html
 
 use crate::error::{MushError, Result};
-use flate2::write::{ZlibDecoder, ZlibEncoder};
-use flate2::Compression;
+use chrono::{DateTime, Utc};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Decompress, FlushDecompress, Status};
+use serde::Serialize;
 use std::io::Write;
 use tracing::{debug, info, warn};
 
@@ -17,6 +23,9 @@ use tracing::{debug, info, warn};
 pub const TELOPT_COMPRESS2: u8 = 86; // MCCP2 (server-to-client)
 pub const TELOPT_COMPRESS3: u8 = 87; // MCCP3 (client-to-server)
 
+/// Output space reserved per inflate step
+const DECOMPRESS_CHUNK: usize = 16 * 1024;
+
 /// MCCP compression state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CompressionState {
@@ -35,11 +44,22 @@ pub struct MccpHandler {
     /// Client-to-server compression state (MCCP3)
     tx_state: CompressionState,
     /// Zlib decompressor for incoming data
-    decompressor: Option<ZlibDecoder<Vec<u8>>>,
+    decompressor: Option<Decompress>,
     /// Zlib compressor for outgoing data
     compressor: Option<ZlibEncoder<Vec<u8>>>,
-    /// Buffer for decompressed data
-    decompress_buffer: Vec<u8>,
+    /// Traffic counters, kept across compression restarts
+    stats: CompressionStats,
+}
+
+/// Result of feeding received bytes to the MCCP2 stream
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Decompressed {
+    /// Decompressed data
+    pub data: Vec<u8>,
+    /// Bytes received after the end of the zlib stream (plain telnet data)
+    ///
+    /// `None` while the compressed stream continues.
+    pub trailing: Option<Vec<u8>>,
 }
 
 impl MccpHandler {
@@ -50,48 +70,52 @@ impl MccpHandler {
             tx_state: CompressionState::None,
             decompressor: None,
             compressor: None,
-            decompress_buffer: Vec::new(),
+            stats: CompressionStats::default(),
         }
     }
 
     /// Handle MCCP2 (server-to-client) negotiation
-    pub fn handle_mccp2_will(&mut self) -> Result<Vec<u8>> {
+    ///
+    /// Called once the telnet layer has agreed to COMPRESS2 (IAC DO COMPRESS2).
+    pub fn handle_mccp2_will(&mut self) -> Result<()> {
         info!("Server offered MCCP2 compression");
         self.rx_state = CompressionState::Negotiated;
-
-        // Respond with IAC DO COMPRESS2
-        Ok(vec![255, 253, TELOPT_COMPRESS2]) // IAC DO COMPRESS2
+        Ok(())
     }
 
     /// Handle MCCP2 subnegotiation - compression starts immediately after
     pub fn handle_mccp2_subnegotiation(&mut self) -> Result<()> {
         info!("MCCP2 compression starting");
         self.rx_state = CompressionState::Active;
+        if self.stats.mccp2_started_at.is_some() {
+            self.stats.mccp2_restarts += 1;
+        }
+        self.stats.mccp2_started_at = Some(Utc::now());
 
-        // Initialize zlib decompressor
-        self.decompressor = Some(ZlibDecoder::new(Vec::new()));
-        self.decompress_buffer.clear();
+        // Initialize zlib decompressor (zlib header expected)
+        self.decompressor = Some(Decompress::new(true));
 
         Ok(())
     }
 
     /// Handle MCCP3 (client-to-server) negotiation
-    pub fn handle_mccp3_will(&mut self) -> Result<Vec<u8>> {
+    ///
+    /// Called once the telnet layer has agreed to COMPRESS3 (IAC DO COMPRESS3).
+    /// The caller then sends IAC SB COMPRESS3 IAC SE and calls `start_mccp3`.
+    pub fn handle_mccp3_will(&mut self) -> Result<()> {
         info!("Server offered MCCP3 compression");
         self.tx_state = CompressionState::Negotiated;
-
-        // Respond with IAC DO COMPRESS3 IAC SB COMPRESS3 IAC SE
-        Ok(vec![
-            255, 253, TELOPT_COMPRESS3, // IAC DO COMPRESS3
-            255, 250, TELOPT_COMPRESS3, // IAC SB COMPRESS3
-            255, 240,                    // IAC SE
-        ])
+        Ok(())
     }
 
     /// Start client-to-server compression after negotiation
     pub fn start_mccp3(&mut self) -> Result<()> {
         info!("MCCP3 compression starting");
         self.tx_state = CompressionState::Active;
+        if self.stats.mccp3_started_at.is_some() {
+            self.stats.mccp3_restarts += 1;
+        }
+        self.stats.mccp3_started_at = Some(Utc::now());
 
         // Initialize zlib compressor
         self.compressor = Some(ZlibEncoder::new(Vec::new(), Compression::default()));
@@ -110,27 +134,69 @@ impl MccpHandler {
     }
 
     /// Decompress incoming data (MCCP2)
-    pub fn decompress(&mut self, compressed_data: &[u8]) -> Result<Vec<u8>> {
+    ///
+    /// All of `input` is consumed. If the zlib stream ends inside it, MCCP2
+    /// goes back to the negotiated state and the bytes after the end are
+    /// returned as `trailing`.
+    ///
+    /// # Errors
+    /// - `MushError::CompressionError`: The stream is corrupt; MCCP2 is turned off
+    pub fn decompress(&mut self, input: &[u8]) -> Result<Decompressed> {
         if self.rx_state != CompressionState::Active {
-            return Ok(compressed_data.to_vec());
+            return Ok(Decompressed {
+                data: input.to_vec(),
+                trailing: None,
+            });
         }
 
         let decompressor = self.decompressor.as_mut()
             .ok_or_else(|| MushError::CompressionError("No decompressor initialized".to_string()))?;
 
-        // Write compressed data to decompressor
-        decompressor.write_all(compressed_data)
-            .map_err(|e| MushError::CompressionError(format!("Decompression write failed: {}", e)))?;
-
-        // Get decompressed output
-        let output = decompressor.get_ref().clone();
-        decompressor.get_mut().clear();
+        let mut output = Vec::with_capacity(DECOMPRESS_CHUNK);
+        let mut pos = 0;
+
+        loop {
+            output.reserve(DECOMPRESS_CHUNK);
+            let (in_before, out_before) = (decompressor.total_in(), decompressor.total_out());
+
+            let status = match decompressor.decompress_vec(&input[pos..], &mut output, FlushDecompress::None) {
+                Ok(status) => status,
+                Err(e) => {
+                    warn!("Corrupt MCCP2 stream: {}", e);
+                    self.rx_state = CompressionState::None;
+                    self.decompressor = None;
+                    return Err(MushError::CompressionError(format!("Corrupt MCCP2 stream: {}", e)));
+                }
+            };
+            pos += (decompressor.total_in() - in_before) as usize;
+
+            if status == Status::StreamEnd {
+                info!("MCCP2 compression ended by server");
+                self.record_received(pos, output.len());
+                self.rx_state = CompressionState::Negotiated;
+                self.decompressor = None;
+                return Ok(Decompressed {
+                    data: output,
+                    trailing: Some(input[pos..].to_vec()),
+                });
+            }
+
+            // Stop once the input is used up and the output buffer was not filled
+            let progressed = decompressor.total_in() != in_before || decompressor.total_out() != out_before;
+            if !progressed || (pos == input.len() && output.len() < output.capacity()) {
+                break;
+            }
+        }
 
         if !output.is_empty() {
-            debug!("Decompressed {} bytes to {} bytes", compressed_data.len(), output.len());
+            debug!("Decompressed {} bytes to {} bytes", input.len(), output.len());
         }
+        self.record_received(input.len(), output.len());
 
-        Ok(output)
+        Ok(Decompressed {
+            data: output,
+            trailing: None,
+        })
     }
 
     /// Compress outgoing data (MCCP3)
@@ -149,32 +215,34 @@ impl MccpHandler {
         compressor.flush()
             .map_err(|e| MushError::CompressionError(format!("Compression flush failed: {}", e)))?;
 
-        // Get compressed output
-        let compressed = compressor.get_ref().clone();
+        // Take the compressed output produced for this data
+        let compressed = std::mem::take(compressor.get_mut());
         debug!("Compressed {} bytes to {} bytes", data.len(), compressed.len());
 
+        self.stats.tx_uncompressed_bytes += data.len() as u64;
+        self.stats.tx_compressed_bytes += compressed.len() as u64;
+
         Ok(compressed)
     }
 
     /// Disable server-to-client compression
-    pub fn disable_mccp2(&mut self) -> Result<Vec<u8>> {
+    ///
+    /// The caller is responsible for sending IAC DONT COMPRESS2.
+    pub fn disable_mccp2(&mut self) -> Result<()> {
         info!("Disabling MCCP2 compression");
         self.rx_state = CompressionState::None;
         self.decompressor = None;
-        self.decompress_buffer.clear();
-
-        // Send IAC DONT COMPRESS2
-        Ok(vec![255, 254, TELOPT_COMPRESS2]) // IAC DONT COMPRESS2
+        Ok(())
     }
 
     /// Disable client-to-server compression
-    pub fn disable_mccp3(&mut self) -> Result<Vec<u8>> {
+    ///
+    /// The caller is responsible for sending IAC DONT COMPRESS3.
+    pub fn disable_mccp3(&mut self) -> Result<()> {
         info!("Disabling MCCP3 compression");
         self.tx_state = CompressionState::None;
         self.compressor = None;
-
-        // Send IAC DONT COMPRESS3
-        Ok(vec![255, 254, TELOPT_COMPRESS3]) // IAC DONT COMPRESS3
+        Ok(())
     }
 
     /// Get compression statistics
@@ -182,15 +250,48 @@ impl MccpHandler {
         CompressionStats {
             mccp2_active: self.is_receiving_compressed(),
             mccp3_active: self.is_sending_compressed(),
+            rx_ratio: ratio(self.stats.rx_decompressed_bytes, self.stats.rx_compressed_bytes),
+            tx_ratio: ratio(self.stats.tx_uncompressed_bytes, self.stats.tx_compressed_bytes),
+            ..self.stats
         }
     }
+
+    fn record_received(&mut self, compressed: usize, decompressed: usize) {
+        self.stats.rx_compressed_bytes += compressed as u64;
+        self.stats.rx_decompressed_bytes += decompressed as u64;
+    }
+}
+
+/// Uncompressed-to-compressed size ratio, if anything was compressed
+fn ratio(uncompressed: u64, compressed: u64) -> Option<f64> {
+    (compressed > 0).then(|| uncompressed as f64 / compressed as f64)
 }
 
 /// Compression statistics
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
 pub struct CompressionStats {
     pub mccp2_active: bool,
     pub mccp3_active: bool,
+    /// Compressed bytes received while MCCP2 was active
+    pub rx_compressed_bytes: u64,
+    /// Bytes the received MCCP2 data decompressed to
+    pub rx_decompressed_bytes: u64,
+    /// Bytes passed to the MCCP3 compressor
+    pub tx_uncompressed_bytes: u64,
+    /// Compressed bytes sent while MCCP3 was active
+    pub tx_compressed_bytes: u64,
+    /// Incoming ratio (decompressed / compressed), e.g. 4.0 saves 75%
+    pub rx_ratio: Option<f64>,
+    /// Outgoing ratio (uncompressed / compressed)
+    pub tx_ratio: Option<f64>,
+    /// When the current (or last) MCCP2 stream started
+    pub mccp2_started_at: Option<DateTime<Utc>>,
+    /// When the current (or last) MCCP3 stream started
+    pub mccp3_started_at: Option<DateTime<Utc>>,
+    /// MCCP2 streams started after the first one
+    pub mccp2_restarts: u32,
+    /// MCCP3 streams started after the first one
+    pub mccp3_restarts: u32,
 }
 
 impl Default for MccpHandler {
@@ -217,8 +318,7 @@ mod tests {
         let mut handler = MccpHandler::new();
 
         // Handle WILL COMPRESS2
-        let response = handler.handle_mccp2_will().unwrap();
-        assert_eq!(response, vec![255, 253, TELOPT_COMPRESS2]); // IAC DO COMPRESS2
+        handler.handle_mccp2_will().unwrap();
         assert_eq!(handler.rx_state, CompressionState::Negotiated);
 
         // Handle subnegotiation
@@ -232,8 +332,7 @@ mod tests {
         let mut handler = MccpHandler::new();
 
         // Handle WILL COMPRESS3
-        let response = handler.handle_mccp3_will().unwrap();
-        assert!(response.contains(&TELOPT_COMPRESS3));
+        handler.handle_mccp3_will().unwrap();
         assert_eq!(handler.tx_state, CompressionState::Negotiated);
 
         // Start compression
@@ -257,4 +356,94 @@ mod tests {
         assert!(stats.mccp2_active);
         assert!(!stats.mccp3_active);
     }
+
+    #[test]
+    fn test_traffic_stats() {
+        let mut handler = active_handler();
+        let text = b"The quick brown fox jumps over the lazy dog. ".repeat(20);
+        let stream = zlib(&text);
+
+        handler.decompress(&stream).unwrap();
+        let stats = handler.get_stats();
+        assert_eq!(stats.rx_compressed_bytes, stream.len() as u64);
+        assert_eq!(stats.rx_decompressed_bytes, text.len() as u64);
+        assert!(stats.rx_ratio.unwrap() > 1.0);
+        assert!(stats.mccp2_started_at.is_some());
+        assert_eq!(stats.mccp2_restarts, 0);
+        assert_eq!(stats.tx_ratio, None);
+
+        // Server starts a second stream
+        handler.handle_mccp2_subnegotiation().unwrap();
+        assert_eq!(handler.get_stats().mccp2_restarts, 1);
+
+        handler.start_mccp3().unwrap();
+        let first = handler.compress(b"look\r\n").unwrap();
+        let second = handler.compress(b"look\r\n").unwrap();
+        let stats = handler.get_stats();
+        assert_eq!(stats.tx_uncompressed_bytes, 12);
+        assert_eq!(stats.tx_compressed_bytes, (first.len() + second.len()) as u64);
+    }
+
+    /// Helper: zlib-compress a complete stream
+    fn zlib(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn active_handler() -> MccpHandler {
+        let mut handler = MccpHandler::new();
+        handler.handle_mccp2_will().unwrap();
+        handler.handle_mccp2_subnegotiation().unwrap();
+        handler
+    }
+
+    #[test]
+    fn test_decompress_split_stream() {
+        let mut handler = active_handler();
+        let stream = zlib(b"Welcome to the MUD!\r\n");
+        let (first, second) = stream.split_at(stream.len() / 2);
+
+        let mut text = handler.decompress(first).unwrap().data;
+        let rest = handler.decompress(second).unwrap();
+        text.extend_from_slice(&rest.data);
+
+        assert_eq!(text, b"Welcome to the MUD!\r\n".to_vec());
+        assert_eq!(rest.trailing, Some(Vec::new()));
+        assert!(!handler.is_receiving_compressed());
+    }
+
+    #[test]
+    fn test_decompress_stream_end_with_trailing_data() {
+        let mut handler = active_handler();
+        let mut input = zlib(b"compressed ");
+        input.extend_from_slice(b"plain");
+
+        let result = handler.decompress(&input).unwrap();
+        assert_eq!(result.data, b"compressed ".to_vec());
+        assert_eq!(result.trailing, Some(b"plain".to_vec()));
+
+        // Back to negotiated: the server may start a new stream
+        assert_eq!(handler.rx_state, CompressionState::Negotiated);
+        handler.handle_mccp2_subnegotiation().unwrap();
+        assert_eq!(handler.decompress(&zlib(b"again")).unwrap().data, b"again".to_vec());
+    }
+
+    #[test]
+    fn test_decompress_large_output() {
+        let mut handler = active_handler();
+        let text = vec![b'x'; DECOMPRESS_CHUNK * 5];
+
+        let result = handler.decompress(&zlib(&text)).unwrap();
+        assert_eq!(result.data, text);
+    }
+
+    #[test]
+    fn test_decompress_corrupt_stream() {
+        let mut handler = active_handler();
+
+        let result = handler.decompress(b"\x78\x9c\xff\xff\xff\xff");
+        assert!(matches!(result, Err(MushError::CompressionError(_))));
+        assert!(!handler.is_receiving_compressed());
+    }
 }
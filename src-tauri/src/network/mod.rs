@@ -2,18 +2,44 @@
 ///
 /// This module handles all network communication:
 /// - TCP client with async I/O
+/// - Dual-stack (Happy Eyeballs) connects across resolved addresses
+/// - SOCKS5 and HTTP CONNECT proxies
+/// - WebSocket transport (ws://, wss://)
 /// - TLS wrapper for secure connections
-/// - MUD protocol support (Telnet, MCCP, MXP, GMCP)
+/// - MUD protocol support (Telnet, MCCP, MXP, MSP, GMCP, CHARSET, NEW-ENVIRON)
 
 pub mod tcp;
+pub mod dialer;
+pub mod proxy;
+pub mod websocket;
 pub mod tls;
+pub mod pinning;
+pub mod client_cert;
+pub mod telnet;
+pub mod ttype;
+pub mod gmcp;
+pub mod msdp;
+pub mod mssp;
 pub mod codec;
+pub mod charset;
+pub mod environ;
 pub mod mccp;
 pub mod mxp;
+pub mod msp;
 
 // Re-export commonly used types
 pub use tcp::TcpClient;
-pub use tls::{TlsClient, TlsClientBuilder, MudStream};
-pub use codec::MudCodec;
+pub use proxy::{ProxyConfig, ProxyKind};
+pub use websocket::WebSocketTransport;
+pub use tls::{TlsClient, TlsClientBuilder, TlsInfo, MudStream};
+pub use pinning::{CertificatePin, CertificateTrust, PinStore};
+pub use telnet::{TelnetHandler, TelnetEvent};
+pub use ttype::TtypeHandler;
+pub use gmcp::{GmcpMessage, GmcpStore};
+pub use msdp::{MsdpCommand, MsdpValue};
+pub use mssp::{MsspData, MsspValue};
+pub use codec::{MudCodec, TextEncoding};
+pub use environ::EnvironOverride;
 pub use mccp::{MccpHandler, CompressionStats};
-pub use mxp::{MxpParser, MxpMode, MxpTag, MxpElement};
+pub use mxp::{ElementDefinition, MxpElement, MxpLink, MxpLinkKind, MxpMode, MxpOutput, MxpParser, MxpSpan, MxpStyle, MxpTag};
+pub use msp::{MspParser, MspRequest, SoundKind};
@@ -0,0 +1,386 @@
+/// MSDP (Mud Server Data Protocol, option 69) support
+///
+/// MSDP subnegotiations carry variables as byte-delimited structures:
+///
+/// `IAC SB MSDP VAR "HEALTH" VAL "100" IAC SE`
+/// `IAC SB MSDP VAR "ROOM" VAL TABLE_OPEN VAR "VNUM" VAL "6008" TABLE_CLOSE IAC SE`
+/// `IAC SB MSDP VAR "EXITS" VAL ARRAY_OPEN VAL "n" VAL "s" ARRAY_CLOSE IAC SE`
+///
+/// References:
+/// - https://tintin.mudhalla.net/protocols/msdp/
+
+use crate::error::{MushError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// MSDP telnet option number
+pub const TELOPT_MSDP: u8 = 69;
+
+/// MSDP structure delimiters
+pub const MSDP_VAR: u8 = 1;
+pub const MSDP_VAL: u8 = 2;
+pub const MSDP_TABLE_OPEN: u8 = 3;
+pub const MSDP_TABLE_CLOSE: u8 = 4;
+pub const MSDP_ARRAY_OPEN: u8 = 5;
+pub const MSDP_ARRAY_CLOSE: u8 = 6;
+
+/// Maximum table/array nesting accepted from the server
+const MAX_DEPTH: usize = 32;
+
+/// A (possibly nested) MSDP value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MsdpValue {
+    String(String),
+    Array(Vec<MsdpValue>),
+    Table(BTreeMap<String, MsdpValue>),
+}
+
+impl MsdpValue {
+    /// Get the value as a string, if it is one
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MsdpValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Flatten the value into `(name, string)` pairs
+    ///
+    /// Table entries become `name.KEY` and array items `name.1`, `name.2`, ...
+    pub fn flatten(&self, name: &str) -> Vec<(String, String)> {
+        let mut out = Vec::new();
+        self.flatten_into(name, &mut out);
+        out
+    }
+
+    fn flatten_into(&self, name: &str, out: &mut Vec<(String, String)>) {
+        match self {
+            MsdpValue::String(s) => out.push((name.to_string(), s.clone())),
+            MsdpValue::Array(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    item.flatten_into(&format!("{}.{}", name, i + 1), out);
+                }
+            }
+            MsdpValue::Table(entries) => {
+                for (key, value) in entries {
+                    value.flatten_into(&format!("{}.{}", name, key), out);
+                }
+            }
+        }
+    }
+
+    /// Append the encoded value (without the leading VAL) to `out`
+    fn encode_into(&self, out: &mut Vec<u8>) {
+        match self {
+            MsdpValue::String(s) => out.extend_from_slice(s.as_bytes()),
+            MsdpValue::Array(items) => {
+                out.push(MSDP_ARRAY_OPEN);
+                for item in items {
+                    out.push(MSDP_VAL);
+                    item.encode_into(out);
+                }
+                out.push(MSDP_ARRAY_CLOSE);
+            }
+            MsdpValue::Table(entries) => {
+                out.push(MSDP_TABLE_OPEN);
+                for (key, value) in entries {
+                    out.push(MSDP_VAR);
+                    out.extend_from_slice(key.as_bytes());
+                    out.push(MSDP_VAL);
+                    value.encode_into(out);
+                }
+                out.push(MSDP_TABLE_CLOSE);
+            }
+        }
+    }
+}
+
+impl From<&str> for MsdpValue {
+    fn from(s: &str) -> Self {
+        MsdpValue::String(s.to_string())
+    }
+}
+
+/// Commands a client may send to an MSDP server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MsdpCommand {
+    /// Request a list (e.g. "COMMANDS", "REPORTABLE_VARIABLES")
+    List,
+    /// Ask the server to report variables whenever they change
+    Report,
+    /// Ask the server to send variables once
+    Send,
+    /// Stop reporting variables
+    Unreport,
+}
+
+impl MsdpCommand {
+    /// Get the command name sent on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MsdpCommand::List => "LIST",
+            MsdpCommand::Report => "REPORT",
+            MsdpCommand::Send => "SEND",
+            MsdpCommand::Unreport => "UNREPORT",
+        }
+    }
+
+    /// Encode the command with its arguments as a subnegotiation payload
+    pub fn encode(&self, values: &[String]) -> Vec<u8> {
+        let value = match values {
+            [single] => MsdpValue::String(single.clone()),
+            _ => MsdpValue::Array(values.iter().map(|v| MsdpValue::String(v.clone())).collect()),
+        };
+        encode(self.as_str(), &value)
+    }
+}
+
+impl fmt::Display for MsdpCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for MsdpCommand {
+    type Err = MushError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_uppercase().as_str() {
+            "LIST" => Ok(MsdpCommand::List),
+            "REPORT" => Ok(MsdpCommand::Report),
+            "SEND" => Ok(MsdpCommand::Send),
+            "UNREPORT" => Ok(MsdpCommand::Unreport),
+            _ => Err(MushError::ValidationError {
+                field: "msdp".to_string(),
+                reason: format!("Unknown MSDP command '{}'", s),
+            }),
+        }
+    }
+}
+
+/// Encode a single variable as a subnegotiation payload
+pub fn encode(name: &str, value: &MsdpValue) -> Vec<u8> {
+    let mut out = vec![MSDP_VAR];
+    out.extend_from_slice(name.as_bytes());
+    out.push(MSDP_VAL);
+    value.encode_into(&mut out);
+    out
+}
+
+/// Parse an MSDP subnegotiation payload into its variables
+///
+/// A variable followed by several VALs is returned as an array.
+///
+/// # Errors
+/// - `MushError::ValidationError`: Malformed or unbalanced structure
+pub fn parse(data: &[u8]) -> Result<Vec<(String, MsdpValue)>> {
+    let mut parser = Parser { data, pos: 0 };
+    let vars = parser.parse_vars(0)?;
+
+    if parser.pos < data.len() {
+        return Err(parser.error("Unexpected data after last variable"));
+    }
+
+    Ok(vars)
+}
+
+/// Recursive descent parser over the payload bytes
+struct Parser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> MushError {
+        MushError::ValidationError {
+            field: "msdp".to_string(),
+            reason: format!("{} at byte {}", reason, self.pos),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    /// Read plain bytes up to the next delimiter
+    fn read_text(&mut self) -> String {
+        let start = self.pos;
+        while let Some(byte) = self.peek() {
+            if (MSDP_VAR..=MSDP_ARRAY_CLOSE).contains(&byte) {
+                break;
+            }
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.data[start..self.pos]).into_owned()
+    }
+
+    /// Parse VAR/VAL pairs until the end of input or a TABLE_CLOSE
+    fn parse_vars(&mut self, depth: usize) -> Result<Vec<(String, MsdpValue)>> {
+        let mut vars: Vec<(String, MsdpValue)> = Vec::new();
+
+        while let Some(byte) = self.peek() {
+            match byte {
+                MSDP_VAR => {
+                    self.pos += 1;
+                    let name = self.read_text();
+                    let mut values = Vec::new();
+                    while self.peek() == Some(MSDP_VAL) {
+                        self.pos += 1;
+                        values.push(self.parse_value(depth)?);
+                    }
+
+                    let value = match values.len() {
+                        0 => MsdpValue::String(String::new()),
+                        1 => values.pop().expect("one value"),
+                        _ => MsdpValue::Array(values),
+                    };
+                    vars.push((name, value));
+                }
+                MSDP_TABLE_CLOSE if depth > 0 => break,
+                _ => return Err(self.error("Expected VAR")),
+            }
+        }
+
+        Ok(vars)
+    }
+
+    /// Parse the value following a VAL
+    fn parse_value(&mut self, depth: usize) -> Result<MsdpValue> {
+        if depth >= MAX_DEPTH {
+            return Err(self.error("Nesting too deep"));
+        }
+
+        match self.peek() {
+            Some(MSDP_TABLE_OPEN) => {
+                self.pos += 1;
+                let entries = self.parse_vars(depth + 1)?;
+                if self.peek() != Some(MSDP_TABLE_CLOSE) {
+                    return Err(self.error("Unterminated table"));
+                }
+                self.pos += 1;
+                Ok(MsdpValue::Table(entries.into_iter().collect()))
+            }
+            Some(MSDP_ARRAY_OPEN) => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(MSDP_VAL) => {
+                            self.pos += 1;
+                            items.push(self.parse_value(depth + 1)?);
+                        }
+                        Some(MSDP_ARRAY_CLOSE) => {
+                            self.pos += 1;
+                            break;
+                        }
+                        _ => return Err(self.error("Unterminated array")),
+                    }
+                }
+                Ok(MsdpValue::Array(items))
+            }
+            _ => Ok(MsdpValue::String(self.read_text())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(parts: &[&[u8]]) -> Vec<u8> {
+        parts.concat()
+    }
+
+    #[test]
+    fn test_parse_simple() {
+        let data = payload(&[&[MSDP_VAR], b"HEALTH", &[MSDP_VAL], b"100"]);
+        let vars = parse(&data).unwrap();
+        assert_eq!(vars, vec![("HEALTH".to_string(), MsdpValue::from("100"))]);
+    }
+
+    #[test]
+    fn test_parse_multiple_vars_and_vals() {
+        let data = payload(&[
+            &[MSDP_VAR], b"HEALTH", &[MSDP_VAL], b"90",
+            &[MSDP_VAR], b"REPORTABLE", &[MSDP_VAL], b"HEALTH", &[MSDP_VAL], b"MANA",
+        ]);
+        let vars = parse(&data).unwrap();
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars[0].1, MsdpValue::from("90"));
+        assert_eq!(
+            vars[1].1,
+            MsdpValue::Array(vec![MsdpValue::from("HEALTH"), MsdpValue::from("MANA")])
+        );
+    }
+
+    #[test]
+    fn test_parse_nested() {
+        let data = payload(&[
+            &[MSDP_VAR], b"ROOM", &[MSDP_VAL, MSDP_TABLE_OPEN],
+            &[MSDP_VAR], b"VNUM", &[MSDP_VAL], b"6008",
+            &[MSDP_VAR], b"EXITS", &[MSDP_VAL, MSDP_ARRAY_OPEN],
+            &[MSDP_VAL], b"n", &[MSDP_VAL], b"s",
+            &[MSDP_ARRAY_CLOSE, MSDP_TABLE_CLOSE],
+        ]);
+        let vars = parse(&data).unwrap();
+        assert_eq!(vars.len(), 1);
+
+        let MsdpValue::Table(room) = &vars[0].1 else {
+            panic!("expected table");
+        };
+        assert_eq!(room["VNUM"], MsdpValue::from("6008"));
+        assert_eq!(
+            room["EXITS"],
+            MsdpValue::Array(vec![MsdpValue::from("n"), MsdpValue::from("s")])
+        );
+
+        let flat = vars[0].1.flatten("ROOM");
+        assert_eq!(
+            flat,
+            vec![
+                ("ROOM.EXITS.1".to_string(), "n".to_string()),
+                ("ROOM.EXITS.2".to_string(), "s".to_string()),
+                ("ROOM.VNUM".to_string(), "6008".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert!(parse(b"HEALTH").is_err());
+        assert!(parse(&payload(&[&[MSDP_VAR], b"X", &[MSDP_VAL, MSDP_TABLE_OPEN]])).is_err());
+        assert!(parse(&payload(&[&[MSDP_VAR], b"X", &[MSDP_VAL, MSDP_ARRAY_OPEN, MSDP_VAL], b"1"])).is_err());
+        assert!(parse(&[MSDP_VAR, MSDP_VAL, MSDP_TABLE_CLOSE]).is_err());
+    }
+
+    #[test]
+    fn test_encode_roundtrip() {
+        let value = MsdpValue::Table(BTreeMap::from([
+            ("NAME".to_string(), MsdpValue::from("Bubba")),
+            ("LIST".to_string(), MsdpValue::Array(vec![MsdpValue::from("a"), MsdpValue::from("b")])),
+        ]));
+        let encoded = encode("CHAR", &value);
+        assert_eq!(parse(&encoded).unwrap(), vec![("CHAR".to_string(), value)]);
+    }
+
+    #[test]
+    fn test_encode_commands() {
+        let list = MsdpCommand::List.encode(&["COMMANDS".to_string()]);
+        assert_eq!(list, payload(&[&[MSDP_VAR], b"LIST", &[MSDP_VAL], b"COMMANDS"]));
+
+        let report = MsdpCommand::Report.encode(&["HEALTH".to_string(), "MANA".to_string()]);
+        assert_eq!(
+            report,
+            payload(&[
+                &[MSDP_VAR], b"REPORT", &[MSDP_VAL, MSDP_ARRAY_OPEN],
+                &[MSDP_VAL], b"HEALTH", &[MSDP_VAL], b"MANA", &[MSDP_ARRAY_CLOSE],
+            ])
+        );
+
+        assert_eq!("unreport".parse::<MsdpCommand>().unwrap(), MsdpCommand::Unreport);
+        assert!("FOO".parse::<MsdpCommand>().is_err());
+    }
+}
@@ -0,0 +1,352 @@
+/// MSP (MUD Sound Protocol, option 90) support
+///
+/// Servers embed sound and music requests in the text:
+///
+/// `!!SOUND(thunder.wav V=80 L=1 P=50 T=weather U=http://example.com/sounds/)`
+/// `!!MUSIC(town.mid V=50 L=-1 C=1)`
+///
+/// The markers are removed from the displayed text and returned as
+/// `MspRequest`s; `MspParser` holds back a marker split across reads until
+/// the rest arrives. The file name `Off` stops the sound (or music) playing.
+/// Files are looked up in the world's sounds directory: `T=` names a
+/// subdirectory, `*` and `?` pick a random matching file, and a missing
+/// extension defaults to `.wav` (sounds) or `.mid` (music).
+///
+/// References:
+/// - https://www.zuggsoft.com/zmud/msp.htm
+
+use crate::error::{MushError, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+/// MSP telnet option number
+pub const TELOPT_MSP: u8 = 90;
+
+/// Longest incomplete marker held back for the next read
+const MAX_PENDING_MARKER: usize = 1024;
+
+/// Start of every marker
+const MARKER_PREFIXES: [&str; 2] = ["!!SOUND(", "!!MUSIC("];
+
+static MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"!!(SOUND|MUSIC)\(([^)\r\n]*)\)(\r?\n)?").unwrap());
+
+/// Sound effect or background music
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundKind {
+    Sound,
+    Music,
+}
+
+impl SoundKind {
+    /// Extension assumed for file names without one
+    fn default_extension(self) -> &'static str {
+        match self {
+            SoundKind::Sound => "wav",
+            SoundKind::Music => "mid",
+        }
+    }
+}
+
+/// A `!!SOUND` / `!!MUSIC` request, or a sound played by a trigger
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MspRequest {
+    pub kind: SoundKind,
+    /// File name relative to the sounds directory, or `Off`
+    pub file: String,
+    /// Volume, 0-100
+    pub volume: u8,
+    /// Times to play; -1 repeats until stopped
+    pub loops: i32,
+    /// Sounds only: a sound interrupts one of lower priority (0-100)
+    pub priority: u8,
+    /// Music only: keep playing if the same music is requested again
+    pub continue_music: bool,
+    /// Sound category, also the subdirectory the file is in
+    pub sound_type: Option<String>,
+    /// Where the file can be downloaded from
+    pub url: Option<String>,
+}
+
+impl MspRequest {
+    /// Play `file` once at full volume with default settings
+    pub fn new(kind: SoundKind, file: impl Into<String>) -> Self {
+        Self {
+            kind,
+            file: file.into(),
+            volume: 100,
+            loops: 1,
+            priority: 50,
+            continue_music: true,
+            sound_type: None,
+            url: None,
+        }
+    }
+
+    /// Check whether this stops the current sound or music instead of playing
+    pub fn is_off(&self) -> bool {
+        self.file.eq_ignore_ascii_case("off")
+    }
+
+    /// Parse the text between the parentheses of a marker
+    fn parse(kind: SoundKind, args: &str) -> Option<Self> {
+        let mut words = args.split_whitespace();
+        let mut request = Self::new(kind, words.next()?);
+
+        for word in words {
+            let Some((key, value)) = word.split_once('=') else {
+                debug!("Ignoring MSP parameter without value: {}", word);
+                continue;
+            };
+            match key.to_ascii_uppercase().as_str() {
+                "V" => request.volume = value.parse::<u8>().map_or(request.volume, |v| v.min(100)),
+                "L" => request.loops = value.parse().unwrap_or(request.loops),
+                "P" => request.priority = value.parse::<u8>().map_or(request.priority, |p| p.min(100)),
+                "C" => request.continue_music = value != "0",
+                "T" => request.sound_type = Some(value.to_string()),
+                "U" => request.url = Some(value.to_string()),
+                _ => debug!("Ignoring unknown MSP parameter {}", key),
+            }
+        }
+
+        Some(request)
+    }
+}
+
+/// Streaming MSP marker extraction for received text
+#[derive(Debug, Clone)]
+pub struct MspParser {
+    /// Start of a marker cut off at the end of the last chunk
+    pending: String,
+    /// Whether the text returned so far ended with a line break
+    at_line_start: bool,
+}
+
+impl Default for MspParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MspParser {
+    pub fn new() -> Self {
+        Self {
+            pending: String::new(),
+            at_line_start: true,
+        }
+    }
+
+    /// Remove MSP markers from the next chunk of text (see `extract`)
+    ///
+    /// A possible marker at the end of the chunk is held back and completed
+    /// by the next call.
+    pub fn process(&mut self, text: &str) -> (String, Vec<MspRequest>) {
+        let input = std::mem::take(&mut self.pending) + text;
+        let (mut output, requests) = strip_markers(&input, self.at_line_start);
+
+        if let Some(start) = partial_marker_start(&output) {
+            self.pending = output.split_off(start);
+        }
+        if !output.is_empty() {
+            self.at_line_start = output.ends_with('\n');
+        }
+        (output, requests)
+    }
+}
+
+/// Remove MSP markers from text, returning the remaining text and the requests
+///
+/// A marker alone on its line takes the line ending with it, so no blank
+/// line is left behind.
+pub fn extract(text: &str) -> (String, Vec<MspRequest>) {
+    strip_markers(text, true)
+}
+
+fn strip_markers(text: &str, at_line_start: bool) -> (String, Vec<MspRequest>) {
+    if !text.contains("!!") {
+        return (text.to_string(), Vec::new());
+    }
+
+    let mut output = String::with_capacity(text.len());
+    let mut requests = Vec::new();
+    let mut last = 0;
+
+    for cap in MARKER.captures_iter(text) {
+        let marker = cap.get(0).unwrap();
+        let kind = if &cap[1] == "SOUND" { SoundKind::Sound } else { SoundKind::Music };
+
+        match MspRequest::parse(kind, &cap[2]) {
+            Some(request) => requests.push(request),
+            None => debug!("Ignoring MSP marker without a file: {}", marker.as_str()),
+        }
+
+        output.push_str(&text[last..marker.start()]);
+        let line_start = if output.is_empty() { at_line_start } else { output.ends_with('\n') };
+        if let Some(newline) = cap.get(3).filter(|_| !line_start) {
+            output.push_str(newline.as_str());
+        }
+        last = marker.end();
+    }
+
+    output.push_str(&text[last..]);
+    (output, requests)
+}
+
+/// Offset of an unfinished marker at the end of `text`, if any
+///
+/// Matches a prefix of `!!SOUND(` / `!!MUSIC(`, or one of those followed
+/// by parameters still waiting for their `)`.
+fn partial_marker_start(text: &str) -> Option<usize> {
+    let line_start = text.rfind('\n').map_or(0, |i| i + 1);
+    let limit = text.len().saturating_sub(MAX_PENDING_MARKER);
+
+    text[line_start..]
+        .match_indices('!')
+        .map(|(i, _)| line_start + i)
+        .filter(|&start| start >= limit)
+        .find(|&start| {
+            let tail = &text[start..];
+            MARKER_PREFIXES.iter().any(|prefix| {
+                prefix.starts_with(tail) || (tail.starts_with(prefix) && !tail.contains([')', '\r']))
+            })
+        })
+}
+
+/// Find the file a request plays inside `dir`
+///
+/// # Errors
+/// - `MushError::ValidationError`: The name leaves the sounds directory
+/// - `MushError::FileNotFound`: No such file
+pub fn resolve(dir: &Path, request: &MspRequest) -> Result<PathBuf> {
+    let mut relative = PathBuf::new();
+    if let Some(sound_type) = request.sound_type.as_deref().filter(|_| !request.file.contains('/')) {
+        relative.push(sound_type);
+    }
+    relative.push(&request.file);
+
+    // Server-supplied names must stay inside the sounds directory
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(MushError::ValidationError {
+            field: "sound".to_string(),
+            reason: format!("'{}' is outside the sounds directory", relative.display()),
+        });
+    }
+
+    if relative.extension().is_none() {
+        relative.set_extension(request.kind.default_extension());
+    }
+    let path = dir.join(&relative);
+
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    if name.contains(['*', '?']) {
+        let parent = path.parent().unwrap_or(dir);
+        let pattern = Regex::new(&format!("^{}$", regex::escape(name).replace(r"\*", ".*").replace(r"\?", ".")))
+            .map_err(|e| MushError::InvalidRegex { pattern: name.to_string(), source: e })?;
+        let matches: Vec<PathBuf> = std::fs::read_dir(parent)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|p| p.is_file() && p.file_name().and_then(|n| n.to_str()).is_some_and(|n| pattern.is_match(n)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        return match matches.len() {
+            0 => Err(MushError::FileNotFound(path.display().to_string())),
+            n => Ok(matches[fastrand::usize(..n)].clone()),
+        };
+    }
+
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(MushError::FileNotFound(path.display().to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_markers() {
+        let (text, requests) = extract(concat!(
+            "Thunder rumbles.\r\n",
+            "!!SOUND(weather/thunder.wav V=80 L=2 P=70 U=http://example.com/sounds/)\r\n",
+            "The rain starts. !!MUSIC(rain L=-1 C=0 T=weather)\r\n",
+            "!!SOUND() !!SOUND(Off)",
+        ));
+
+        assert_eq!(text, "Thunder rumbles.\r\nThe rain starts. \r\n ");
+        assert_eq!(requests.len(), 3);
+
+        assert_eq!(requests[0].kind, SoundKind::Sound);
+        assert_eq!(requests[0].file, "weather/thunder.wav");
+        assert_eq!((requests[0].volume, requests[0].loops, requests[0].priority), (80, 2, 70));
+        assert_eq!(requests[0].url.as_deref(), Some("http://example.com/sounds/"));
+
+        assert_eq!(requests[1].kind, SoundKind::Music);
+        assert_eq!((requests[1].loops, requests[1].continue_music), (-1, false));
+        assert_eq!(requests[1].sound_type.as_deref(), Some("weather"));
+
+        assert!(requests[2].is_off());
+        assert_eq!(extract("no sounds here").0, "no sounds here");
+    }
+
+    #[test]
+    fn test_markers_split_across_reads() {
+        let mut parser = MspParser::new();
+
+        let (text, requests) = parser.process("You hear thunder. !!SOU");
+        assert_eq!(text, "You hear thunder. ");
+        assert!(requests.is_empty());
+
+        let (text, requests) = parser.process("ND(thunder V=5");
+        assert_eq!(text, "");
+        assert!(requests.is_empty());
+
+        let (text, requests) = parser.process("0)\r\nWow!\r\n!");
+        assert_eq!(text, "\r\nWow!\r\n");
+        assert_eq!(requests[0].volume, 50);
+
+        // A lone marker line still takes its line ending, even when split
+        let (text, requests) = parser.process("!MUSIC(town)\r\nThe end.");
+        assert_eq!(text, "The end.");
+        assert_eq!(requests[0].file, "town");
+
+        // Not a marker after all
+        assert_eq!(parser.process(" Yes!!").0, " Yes");
+        assert_eq!(parser.process(" ok").0, "!! ok");
+    }
+
+    #[test]
+    fn test_resolve() {
+        let dir = std::env::temp_dir().join(format!("macmush-msp-{}", fastrand::u64(..)));
+        std::fs::create_dir_all(dir.join("weather")).unwrap();
+        std::fs::write(dir.join("weather/rain1.wav"), b"RIFF").unwrap();
+        std::fs::write(dir.join("weather/rain2.wav"), b"RIFF").unwrap();
+        std::fs::write(dir.join("town.mid"), b"MThd").unwrap();
+
+        // Default extension, T= subdirectory and wildcards
+        let music = MspRequest::new(SoundKind::Music, "town");
+        assert_eq!(resolve(&dir, &music).unwrap(), dir.join("town.mid"));
+
+        let mut rain = MspRequest::new(SoundKind::Sound, "rain?");
+        rain.sound_type = Some("weather".to_string());
+        let path = resolve(&dir, &rain).unwrap();
+        assert!(path == dir.join("weather/rain1.wav") || path == dir.join("weather/rain2.wav"));
+
+        let missing = MspRequest::new(SoundKind::Sound, "weather/hail.wav");
+        assert!(matches!(resolve(&dir, &missing), Err(MushError::FileNotFound(_))));
+
+        for escape in ["../secret.wav", "/etc/passwd"] {
+            let request = MspRequest::new(SoundKind::Sound, escape);
+            assert!(matches!(resolve(&dir, &request), Err(MushError::ValidationError { .. })));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
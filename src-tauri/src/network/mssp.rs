@@ -0,0 +1,193 @@
+/// MSSP (Mud Server Status Protocol, option 70) support
+///
+/// The server sends its status as VAR/VAL pairs in a single subnegotiation:
+///
+/// `IAC SB MSSP VAR "PLAYERS" VAL "52" VAR "UPTIME" VAL "1234567890" IAC SE`
+///
+/// A variable with several VALs (e.g. multiple PORTs) becomes a list.
+///
+/// References:
+/// - https://tintin.mudhalla.net/protocols/mssp/
+
+use crate::error::{MushError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// MSSP telnet option number
+pub const TELOPT_MSSP: u8 = 70;
+
+/// MSSP delimiters
+pub const MSSP_VAR: u8 = 1;
+pub const MSSP_VAL: u8 = 2;
+
+/// A typed MSSP value
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MsspValue {
+    Integer(i64),
+    Text(String),
+    List(Vec<MsspValue>),
+}
+
+impl MsspValue {
+    /// Type a raw value: integers become `Integer`, everything else `Text`
+    fn from_raw(raw: &str) -> Self {
+        match raw.trim().parse::<i64>() {
+            Ok(n) => MsspValue::Integer(n),
+            Err(_) => MsspValue::Text(raw.to_string()),
+        }
+    }
+
+    /// Get the value as an integer, if it is one
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            MsspValue::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Get the value as text (integers are formatted)
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            MsspValue::Integer(n) => Some(n.to_string()),
+            MsspValue::Text(s) => Some(s.clone()),
+            MsspValue::List(_) => None,
+        }
+    }
+}
+
+/// Server status reported via MSSP, keyed by upper-case variable name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct MsspData {
+    values: BTreeMap<String, MsspValue>,
+}
+
+impl MsspData {
+    /// Parse an MSSP subnegotiation payload
+    ///
+    /// # Errors
+    /// - `MushError::ValidationError`: Payload does not start with VAR or has a VAL without a VAR
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        if data.first() != Some(&MSSP_VAR) {
+            return Err(MushError::ValidationError {
+                field: "mssp".to_string(),
+                reason: "Payload must start with VAR".to_string(),
+            });
+        }
+
+        let mut values = BTreeMap::new();
+        let mut name: Option<String> = None;
+        let mut vals: Vec<MsspValue> = Vec::new();
+
+        let mut flush = |name: Option<String>, vals: &mut Vec<MsspValue>| {
+            if let Some(name) = name {
+                let value = match vals.len() {
+                    0 => MsspValue::Text(String::new()),
+                    1 => vals.pop().expect("one value"),
+                    _ => MsspValue::List(std::mem::take(vals)),
+                };
+                vals.clear();
+                values.insert(name.to_uppercase(), value);
+            }
+        };
+
+        let mut pos = 0;
+        while pos < data.len() {
+            let delimiter = data[pos];
+            let start = pos + 1;
+            let end = data[start..]
+                .iter()
+                .position(|&b| b == MSSP_VAR || b == MSSP_VAL)
+                .map_or(data.len(), |i| start + i);
+            let text = String::from_utf8_lossy(&data[start..end]).into_owned();
+
+            if delimiter == MSSP_VAR {
+                flush(name.take(), &mut vals);
+                name = Some(text);
+            } else {
+                vals.push(MsspValue::from_raw(&text));
+            }
+
+            pos = end;
+        }
+        flush(name, &mut vals);
+
+        Ok(Self { values })
+    }
+
+    /// Get a variable (case-insensitive)
+    pub fn get(&self, name: &str) -> Option<&MsspValue> {
+        self.values.get(&name.to_uppercase())
+    }
+
+    /// Get all variables
+    pub fn values(&self) -> &BTreeMap<String, MsspValue> {
+        &self.values
+    }
+
+    /// Check whether any variables were reported
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Server name (NAME)
+    pub fn name(&self) -> Option<String> {
+        self.get("NAME").and_then(MsspValue::as_text)
+    }
+
+    /// Number of players online (PLAYERS)
+    pub fn players(&self) -> Option<i64> {
+        self.get("PLAYERS").and_then(MsspValue::as_integer)
+    }
+
+    /// Unix time the server was started (UPTIME)
+    pub fn uptime(&self) -> Option<i64> {
+        self.get("UPTIME").and_then(MsspValue::as_integer)
+    }
+
+    /// Codebase name (CODEBASE)
+    pub fn codebase(&self) -> Option<String> {
+        self.get("CODEBASE").and_then(MsspValue::as_text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mssp() {
+        let data = b"\x01NAME\x02Test MUD\x01PLAYERS\x0252\x01UPTIME\x021234567890\x01CODEBASE\x02Merc 2.2\x01PORT\x024000\x024001";
+        let mssp = MsspData::parse(data).unwrap();
+
+        assert_eq!(mssp.name(), Some("Test MUD".to_string()));
+        assert_eq!(mssp.players(), Some(52));
+        assert_eq!(mssp.uptime(), Some(1234567890));
+        assert_eq!(mssp.codebase(), Some("Merc 2.2".to_string()));
+        assert_eq!(
+            mssp.get("port"),
+            Some(&MsspValue::List(vec![MsspValue::Integer(4000), MsspValue::Integer(4001)]))
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_value() {
+        let mssp = MsspData::parse(b"\x01CONTACT\x01PLAYERS\x023").unwrap();
+        assert_eq!(mssp.get("CONTACT"), Some(&MsspValue::Text(String::new())));
+        assert_eq!(mssp.players(), Some(3));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(MsspData::parse(b"").is_err());
+        assert!(MsspData::parse(b"\x02orphan").is_err());
+    }
+
+    #[test]
+    fn test_serialize_as_map() {
+        let mssp = MsspData::parse(b"\x01NAME\x02Test\x01PLAYERS\x025").unwrap();
+        let json = serde_json::to_value(&mssp).unwrap();
+        assert_eq!(json, serde_json::json!({"NAME": "Test", "PLAYERS": 5}));
+    }
+}
@@ -3,9 +3,16 @@
 /// Provides parsing and handling for MXP tags, which enable MUDs to send
 /// formatted text with clickable links, colors, fonts, and other rich content.
 ///
+/// Once MXP is negotiated, received text goes through `MxpParser::process`:
+/// line mode sequences (ESC[#z) and tags are removed from the visible text,
+/// `&entity;` references are expanded, and the styles the tags applied come
+/// back as spans over the visible text. Tags, entities and mode sequences
+/// split across reads are held back until the rest arrives.
+///
 /// Reference: https://www.zuggsoft.com/zmud/mxp.htm
 
-use crate::error::{MushError, Result};
+use crate::error::Result;
+use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -14,6 +21,18 @@ use tracing::{debug, info};
 /// MXP telnet option number
 pub const TELOPT_MXP: u8 = 91;
 
+/// Longest incomplete tag held back for the next read; longer ones are shown as text
+const MAX_PENDING_TAG: usize = 1024;
+
+/// Longest entity name looked for after `&`
+const MAX_ENTITY_NAME: usize = 32;
+
+/// `&text;` in a link's href or hint
+static LINK_TEXT: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)&text;").unwrap());
+
+/// `&attribute;` reference in a custom element's definition
+static ATTRIBUTE_REF: Lazy<Regex> = Lazy::new(|| Regex::new(r"&([A-Za-z_][\w.-]*);").unwrap());
+
 /// MXP line modes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MxpMode {
@@ -67,7 +86,9 @@ pub enum MxpTag {
     /// Hyperlink (web, telnet, mailto)
     Anchor { href: String, hint: Option<String> },
     /// Send command to MUD
-    Send { href: String, hint: Option<String>, prompt: Option<String> },
+    ///
+    /// An empty `href` sends the link text; `|` separates the commands of a menu.
+    Send { href: String, hint: Option<String>, prompt: bool },
     /// Line break
     Br,
     /// Paragraph break
@@ -82,21 +103,259 @@ pub enum MxpTag {
     Custom { name: String, attrs: HashMap<String, String> },
 }
 
+impl MxpTag {
+    /// Check whether the tag may be used on open-mode lines
+    pub fn is_open(&self) -> bool {
+        matches!(
+            self,
+            Self::Bold | Self::Italic | Self::Underline | Self::Strikethrough | Self::Color { .. } | Self::Font { .. } | Self::Br
+        )
+    }
+
+    /// Check whether the tag stands alone instead of wrapping text
+    fn is_standalone(&self) -> bool {
+        matches!(self, Self::Br | Self::P | Self::Expire { .. } | Self::Version | Self::Support { .. })
+    }
+}
+
 /// Parsed MXP element with content
 #[derive(Debug, Clone)]
 pub struct MxpElement {
     pub tag: MxpTag,
+    /// Visible text between the tag and its closing tag (up to the end of
+    /// the processed text if it is still open)
     pub content: String,
+    /// Line the tag appeared on, counted from when MXP was enabled
     pub line_number: usize,
 }
 
+/// Custom element defined with `<!ELEMENT>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementDefinition {
+    /// Tags the element stands for, e.g. `<color red><b>`
+    pub definition: String,
+    /// Attribute names (lowercase) with their defaults, in positional order
+    pub attributes: Vec<(String, String)>,
+    /// May be used on open-mode lines
+    pub open: bool,
+    /// Has no closing tag
+    pub empty: bool,
+}
+
+/// Text style applied by MXP tags
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MxpStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub fore: Option<String>,
+    pub back: Option<String>,
+    pub font: Option<String>,
+    pub size: Option<String>,
+}
+
+impl MxpStyle {
+    /// Add the effect of a tag to the style
+    fn apply(&mut self, tag: &MxpTag) {
+        match tag {
+            MxpTag::Bold => self.bold = true,
+            MxpTag::Italic => self.italic = true,
+            MxpTag::Underline => self.underline = true,
+            MxpTag::Strikethrough => self.strikethrough = true,
+            MxpTag::Color { fore, back } => {
+                if fore.is_some() {
+                    self.fore = fore.clone();
+                }
+                if back.is_some() {
+                    self.back = back.clone();
+                }
+            }
+            MxpTag::Font { face, size, color } => {
+                if face.is_some() {
+                    self.font = face.clone();
+                }
+                if size.is_some() {
+                    self.size = size.clone();
+                }
+                if color.is_some() {
+                    self.fore = color.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Styled run of visible text
+///
+/// `start` and `end` count UTF-16 code units, so they can be used as
+/// JavaScript string indices by the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MxpSpan {
+    pub start: usize,
+    pub end: usize,
+    pub style: MxpStyle,
+}
+
+/// What clicking an MXP link does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MxpLinkKind {
+    /// Send a command to the MUD (`<send>`)
+    Send,
+    /// Open a web page (`<a>`)
+    Url,
+}
+
+/// Clickable run of visible text made by `<send>` or `<a>`
+///
+/// Offsets count UTF-16 code units, like `MxpSpan`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MxpLink {
+    pub start: usize,
+    pub end: usize,
+    pub kind: MxpLinkKind,
+    /// Commands to choose from (more than one makes a pop-up menu), or the URL
+    pub hrefs: Vec<String>,
+    /// Menu label for each href
+    pub labels: Vec<String>,
+    /// Tooltip
+    pub hint: Option<String>,
+    /// Put the command in the input box for editing instead of sending it
+    pub prompt: bool,
+}
+
+impl MxpLink {
+    /// Link for a `<send>` or `<a>` tag around `text`
+    ///
+    /// `&text;` in the href and hint stands for the link text. Anchors
+    /// only link to http(s) and mailto URLs.
+    fn new(tag: &MxpTag, text: &str, start: usize, end: usize) -> Option<Self> {
+        let fill = |value: &str| LINK_TEXT.replace_all(value, regex::NoExpand(text)).into_owned();
+
+        let (kind, hrefs, hint, prompt) = match tag {
+            MxpTag::Send { href, hint, prompt } => {
+                let href = if href.is_empty() { text.to_string() } else { fill(href) };
+                let hrefs: Vec<String> = href.split('|').map(|h| h.trim().to_string()).collect();
+                (MxpLinkKind::Send, hrefs, hint.as_deref().map(fill), *prompt)
+            }
+            MxpTag::Anchor { href, hint } => {
+                let href = fill(href);
+                let scheme = href.split_once(':').map(|(scheme, _)| scheme.to_lowercase());
+                if !matches!(scheme.as_deref(), Some("http" | "https" | "mailto")) {
+                    debug!("Ignoring MXP link to {}", href);
+                    return None;
+                }
+                (MxpLinkKind::Url, vec![href], hint.as_deref().map(fill), false)
+            }
+            _ => return None,
+        };
+
+        if hrefs.iter().all(String::is_empty) {
+            return None;
+        }
+
+        // With one hint more than hrefs the first is the tooltip, the rest label the menu
+        let hints: Vec<String> = hint.iter().flat_map(|h| h.split('|')).map(str::to_string).collect();
+        let (hint, labels) = if hints.len() == hrefs.len() + 1 {
+            (Some(hints[0].clone()), hints[1..].to_vec())
+        } else if hints.len() == hrefs.len() && hrefs.len() > 1 {
+            (None, hints)
+        } else {
+            (hint, hrefs.clone())
+        };
+
+        Some(Self {
+            start,
+            end,
+            kind,
+            hrefs,
+            labels,
+            hint,
+            prompt,
+        })
+    }
+}
+
+/// Received text after MXP processing
+#[derive(Debug, Clone, Default)]
+pub struct MxpOutput {
+    /// Text to display: tags and mode sequences removed, entities expanded
+    pub text: String,
+    /// Styled runs of `text`; unstyled text has no span
+    pub spans: Vec<MxpSpan>,
+    /// Links in `text`, in order
+    pub links: Vec<MxpLink>,
+    /// Tags found, in the order they were opened
+    pub elements: Vec<MxpElement>,
+}
+
+/// Tag (or custom element) waiting for its closing tag
+#[derive(Debug, Clone)]
+struct OpenTag {
+    /// Canonical tag name the closing tag must match
+    name: String,
+    /// Tags in effect until it closes (several for a custom element)
+    tags: Vec<MxpTag>,
+    /// Opened on an open-mode line, so it closes at the end of the line
+    open_mode: bool,
+    /// Index of its element in the output being built
+    element: Option<usize>,
+    /// Where its text starts in the output being built (0 if opened in an earlier one)
+    start: usize,
+}
+
+/// Word, quoted string or `key=value` pair inside a tag
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Token {
+    key: Option<String>,
+    value: String,
+    quoted: bool,
+}
+
+impl Token {
+    /// Check whether this is the bare word `flag` (e.g. `OPEN`, `PROMPT`)
+    fn is_flag(&self, flag: &str) -> bool {
+        self.key.is_none() && !self.quoted && self.value.eq_ignore_ascii_case(flag)
+    }
+}
+
+/// What follows an ESC in received text
+enum Escape {
+    /// MXP mode sequence `ESC[#z` of the given length
+    Mode(usize, u8),
+    /// Cut off at the end of the text
+    Incomplete,
+    /// Anything else (e.g. an ANSI colour code), passed through
+    Other,
+}
+
+/// What follows an `&` in received text
+enum Entity {
+    /// Reference of the given length and its replacement
+    Expanded(usize, String),
+    /// Cut off at the end of the text
+    Incomplete,
+    /// Not a known entity, shown as typed
+    Literal,
+}
+
 /// MXP parser state
 #[derive(Debug)]
 pub struct MxpParser {
     mode: MxpMode,
+    /// Mode each new line starts in (changed by the locked modes)
+    default_mode: MxpMode,
     enabled: bool,
-    elements: HashMap<String, String>,  // Custom element definitions
+    elements: HashMap<String, ElementDefinition>,  // Custom element definitions
     entities: HashMap<String, String>,   // Custom entity definitions
+    /// Tags still open, innermost last
+    open_tags: Vec<OpenTag>,
+    /// Incomplete tag, entity or mode sequence carried over to the next call
+    pending: String,
+    /// Lines processed since MXP was enabled
+    line_number: usize,
 }
 
 impl MxpParser {
@@ -104,9 +363,13 @@ impl MxpParser {
     pub fn new() -> Self {
         Self {
             mode: MxpMode::Open,
+            default_mode: MxpMode::Open,
             enabled: false,
             elements: HashMap::new(),
             entities: HashMap::new(),
+            open_tags: Vec::new(),
+            pending: String::new(),
+            line_number: 0,
         }
     }
 
@@ -117,8 +380,12 @@ impl MxpParser {
     }
 
     /// Disable MXP processing
+    ///
+    /// Open tags and held-back text are dropped; definitions are kept.
     pub fn disable(&mut self) {
         self.enabled = false;
+        self.open_tags.clear();
+        self.pending.clear();
         debug!("MXP disabled");
     }
 
@@ -128,6 +395,8 @@ impl MxpParser {
     }
 
     /// Get current mode
+    ///
+    /// The locked modes are reported as the mode they lock in.
     pub fn mode(&self) -> MxpMode {
         self.mode
     }
@@ -135,182 +404,708 @@ impl MxpParser {
     /// Set MXP mode
     pub fn set_mode(&mut self, mode: MxpMode) {
         debug!("MXP mode changed: {:?} -> {:?}", self.mode, mode);
-        self.mode = mode;
 
-        // Reset mode clears state
-        if mode == MxpMode::Reset {
-            self.mode = MxpMode::Open;
+        match mode {
+            MxpMode::Open | MxpMode::Secure | MxpMode::Locked => self.mode = mode,
+            // Reset mode clears state
+            MxpMode::Reset => {
+                self.open_tags.clear();
+                self.default_mode = MxpMode::Open;
+                self.mode = MxpMode::Open;
+            }
+            MxpMode::LockedOpen => self.lock_mode(MxpMode::Open),
+            MxpMode::LockedSecure => self.lock_mode(MxpMode::Secure),
+            MxpMode::LockedLocked => self.lock_mode(MxpMode::Locked),
         }
     }
 
+    /// Make `mode` the mode of this and all following lines
+    fn lock_mode(&mut self, mode: MxpMode) {
+        self.default_mode = mode;
+        self.mode = mode;
+    }
+
+    /// Get a custom element defined with `<!ELEMENT>`
+    pub fn element(&self, name: &str) -> Option<&ElementDefinition> {
+        self.elements.get(&name.to_lowercase())
+    }
+
+    /// Get a custom entity defined with `<!ENTITY>`
+    pub fn entity(&self, name: &str) -> Option<&str> {
+        self.entities.get(&name.to_lowercase()).map(String::as_str)
+    }
+
     /// Parse MXP mode sequence: ESC[#z
+    ///
+    /// Applies the first mode sequence in `text` and returns the offset just
+    /// past it.
     pub fn parse_mode_sequence(&mut self, text: &str) -> Option<usize> {
-        let re = Regex::new(r"\x1B\[(\d+)z").ok()?;
-        if let Some(cap) = re.captures(text) {
-            if let Ok(mode_num) = cap[1].parse::<u8>() {
-                if let Some(mode) = MxpMode::from_u8(mode_num) {
-                    self.set_mode(mode);
-                    return Some(cap.get(0)?.end());
-                }
+        text.match_indices('\x1B').find_map(|(start, _)| match Self::escape(&text[start..]) {
+            Escape::Mode(len, number) => {
+                let mode = MxpMode::from_u8(number)?;
+                self.set_mode(mode);
+                Some(start + len)
             }
-        }
-        None
+            _ => None,
+        })
     }
 
     /// Parse MXP tags from text
+    ///
+    /// Runs the text through `process` and returns only the tags found.
     pub fn parse(&mut self, text: &str) -> Result<Vec<MxpElement>> {
-        if !self.enabled || self.mode == MxpMode::Locked || self.mode == MxpMode::LockedLocked {
-            // No parsing in locked modes
-            return Ok(vec![]);
+        Ok(self.process(text).elements)
+    }
+
+    /// Apply MXP to received text
+    ///
+    /// Returns the text unchanged while MXP is disabled. Otherwise mode
+    /// sequences and tags are removed, entities expanded and the resulting
+    /// styles returned as spans. Tags open at the end are carried over to
+    /// the next call (except open-mode tags, which close at the end of
+    /// their line).
+    pub fn process(&mut self, text: &str) -> MxpOutput {
+        if !self.enabled {
+            return MxpOutput {
+                text: text.to_string(),
+                ..MxpOutput::default()
+            };
         }
 
-        let mut elements = Vec::new();
+        let input = std::mem::take(&mut self.pending) + text;
+        let mut output = MxpOutput::default();
+        // Style in effect and the byte offset its run started at
+        let mut run = (self.style(), 0);
         let mut pos = 0;
-        let line_number = 0; // TODO: track actual line numbers
 
-        while pos < text.len() {
-            // Check for mode sequence
-            if let Some(end) = self.parse_mode_sequence(&text[pos..]) {
-                pos += end;
-                continue;
-            }
+        while let Some(c) = input[pos..].chars().next() {
+            let rest = &input[pos..];
+            let locked = self.mode == MxpMode::Locked;
 
-            // Look for opening tag
-            if let Some(tag_start) = text[pos..].find('<') {
-                let tag_pos = pos + tag_start;
-
-                // Find closing >
-                if let Some(tag_end_rel) = text[tag_pos + 1..].find('>') {
-                    let tag_end = tag_pos + 1 + tag_end_rel;
-                    let tag_text = &text[tag_pos + 1..tag_end];
-
-                    // Parse the tag
-                    if let Some(element) = self.parse_tag(tag_text, line_number) {
-                        // Extract content between tags (for closing tags)
-                        let content = String::new(); // TODO: extract content between open/close
-
-                        elements.push(MxpElement {
-                            tag: element,
-                            content,
-                            line_number,
-                        });
+            match c {
+                '\x1B' => match Self::escape(rest) {
+                    Escape::Mode(len, number) => {
+                        match MxpMode::from_u8(number) {
+                            Some(mode) => self.set_mode(mode),
+                            None => debug!("Ignoring MXP line tag {}", number),
+                        }
+                        self.restyle(&mut output, &mut run);
+                        pos += len;
+                    }
+                    Escape::Incomplete => {
+                        self.pending = rest.to_string();
+                        break;
                     }
+                    Escape::Other => {
+                        output.text.push('\x1B');
+                        pos += 1;
+                    }
+                },
 
-                    pos = tag_end + 1;
-                } else {
-                    pos = tag_pos + 1;
+                '\n' => {
+                    self.end_line(&mut output);
+                    self.restyle(&mut output, &mut run);
+                    output.text.push('\n');
+                    pos += 1;
                 }
+
+                '<' if !locked => match Self::tag_end(rest) {
+                    Some(end) if rest.as_bytes()[end] == b'>' => {
+                        self.handle_tag(&rest[1..end], &mut output);
+                        self.restyle(&mut output, &mut run);
+                        pos += end + 1;
+                    }
+                    None if rest.len() < MAX_PENDING_TAG => {
+                        self.pending = rest.to_string();
+                        break;
+                    }
+                    _ => {
+                        output.text.push('<');
+                        pos += 1;
+                    }
+                },
+
+                '&' if !locked => match self.expand_entity(rest) {
+                    Entity::Expanded(len, value) => {
+                        output.text.push_str(&value);
+                        pos += len;
+                    }
+                    Entity::Incomplete => {
+                        self.pending = rest.to_string();
+                        break;
+                    }
+                    Entity::Literal => {
+                        output.text.push('&');
+                        pos += 1;
+                    }
+                },
+
+                _ => {
+                    output.text.push(c);
+                    pos += c.len_utf8();
+                }
+            }
+        }
+
+        let (style, start) = run;
+        let end = output.text.len();
+        Self::push_span(&mut output, start, end, style);
+
+        // Tags still open cover the rest of the text and carry on in the next call
+        for open in &mut self.open_tags {
+            Self::finish(&mut output, open);
+            open.element = None;
+            open.start = 0;
+        }
+
+        output.links.sort_by_key(|link| link.start);
+        Self::offsets_to_utf16(&mut output);
+        output
+    }
+
+    /// Visible text of a complete piece of text (e.g. a prompt)
+    ///
+    /// Removes tags and expands entities like `process`, without touching
+    /// the tags open in the received stream.
+    pub fn visible_text(&self, text: &str) -> String {
+        let mut scratch = Self {
+            mode: self.mode,
+            default_mode: self.default_mode,
+            enabled: self.enabled,
+            elements: self.elements.clone(),
+            entities: self.entities.clone(),
+            open_tags: Vec::new(),
+            pending: String::new(),
+            line_number: self.line_number,
+        };
+        let mut output = scratch.process(text);
+        output.text.push_str(&scratch.pending);
+        output.text
+    }
+
+    /// Start a new styled run if the tags in effect have changed
+    fn restyle(&self, output: &mut MxpOutput, run: &mut (MxpStyle, usize)) {
+        let style = self.style();
+        if style != run.0 {
+            let end = output.text.len();
+            let (previous, start) = std::mem::replace(run, (style, end));
+            Self::push_span(output, start, end, previous);
+        }
+    }
+
+    /// Record a styled run unless it is empty or unstyled
+    fn push_span(output: &mut MxpOutput, start: usize, end: usize, style: MxpStyle) {
+        if end > start && style != MxpStyle::default() {
+            output.spans.push(MxpSpan { start, end, style });
+        }
+    }
+
+    /// Convert span and link offsets from bytes to UTF-16 code units
+    fn offsets_to_utf16(output: &mut MxpOutput) {
+        if output.text.is_ascii() {
+            return;
+        }
+
+        let text = &output.text;
+        let utf16 = |byte: usize| text[..byte].encode_utf16().count();
+        for span in &mut output.spans {
+            span.start = utf16(span.start);
+            span.end = utf16(span.end);
+        }
+        for link in &mut output.links {
+            link.start = utf16(link.start);
+            link.end = utf16(link.end);
+        }
+    }
+
+    /// Style resulting from all open tags
+    fn style(&self) -> MxpStyle {
+        let mut style = MxpStyle::default();
+        for tag in self.open_tags.iter().flat_map(|open| &open.tags) {
+            style.apply(tag);
+        }
+        style
+    }
+
+    /// Close open-mode tags and return to the default mode at a newline
+    fn end_line(&mut self, output: &mut MxpOutput) {
+        let mut index = 0;
+        while index < self.open_tags.len() {
+            if self.open_tags[index].open_mode {
+                let open = self.open_tags.remove(index);
+                Self::finish(output, &open);
             } else {
-                break;
+                index += 1;
             }
         }
 
-        Ok(elements)
+        self.mode = self.default_mode;
+        self.line_number += 1;
     }
 
-    /// Parse individual MXP tag
-    fn parse_tag(&self, tag_text: &str, _line: usize) -> Option<MxpTag> {
-        let tag_lower = tag_text.to_lowercase();
-        let parts: Vec<&str> = tag_lower.split_whitespace().collect();
+    /// Fill in the content of a tag's element and add its links
+    ///
+    /// Called when the tag closes, and for tags still open at the end of
+    /// the text processed.
+    fn finish(output: &mut MxpOutput, open: &OpenTag) {
+        let (start, end) = (open.start, output.text.len());
+        let content = &output.text[start..];
+
+        if let Some(index) = open.element {
+            output.elements[index].content = content.to_string();
+        }
+
+        if end > start {
+            let links: Vec<MxpLink> = open.tags.iter().filter_map(|tag| MxpLink::new(tag, content, start, end)).collect();
+            output.links.extend(links);
+        }
+    }
+
+    /// Offset of the `>` or newline ending the tag at the start of `text`
+    ///
+    /// `>` inside quoted attribute values does not end the tag. Returns
+    /// `None` if the text ends first.
+    fn tag_end(text: &str) -> Option<usize> {
+        let mut quote = None;
+        for (i, c) in text.char_indices() {
+            match (c, quote) {
+                ('\n', _) => return Some(i),
+                ('>', None) => return Some(i),
+                ('"' | '\'', None) => quote = Some(c),
+                (c, Some(q)) if c == q => quote = None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Classify the escape sequence at the start of `text`
+    fn escape(text: &str) -> Escape {
+        let Some(params) = text.strip_prefix("\x1B[") else {
+            return if text.len() == 1 { Escape::Incomplete } else { Escape::Other };
+        };
+
+        let digits = params.bytes().take_while(u8::is_ascii_digit).count();
+        match params.as_bytes().get(digits) {
+            None => Escape::Incomplete,
+            Some(b'z') if digits > 0 => match params[..digits].parse() {
+                Ok(number) => Escape::Mode(2 + digits + 1, number),
+                Err(_) => Escape::Other,
+            },
+            Some(_) => Escape::Other,
+        }
+    }
+
+    /// Expand the entity reference at the start of `text` (which starts with `&`)
+    fn expand_entity(&self, text: &str) -> Entity {
+        let name_len = text[1..]
+            .bytes()
+            .take(MAX_ENTITY_NAME)
+            .take_while(|b| b.is_ascii_alphanumeric() || matches!(*b, b'#' | b'_' | b'-' | b'.'))
+            .count();
+
+        match text.as_bytes().get(1 + name_len) {
+            Some(b';') if name_len > 0 => {}
+            None if name_len < MAX_ENTITY_NAME => return Entity::Incomplete,
+            _ => return Entity::Literal,
+        }
+
+        let name = text[1..1 + name_len].to_lowercase();
+        let value = match name.as_str() {
+            "lt" => Some("<".to_string()),
+            "gt" => Some(">".to_string()),
+            "amp" => Some("&".to_string()),
+            "quot" => Some("\"".to_string()),
+            "apos" => Some("'".to_string()),
+            "nbsp" => Some("\u{A0}".to_string()),
+            _ => match name.strip_prefix('#') {
+                Some(number) => match number.strip_prefix('x') {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                }
+                .and_then(char::from_u32)
+                .map(String::from),
+                None => self.entities.get(&name).cloned(),
+            },
+        };
+
+        match value {
+            Some(value) => Entity::Expanded(name_len + 2, value),
+            None => Entity::Literal,
+        }
+    }
 
-        if parts.is_empty() {
+    /// Act on one tag (the text between `<` and `>`)
+    fn handle_tag(&mut self, body: &str, output: &mut MxpOutput) {
+        let secure = self.mode == MxpMode::Secure;
+
+        if let Some(name) = body.strip_prefix('/') {
+            self.close_tag(&Self::canonical_name(name.trim()), secure, output);
+            return;
+        }
+
+        if let Some(definition) = body.strip_prefix('!') {
+            if secure {
+                self.define(definition);
+            } else {
+                debug!("Ignoring MXP definition outside secure mode: <{}>", body);
+            }
+            return;
+        }
+
+        let tokens = Self::tokenize(body);
+        let Some(first) = tokens.first().filter(|t| t.key.is_none() && !t.quoted) else {
+            return;
+        };
+        let name = Self::canonical_name(&first.value);
+
+        // Custom elements stand for the tags in their definition
+        let (tag, tags, empty) = match self.elements.get(&name) {
+            Some(element) => {
+                if !secure && !element.open {
+                    debug!("Ignoring secure element <{}> on an open line", name);
+                    return;
+                }
+                let attrs = Self::attributes(&tokens[1..], element);
+                let tags: Vec<MxpTag> = Self::definition_tags(&element.definition, &attrs)
+                    .into_iter()
+                    .filter(|tag| secure || tag.is_open())
+                    .collect();
+                (MxpTag::Custom { name: name.clone(), attrs }, tags, element.empty)
+            }
+            None => {
+                let Some(tag) = Self::parse_tag(body) else {
+                    return;
+                };
+                if !secure && !tag.is_open() {
+                    debug!("Ignoring secure tag <{}> on an open line", name);
+                    return;
+                }
+                if matches!(tag, MxpTag::Br) {
+                    output.text.push('\n');
+                }
+                // Unknown elements are reported but change nothing
+                let empty = tag.is_standalone() || matches!(tag, MxpTag::Custom { .. });
+                (tag.clone(), vec![tag], empty)
+            }
+        };
+
+        output.elements.push(MxpElement {
+            tag,
+            content: String::new(),
+            line_number: self.line_number,
+        });
+
+        if !empty {
+            self.open_tags.push(OpenTag {
+                name,
+                tags,
+                open_mode: !secure,
+                element: Some(output.elements.len() - 1),
+                start: output.text.len(),
+            });
+        }
+    }
+
+    /// Close the innermost open tag called `name` and everything opened inside it
+    ///
+    /// Open-mode lines cannot close tags opened in secure mode.
+    fn close_tag(&mut self, name: &str, secure: bool, output: &mut MxpOutput) {
+        let Some(index) = self.open_tags.iter().rposition(|open| open.name == name) else {
+            debug!("Ignoring unmatched closing tag </{}>", name);
+            return;
+        };
+
+        if !secure && !self.open_tags[index].open_mode {
+            debug!("Ignoring </{}> for a secure tag on an open line", name);
+            return;
+        }
+
+        for open in self.open_tags.split_off(index).iter().rev() {
+            Self::finish(output, open);
+        }
+    }
+
+    /// Handle `<!ELEMENT ...>` and `<!ENTITY ...>` (also `<!EL>` and `<!EN>`)
+    fn define(&mut self, body: &str) {
+        const FLAGS: [&str; 7] = ["open", "empty", "delete", "private", "publish", "add", "remove"];
+
+        let tokens = Self::tokenize(body);
+        let mut words = tokens.iter();
+        let kind = words.next().map(|t| t.value.to_lowercase()).unwrap_or_default();
+        let Some(name) = words.next().filter(|t| t.key.is_none()).map(|t| t.value.to_lowercase()) else {
+            debug!("Ignoring MXP definition without a name: <!{}>", body);
+            return;
+        };
+
+        // Unquoted words like OPEN and DELETE are flags, the first other word is the value
+        let rest: Vec<&Token> = words.collect();
+        let has_flag = |flag: &str| rest.iter().any(|t| t.is_flag(flag));
+        let value = rest
+            .iter()
+            .find(|t| t.key.is_none() && !FLAGS.iter().any(|flag| t.is_flag(flag)))
+            .map(|t| t.value.clone())
+            .unwrap_or_default();
+
+        match kind.as_str() {
+            "element" | "el" => {
+                if has_flag("delete") {
+                    debug!("MXP element <{}> deleted", name);
+                    self.elements.remove(&name);
+                    return;
+                }
+
+                let attributes = rest
+                    .iter()
+                    .find(|t| t.key.as_deref() == Some("att"))
+                    .map(|t| {
+                        Self::tokenize(&t.value)
+                            .into_iter()
+                            .map(|a| match a.key {
+                                Some(key) => (key, a.value),
+                                None => (a.value.to_lowercase(), String::new()),
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default();
+
+                debug!("MXP element <{}> defined as {}", name, value);
+                self.elements.insert(
+                    name,
+                    ElementDefinition {
+                        definition: value,
+                        attributes,
+                        open: has_flag("open"),
+                        empty: has_flag("empty"),
+                    },
+                );
+            }
+
+            "entity" | "en" => {
+                if has_flag("delete") {
+                    self.entities.remove(&name);
+                } else if has_flag("add") {
+                    let entry = self.entities.entry(name.clone()).or_default();
+                    if !entry.is_empty() {
+                        entry.push('|');
+                    }
+                    entry.push_str(&value);
+                } else if has_flag("remove") {
+                    if let Some(entry) = self.entities.get_mut(&name) {
+                        *entry = entry.split('|').filter(|item| *item != value).collect::<Vec<_>>().join("|");
+                    }
+                } else {
+                    self.entities.insert(name.clone(), value);
+                }
+                debug!("MXP entity &{}; is now {:?}", name, self.entities.get(&name));
+            }
+
+            _ => debug!("Ignoring unsupported MXP definition: <!{}>", body),
+        }
+    }
+
+    /// Values of a custom element's attributes at one use
+    ///
+    /// Named values go to their attribute, bare values fill the remaining
+    /// attributes in order, and the rest keep their defaults.
+    fn attributes(tokens: &[Token], element: &ElementDefinition) -> HashMap<String, String> {
+        let mut attrs: HashMap<String, String> = element.attributes.iter().cloned().collect();
+        let mut given: Vec<&str> = tokens.iter().filter_map(|t| t.key.as_deref()).collect();
+
+        for token in tokens {
+            match &token.key {
+                Some(key) => {
+                    attrs.insert(key.clone(), token.value.clone());
+                }
+                None => {
+                    let next = element.attributes.iter().find(|(name, _)| !given.contains(&name.as_str()));
+                    if let Some((name, _)) = next {
+                        given.push(name.as_str());
+                        attrs.insert(name.clone(), token.value.clone());
+                    }
+                }
+            }
+        }
+
+        attrs
+    }
+
+    /// Tags in a custom element's definition, with `&attribute;` references filled in
+    fn definition_tags(definition: &str, attrs: &HashMap<String, String>) -> Vec<MxpTag> {
+        let definition = ATTRIBUTE_REF.replace_all(definition, |cap: &regex::Captures| {
+            attrs.get(&cap[1].to_lowercase()).cloned().unwrap_or_else(|| cap[0].to_string())
+        });
+
+        definition
+            .split('<')
+            .skip(1)
+            .filter_map(|part| part.split_once('>').map(|(body, _)| body))
+            .filter(|body| !body.starts_with('/') && !body.starts_with('!'))
+            .filter_map(Self::parse_tag)
+            .filter(|tag| !matches!(tag, MxpTag::Custom { .. }))
+            .collect()
+    }
+
+    /// Canonical (lowercase, de-aliased) name of a tag
+    fn canonical_name(name: &str) -> String {
+        let name = name.to_lowercase();
+        match name.as_str() {
+            "bold" | "strong" | "h" | "high" => "b".to_string(),
+            "italic" | "em" => "i".to_string(),
+            "underline" => "u".to_string(),
+            "strikeout" => "s".to_string(),
+            "c" => "color".to_string(),
+            _ => name,
+        }
+    }
+
+    /// Parse individual MXP tag
+    fn parse_tag(tag_text: &str) -> Option<MxpTag> {
+        let tokens = Self::tokenize(tag_text);
+        let (first, args) = tokens.split_first()?;
+        if first.key.is_some() || first.quoted {
             return None;
         }
 
-        let tag_name = parts[0];
+        let tag_name = Self::canonical_name(&first.value);
 
-        match tag_name {
-            "b" | "bold" => Some(MxpTag::Bold),
-            "i" | "italic" => Some(MxpTag::Italic),
-            "u" | "underline" => Some(MxpTag::Underline),
-            "s" | "strikeout" => Some(MxpTag::Strikethrough),
+        match tag_name.as_str() {
+            "b" => Some(MxpTag::Bold),
+            "i" => Some(MxpTag::Italic),
+            "u" => Some(MxpTag::Underline),
+            "s" => Some(MxpTag::Strikethrough),
             "br" => Some(MxpTag::Br),
             "p" => Some(MxpTag::P),
 
             "color" => {
-                let attrs = Self::parse_attributes(&parts[1..]);
+                let mut attrs = Self::parse_attributes(args, &["fore", "back"]);
                 Some(MxpTag::Color {
-                    fore: attrs.get("fore").or_else(|| attrs.get("color")).cloned(),
-                    back: attrs.get("back").cloned(),
+                    fore: attrs.remove("fore").or_else(|| attrs.remove("color")),
+                    back: attrs.remove("back"),
                 })
             }
 
             "font" => {
-                let attrs = Self::parse_attributes(&parts[1..]);
+                let mut attrs = Self::parse_attributes(args, &["face", "size", "color"]);
                 Some(MxpTag::Font {
-                    face: attrs.get("face").cloned(),
-                    size: attrs.get("size").cloned(),
-                    color: attrs.get("color").cloned(),
+                    face: attrs.remove("face"),
+                    size: attrs.remove("size"),
+                    color: attrs.remove("color"),
                 })
             }
 
             "a" => {
-                let attrs = Self::parse_attributes(&parts[1..]);
-                attrs.get("href").map(|href| MxpTag::Anchor {
-                    href: href.clone(),
-                    hint: attrs.get("hint").cloned(),
+                let mut attrs = Self::parse_attributes(args, &["href", "hint"]);
+                attrs.remove("href").map(|href| MxpTag::Anchor {
+                    href,
+                    hint: attrs.remove("hint"),
                 })
             }
 
             "send" => {
-                let attrs = Self::parse_attributes(&parts[1..]);
-                attrs.get("href").map(|href| MxpTag::Send {
-                    href: href.clone(),
-                    hint: attrs.get("hint").cloned(),
-                    prompt: attrs.get("prompt").cloned(),
+                let (flags, args): (Vec<&Token>, Vec<&Token>) = args.iter().partition(|t| t.is_flag("prompt"));
+                let mut attrs = Self::parse_attributes(args, &["href", "hint"]);
+                Some(MxpTag::Send {
+                    href: attrs.remove("href").unwrap_or_default(),
+                    hint: attrs.remove("hint"),
+                    prompt: !flags.is_empty(),
                 })
             }
 
             "expire" => {
-                let attrs = Self::parse_attributes(&parts[1..]);
-                attrs.get("name").map(|name| MxpTag::Expire {
-                    name: name.clone(),
-                })
+                let mut attrs = Self::parse_attributes(args, &["name"]);
+                attrs.remove("name").map(|name| MxpTag::Expire { name })
             }
 
             "version" => Some(MxpTag::Version),
 
             "support" => {
-                let attrs = Self::parse_attributes(&parts[1..]);
-                if let Some(tag) = attrs.get("tag") {
-                    Some(MxpTag::Support {
-                        tag: tag.clone(),
-                    })
-                } else if let Some(tag_str) = parts.get(1) {
-                    Some(MxpTag::Support {
-                        tag: tag_str.to_string(),
-                    })
-                } else {
-                    None
-                }
+                let mut attrs = Self::parse_attributes(args, &["tag"]);
+                attrs.remove("tag").map(|tag| MxpTag::Support { tag })
             }
 
             _ => {
                 // Custom element
-                let attrs = Self::parse_attributes(&parts[1..]);
+                let attrs = Self::parse_attributes(args, &[]);
                 Some(MxpTag::Custom {
-                    name: tag_name.to_string(),
+                    name: tag_name,
                     attrs,
                 })
             }
         }
     }
 
-    /// Parse tag attributes from parts
-    fn parse_attributes(parts: &[&str]) -> HashMap<String, String> {
+    /// Parse tag attributes, filling `positional` names from bare values in order
+    fn parse_attributes<'a>(tokens: impl IntoIterator<Item = &'a Token>, positional: &[&str]) -> HashMap<String, String> {
         let mut attrs = HashMap::new();
+        let mut names = positional.iter();
 
-        for part in parts {
-            if let Some(eq_pos) = part.find('=') {
-                let key = part[..eq_pos].to_lowercase();
-                let value = part[eq_pos + 1..].trim_matches(|c| c == '"' || c == '\'');
-                attrs.insert(key, value.to_string());
+        for token in tokens {
+            match &token.key {
+                Some(key) => {
+                    attrs.insert(key.clone(), token.value.clone());
+                }
+                None => {
+                    if let Some(name) = names.find(|name| !attrs.contains_key(**name)) {
+                        attrs.insert(name.to_string(), token.value.clone());
+                    }
+                }
             }
         }
 
         attrs
     }
 
+    /// Split a tag into words, quoted strings and `key=value` pairs
+    ///
+    /// Keys are lowercased; values keep their case.
+    fn tokenize(text: &str) -> Vec<Token> {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            let Some(&c) = chars.peek() else {
+                break;
+            };
+
+            if c == '"' || c == '\'' {
+                chars.next();
+                let value = chars.by_ref().take_while(|&ch| ch != c).collect();
+                tokens.push(Token { key: None, value, quoted: true });
+                continue;
+            }
+
+            let mut word = String::new();
+            while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace() && *ch != '=') {
+                word.push(ch);
+            }
+
+            if chars.next_if_eq(&'=').is_none() {
+                tokens.push(Token { key: None, value: word, quoted: false });
+                continue;
+            }
+
+            let token = match chars.peek().copied() {
+                Some(quote) if quote == '"' || quote == '\'' => {
+                    chars.next();
+                    let value = chars.by_ref().take_while(|&ch| ch != quote).collect();
+                    Token { key: Some(word.to_lowercase()), value, quoted: true }
+                }
+                _ => {
+                    let mut value = String::new();
+                    while let Some(ch) = chars.next_if(|ch| !ch.is_whitespace()) {
+                        value.push(ch);
+                    }
+                    Token { key: Some(word.to_lowercase()), value, quoted: false }
+                }
+            };
+            tokens.push(token);
+        }
+
+        tokens
+    }
+
     /// Strip all MXP tags from text (for display without MXP support)
     pub fn strip_tags(text: &str) -> String {
         let re = Regex::new(r"<[^>]+>").unwrap();
@@ -357,6 +1152,12 @@ mod tests {
         let result = parser.parse_mode_sequence("\x1B[1z");
         assert!(result.is_some());
         assert_eq!(parser.mode(), MxpMode::Secure);
+
+        // The sequence can follow other text; line tags aren't modes
+        assert_eq!(parser.parse_mode_sequence("hi\x1B[2z"), Some(6));
+        assert_eq!(parser.mode(), MxpMode::Locked);
+        assert_eq!(parser.parse_mode_sequence("\x1B[20z"), None);
+        assert_eq!(parser.parse_mode_sequence("\x1B[1m"), None);
     }
 
     #[test]
@@ -388,12 +1189,15 @@ mod tests {
     fn test_parse_send_tag() {
         let mut parser = MxpParser::new();
         parser.enable();
+        parser.set_mode(MxpMode::Secure);
 
-        let result = parser.parse("<send href='north'>Go North</send>").unwrap();
+        let result = parser.parse("<send href='north' hint='Go north now'>Go North</send>").unwrap();
         assert!(!result.is_empty());
+        assert_eq!(result[0].content, "Go North");
 
-        if let MxpTag::Send { href, .. } = &result[0].tag {
+        if let MxpTag::Send { href, hint, .. } = &result[0].tag {
             assert_eq!(href, "north");
+            assert_eq!(hint.as_deref(), Some("Go north now"));
         } else {
             panic!("Expected Send tag");
         }
@@ -424,4 +1228,215 @@ mod tests {
         let result = parser.parse("<b>text</b>").unwrap();
         assert!(result.is_empty(), "Disabled parser should not parse tags");
     }
+
+    fn styled(start: usize, end: usize, apply: impl FnOnce(&mut MxpStyle)) -> MxpSpan {
+        let mut style = MxpStyle::default();
+        apply(&mut style);
+        MxpSpan { start, end, style }
+    }
+
+    #[test]
+    fn test_process_strips_tags_into_spans() {
+        let mut parser = MxpParser::new();
+        assert_eq!(parser.process("<b>raw</b>").text, "<b>raw</b>", "Disabled parser passes text through");
+
+        parser.enable();
+        let output = parser.process("Hello <B>bold</BOLD> <c red>red</c> \x1B[1;31mansi\x1B[0m é<i>!</i>\n");
+        assert_eq!(output.text, "Hello bold red \x1B[1;31mansi\x1B[0m é!\n");
+        assert_eq!(
+            output.spans,
+            vec![
+                styled(6, 10, |s| s.bold = true),
+                styled(11, 14, |s| s.fore = Some("red".to_string())),
+                // Offsets count UTF-16 code units
+                styled(32, 33, |s| s.italic = true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_modes() {
+        let mut parser = MxpParser::new();
+        parser.enable();
+
+        // Open lines may not use secure tags; secure lines may, until the newline
+        let output = parser.process("<send north>n</send>\n\x1B[1z<send north>n</send>\n<send south>s</send>\n");
+        assert_eq!(output.text, "n\nn\ns\n");
+        assert_eq!(output.elements.len(), 1);
+        assert_eq!(output.elements[0].line_number, 1);
+        assert_eq!(parser.mode(), MxpMode::Open);
+
+        // Locked lines are verbatim
+        let output = parser.process("\x1B[2z<b>&amp;</b>\n<b>&amp;</b>");
+        assert_eq!(output.text, "<b>&amp;</b>\n&");
+        assert_eq!(output.spans, vec![styled(13, 14, |s| s.bold = true)]);
+
+        // Locked modes persist across lines until reset
+        let output = parser.process("\n\x1B[6z<send look>a</send>\n<send look>b</send>\n\x1B[3z<send look>c</send>");
+        assert_eq!(output.text, "\na\nb\nc");
+        assert_eq!(output.elements.len(), 2);
+        assert_eq!(parser.mode(), MxpMode::Open);
+    }
+
+    #[test]
+    fn test_open_tags_close_at_end_of_line() {
+        let mut parser = MxpParser::new();
+        parser.enable();
+
+        let output = parser.process("<b>one\ntwo\n\x1B[1z<u>three\nfour</u>");
+        assert_eq!(output.text, "one\ntwo\nthree\nfour");
+        assert_eq!(
+            output.spans,
+            vec![styled(0, 3, |s| s.bold = true), styled(8, 18, |s| s.underline = true)]
+        );
+        assert_eq!(output.elements[0].content, "one");
+        assert_eq!(output.elements[1].content, "three\nfour");
+    }
+
+    #[test]
+    fn test_element_and_entity_definitions() {
+        let mut parser = MxpParser::new();
+        parser.enable();
+
+        // Definitions are only accepted on secure lines
+        parser.process("<!ENTITY hp 50>\n");
+        assert_eq!(parser.entity("hp"), None);
+
+        parser.process(concat!(
+            "\x1B[6z",
+            "<!ELEMENT Hi '<color &col;><b>' ATT='col=green' OPEN>",
+            "<!ELEMENT Exit '<send &text;>'>",
+            "<!ENTITY Hp 100 DESC='Hit points'>",
+            "<!EN exits north>",
+            "<!EN exits south ADD>",
+            "\x1B[3z",
+        ));
+        assert_eq!(parser.entity("HP"), Some("100"));
+        assert_eq!(parser.entity("exits"), Some("north|south"));
+        let element = parser.element("hi").unwrap();
+        assert_eq!(element.definition, "<color &col;><b>");
+        assert_eq!(element.attributes, vec![("col".to_string(), "green".to_string())]);
+        assert!(element.open && !element.empty);
+
+        let output = parser.process("<hi>a</hi><HI blue>b</hi><exit>c</exit> &hp; &lt;&#65;&gt; &bogus; AT&T\n");
+        assert_eq!(output.text, "abc 100 <A> &bogus; AT&T\n");
+        assert_eq!(
+            output.spans,
+            vec![
+                styled(0, 1, |s| {
+                    s.bold = true;
+                    s.fore = Some("green".to_string());
+                }),
+                styled(1, 2, |s| {
+                    s.bold = true;
+                    s.fore = Some("blue".to_string());
+                }),
+            ]
+        );
+        assert!(
+            matches!(&output.elements[1].tag, MxpTag::Custom { name, attrs } if name == "hi" && attrs["col"] == "blue")
+        );
+
+        parser.process("\x1B[1z<!ELEMENT hi DELETE><!EN hp DELETE>\n");
+        assert_eq!(parser.element("hi"), None);
+        assert_eq!(parser.process("<hi>a</hi> &hp;").text, "a &hp;");
+    }
+
+    #[test]
+    fn test_split_across_reads() {
+        let mut parser = MxpParser::new();
+        parser.enable();
+
+        assert_eq!(parser.process("You see <co").text, "You see ");
+        let output = parser.process("lor red>a rat</color> &am");
+        assert_eq!(output.text, "a rat ");
+        assert_eq!(output.spans, vec![styled(0, 5, |s| s.fore = Some("red".to_string()))]);
+        assert_eq!(output.elements[0].content, "a rat");
+        assert_eq!(parser.process("p; \x1B[").text, "& ");
+        assert_eq!(parser.process("1z<b>x</b>").spans, vec![styled(0, 1, |s| s.bold = true)]);
+
+        // A '<' that never closes on its line is plain text
+        assert_eq!(parser.process("1 < 2").text, "1 ");
+        assert_eq!(parser.process("\nok").text, "< 2\nok");
+        assert_eq!(parser.visible_text("<b>HP &lt;"), "HP <");
+    }
+
+    #[test]
+    fn test_send_and_anchor_links() {
+        let mut parser = MxpParser::new();
+        parser.enable();
+
+        let output = parser.process(concat!(
+            "\x1B[1z<send href='buy sword|sell sword' hint='Shop|Buy it|Sell it'>shop</send> ",
+            "<a href='https://example.com/help' hint='Help'>help</a> ",
+            "<a href='file:///etc/passwd'>x</a> ",
+            "<SEND PROMPT>say hi</SEND> <send hint='Cast|Fireball|Heal'>fireball|heal</send>\n",
+        ));
+        assert_eq!(output.text, "shop help x say hi fireball|heal\n");
+        assert_eq!(
+            output.links,
+            vec![
+                MxpLink {
+                    start: 0,
+                    end: 4,
+                    kind: MxpLinkKind::Send,
+                    hrefs: vec!["buy sword".to_string(), "sell sword".to_string()],
+                    labels: vec!["Buy it".to_string(), "Sell it".to_string()],
+                    hint: Some("Shop".to_string()),
+                    prompt: false,
+                },
+                MxpLink {
+                    start: 5,
+                    end: 9,
+                    kind: MxpLinkKind::Url,
+                    hrefs: vec!["https://example.com/help".to_string()],
+                    labels: vec!["https://example.com/help".to_string()],
+                    hint: Some("Help".to_string()),
+                    prompt: false,
+                },
+                MxpLink {
+                    start: 12,
+                    end: 18,
+                    kind: MxpLinkKind::Send,
+                    hrefs: vec!["say hi".to_string()],
+                    labels: vec!["say hi".to_string()],
+                    hint: None,
+                    prompt: true,
+                },
+                MxpLink {
+                    start: 19,
+                    end: 32,
+                    kind: MxpLinkKind::Send,
+                    hrefs: vec!["fireball".to_string(), "heal".to_string()],
+                    labels: vec!["Fireball".to_string(), "Heal".to_string()],
+                    hint: Some("Cast".to_string()),
+                    prompt: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_open_lines_cannot_create_links() {
+        let mut parser = MxpParser::new();
+        parser.enable();
+        parser.process("\x1B[1z<!ELEMENT Loot '<b><send \"get &text;\">' OPEN><!ELEMENT Exit '<send &text;>'>\n");
+
+        // Open line: only the open parts of elements apply
+        let output = parser.process("<send north>n</send> <a href='https://example.com'>w</a> <loot>gold</loot> <exit>e</exit>\n");
+        assert_eq!(output.text, "n w gold e\n");
+        assert!(output.links.is_empty());
+        assert_eq!(output.spans, vec![styled(4, 8, |s| s.bold = true)]);
+
+        // Secure line: links, with &text; filled in from the link text
+        let output = parser.process("\x1B[1z<loot>gold</loot> <exit>east</exit>\n");
+        let hrefs: Vec<_> = output.links.iter().map(|link| (link.start, link.end, link.hrefs[0].as_str())).collect();
+        assert_eq!(hrefs, vec![(0, 4, "get gold"), (5, 9, "east")]);
+
+        // A secure link left open on one read continues into the next
+        let first = parser.process("\x1B[1z<send look>lo");
+        let second = parser.process("ok</send>");
+        assert_eq!((first.links[0].end, first.links[0].hrefs[0].as_str()), (2, "look"));
+        assert_eq!((second.links[0].start, second.links[0].end), (0, 2));
+    }
 }
@@ -0,0 +1,332 @@
+/// Certificate pinning with trust-on-first-use (TOFU)
+///
+/// Many MUDs use self-signed certificates. Instead of turning verification
+/// off, the first certificate such a server presents is pinned by its
+/// SHA-256 fingerprint and every later connection must present the same
+/// one:
+/// - Certificates that chain to a system root are accepted as usual
+/// - A pinned server must match its pin, even if its new certificate is CA-signed
+/// - An unpinned server with an unknown issuer is accepted and reported
+///   as `CertificateTrust::FirstUse` so the caller can store the pin
+///
+/// The handshake signature is always checked, so a pin proves the server
+/// holds the pinned certificate's private key.
+
+use crate::error::{MushError, Result};
+use chrono::{DateTime, Utc};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::crypto::CryptoProvider;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{CertificateError, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+/// How the server certificate of a TLS connection was trusted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum CertificateTrust {
+    /// Chains to a trusted root certificate
+    Verified,
+    /// Matches the pinned fingerprint
+    Pinned,
+    /// Not pinned yet and not CA-signed: accepted on first use, pin this fingerprint
+    FirstUse(String),
+}
+
+/// A pinned server certificate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CertificatePin {
+    pub host: String,
+    pub port: u16,
+    /// SHA-256 fingerprint, colon-separated upper-case hex
+    pub fingerprint: String,
+    pub pinned_at: DateTime<Utc>,
+}
+
+/// Pinned certificates keyed by `host:port`
+///
+/// Pins belong to the server, not to a world: every world pointing at the
+/// same host and port shares one pin. A pin vouches for the server's key,
+/// so two worlds (e.g. two characters) on one server must agree on it, and
+/// worlds opened via quick connect get a new id on each connect, so a pin
+/// keyed by world id would never be found again.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PinStore {
+    pins: BTreeMap<String, CertificatePin>,
+}
+
+impl PinStore {
+    /// Load pins from a JSON file; a missing file yields an empty store
+    ///
+    /// # Errors
+    /// - `MushError::IoError`: File exists but cannot be read
+    /// - `MushError::JsonError`: File is not a valid pin store
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Save pins to a JSON file
+    ///
+    /// # Errors
+    /// - `MushError::IoError`: File cannot be written
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json)?;
+        Ok(())
+    }
+
+    fn key(host: &str, port: u16) -> String {
+        format!("{}:{}", host.to_lowercase(), port)
+    }
+
+    /// Get the pin for a server
+    pub fn get(&self, host: &str, port: u16) -> Option<&CertificatePin> {
+        self.pins.get(&Self::key(host, port))
+    }
+
+    /// Pin a fingerprint for a server, replacing any existing pin
+    ///
+    /// # Errors
+    /// - `MushError::ValidationError`: Fingerprint is not a SHA-256 fingerprint
+    pub fn pin(&mut self, host: &str, port: u16, fingerprint: &str) -> Result<CertificatePin> {
+        let fingerprint = normalize_fingerprint(fingerprint).ok_or_else(|| MushError::ValidationError {
+            field: "fingerprint".to_string(),
+            reason: "Expected a SHA-256 fingerprint (64 hex digits)".to_string(),
+        })?;
+
+        info!("Pinning certificate for {}:{} ({})", host, port, fingerprint);
+        let pin = CertificatePin {
+            host: host.to_lowercase(),
+            port,
+            fingerprint,
+            pinned_at: Utc::now(),
+        };
+        self.pins.insert(Self::key(host, port), pin.clone());
+        Ok(pin)
+    }
+
+    /// Remove the pin for a server; returns whether one existed
+    pub fn revoke(&mut self, host: &str, port: u16) -> bool {
+        self.pins.remove(&Self::key(host, port)).is_some()
+    }
+
+    /// List all pins
+    pub fn list(&self) -> Vec<CertificatePin> {
+        self.pins.values().cloned().collect()
+    }
+}
+
+/// Check whether a verification error only says the certificate isn't CA-signed
+fn is_self_signed_error(error: &CertificateError) -> bool {
+    match error {
+        CertificateError::UnknownIssuer => true,
+        CertificateError::Other(other) => {
+            let source: &(dyn std::error::Error + 'static) = &*other.0;
+            matches!(source.downcast_ref::<webpki::Error>(), Some(webpki::Error::CaUsedAsEndEntity))
+        }
+        _ => false,
+    }
+}
+
+/// SHA-256 fingerprint of a DER certificate, e.g. "AB:CD:..."
+pub fn fingerprint(cert: &[u8]) -> String {
+    Sha256::digest(cert)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Accept fingerprints with or without separators and in any case
+pub(crate) fn normalize_fingerprint(input: &str) -> Option<String> {
+    let hex: String = input.chars().filter(|c| !matches!(c, ':' | ' ' | '-')).collect();
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let hex = hex.to_uppercase();
+    Some(
+        hex.as_bytes()
+            .chunks(2)
+            .map(|pair| String::from_utf8_lossy(pair).into_owned())
+            .collect::<Vec<_>>()
+            .join(":"),
+    )
+}
+
+/// What the verifier saw during the last handshake
+#[derive(Debug, Default)]
+pub(crate) struct Observation {
+    /// Fingerprint of the certificate the server presented
+    pub fingerprint: Option<String>,
+    /// How it was trusted, if it was
+    pub trust: Option<CertificateTrust>,
+}
+
+/// Server certificate verifier implementing CA verification plus TOFU pinning
+#[derive(Debug)]
+pub(crate) struct PinningVerifier {
+    /// CA verifier; `None` when no root certificates are available
+    webpki: Option<Arc<WebPkiServerVerifier>>,
+    provider: Arc<CryptoProvider>,
+    pinned: Option<String>,
+    observation: Arc<Mutex<Observation>>,
+}
+
+impl PinningVerifier {
+    pub(crate) fn new(
+        roots: RootCertStore,
+        provider: Arc<CryptoProvider>,
+        pinned: Option<String>,
+        observation: Arc<Mutex<Observation>>,
+    ) -> Self {
+        let webpki = WebPkiServerVerifier::builder_with_provider(Arc::new(roots), provider.clone())
+            .build()
+            .map_err(|e| warn!("CA verification unavailable: {}", e))
+            .ok();
+
+        Self {
+            webpki,
+            provider,
+            pinned: pinned.as_deref().and_then(normalize_fingerprint),
+            observation,
+        }
+    }
+
+    fn record(&self, fingerprint: String, trust: Option<CertificateTrust>) {
+        if let Ok(mut observation) = self.observation.lock() {
+            observation.fingerprint = Some(fingerprint);
+            observation.trust = trust;
+        }
+    }
+}
+
+impl ServerCertVerifier for PinningVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let presented = fingerprint(end_entity);
+
+        if let Some(pinned) = &self.pinned {
+            if *pinned == presented {
+                debug!("Server certificate matches pin");
+                self.record(presented, Some(CertificateTrust::Pinned));
+                return Ok(ServerCertVerified::assertion());
+            }
+
+            warn!("Server certificate does not match pin (pinned {}, got {})", pinned, presented);
+            self.record(presented, None);
+            return Err(rustls::Error::InvalidCertificate(CertificateError::ApplicationVerificationFailure));
+        }
+
+        let ca_result = match &self.webpki {
+            Some(webpki) => webpki.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now),
+            None => Err(rustls::Error::InvalidCertificate(CertificateError::UnknownIssuer)),
+        };
+
+        match ca_result {
+            Ok(verified) => {
+                self.record(presented, Some(CertificateTrust::Verified));
+                Ok(verified)
+            }
+            // Self-signed: unknown issuer, or a self-signed CA certificate
+            // used as the server certificate. Anything else (bad signature,
+            // wrong name, expired...) fails as usual.
+            Err(rustls::Error::InvalidCertificate(ref error)) if is_self_signed_error(error) => {
+                info!("Accepting self-signed certificate on first use ({})", presented);
+                self.record(presented.clone(), Some(CertificateTrust::FirstUse(presented)));
+                Ok(ServerCertVerified::assertion())
+            }
+            Err(e) => {
+                self.record(presented, None);
+                Err(e)
+            }
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.provider.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_format() {
+        let fp = fingerprint(b"certificate");
+        assert_eq!(fp.len(), 32 * 3 - 1);
+        assert_eq!(normalize_fingerprint(&fp.replace(':', "").to_lowercase()), Some(fp));
+        assert_eq!(normalize_fingerprint("AB:CD"), None);
+    }
+
+    #[test]
+    fn test_pin_store() {
+        let mut store = PinStore::default();
+        let fp = fingerprint(b"certificate");
+
+        store.pin("MUD.example.com", 4443, &fp).unwrap();
+        assert_eq!(store.get("mud.example.com", 4443).unwrap().fingerprint, fp);
+        assert!(store.get("mud.example.com", 4000).is_none());
+        assert!(store.pin("mud.example.com", 4000, "not-a-fingerprint").is_err());
+
+        let path = std::env::temp_dir().join(format!("macmush-pins-{}.json", uuid::Uuid::new_v4()));
+        store.save(&path).unwrap();
+        let mut loaded = PinStore::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, store);
+
+        assert!(loaded.revoke("mud.example.com", 4443));
+        assert!(!loaded.revoke("mud.example.com", 4443));
+        assert!(loaded.list().is_empty());
+        assert!(PinStore::load(&path).unwrap().list().is_empty());
+    }
+
+    #[test]
+    fn test_only_self_signed_errors_allow_first_use() {
+        let other = |error: webpki::Error| CertificateError::Other(rustls::OtherError(Arc::new(error)));
+
+        assert!(is_self_signed_error(&CertificateError::UnknownIssuer));
+        assert!(is_self_signed_error(&other(webpki::Error::CaUsedAsEndEntity)));
+
+        assert!(!is_self_signed_error(&CertificateError::BadSignature));
+        assert!(!is_self_signed_error(&CertificateError::Expired));
+        assert!(!is_self_signed_error(&other(webpki::Error::UnsupportedCriticalExtension)));
+        assert!(!is_self_signed_error(&other(webpki::Error::PathLenConstraintViolated)));
+    }
+}
@@ -0,0 +1,491 @@
+/// Proxy tunnelling for MUD connections
+///
+/// Opens the TCP connection through a proxy when the world has one:
+/// - SOCKS5 (RFC 1928), with optional username/password authentication (RFC 1929)
+/// - HTTP CONNECT, with optional Basic authentication
+///
+/// The proxy handshake completes before anything else is sent, so TLS and
+/// telnet run unchanged inside the tunnel. The MUD hostname is passed to the
+/// proxy unresolved, which lets it reach hosts we cannot resolve locally.
+
+use crate::error::{MushError, Result};
+use crate::network::dialer;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+/// Proxy protocol
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyKind {
+    Socks5,
+    Http,
+}
+
+/// Proxy server used to reach a world
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProxyConfig {
+    pub kind: ProxyKind,
+    pub host: String,
+    pub port: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    /// SOCKS5 proxy without authentication
+    pub fn socks5(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            kind: ProxyKind::Socks5,
+            host: host.into(),
+            port,
+            username: None,
+            password: None,
+        }
+    }
+
+    /// HTTP CONNECT proxy without authentication
+    pub fn http(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            kind: ProxyKind::Http,
+            ..Self::socks5(host, port)
+        }
+    }
+
+    /// Authenticate with a username and password
+    pub fn credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Proxy URL for logs and errors, e.g. "socks5://proxy:1080" (never includes the password)
+    pub fn url(&self) -> String {
+        let scheme = match self.kind {
+            ProxyKind::Socks5 => "socks5",
+            ProxyKind::Http => "http",
+        };
+        format!("{}://{}:{}", scheme, self.host, self.port)
+    }
+}
+
+/// Open a TCP stream to `host:port`, directly or through `proxy`
+///
+/// Either way the first hop is dialled by `dialer::connect`, trying each
+/// resolved address with `attempt_timeout`.
+///
+/// # Errors
+/// - `MushError::ConnectionFailed`: The MUD (or the proxy) refused the connection
+/// - `MushError::ProxyAuthFailed`: The proxy rejected our credentials
+/// - `MushError::ProxyConnectFailed`: The proxy could not reach the MUD
+pub async fn open_stream(
+    proxy: Option<&ProxyConfig>,
+    host: &str,
+    port: u16,
+    attempt_timeout: Duration,
+) -> Result<TcpStream> {
+    let Some(proxy) = proxy else {
+        return dialer::connect(host, port, attempt_timeout).await;
+    };
+
+    info!("Connecting to {}:{} via {}", host, port, proxy.url());
+    let mut stream = dialer::connect(&proxy.host, proxy.port, attempt_timeout).await?;
+
+    let tunnel = Tunnel { proxy, host, port };
+    match proxy.kind {
+        ProxyKind::Socks5 => tunnel.socks5(&mut stream).await?,
+        ProxyKind::Http => tunnel.http_connect(&mut stream).await?,
+    }
+
+    debug!("Proxy tunnel to {}:{} established", host, port);
+    Ok(stream)
+}
+
+/// SOCKS protocol version
+const SOCKS_VERSION: u8 = 5;
+
+/// SOCKS5 authentication methods
+const SOCKS_AUTH_NONE: u8 = 0x00;
+const SOCKS_AUTH_PASSWORD: u8 = 0x02;
+const SOCKS_AUTH_UNACCEPTABLE: u8 = 0xFF;
+
+/// SOCKS5 CONNECT command and address types
+const SOCKS_CMD_CONNECT: u8 = 0x01;
+const SOCKS_ATYP_IPV4: u8 = 0x01;
+const SOCKS_ATYP_DOMAIN: u8 = 0x03;
+const SOCKS_ATYP_IPV6: u8 = 0x04;
+
+/// Largest HTTP CONNECT response header we accept
+const MAX_HTTP_RESPONSE: usize = 8192;
+
+/// One proxy handshake towards `host:port`
+struct Tunnel<'a> {
+    proxy: &'a ProxyConfig,
+    host: &'a str,
+    port: u16,
+}
+
+impl Tunnel<'_> {
+    fn auth_failed(&self, reason: impl Into<String>) -> MushError {
+        let reason = reason.into();
+        warn!("Proxy authentication failed for {}: {}", self.proxy.url(), reason);
+        MushError::ProxyAuthFailed {
+            proxy: self.proxy.url(),
+            reason,
+        }
+    }
+
+    fn connect_failed(&self, reason: impl Into<String>) -> MushError {
+        let reason = reason.into();
+        warn!("Proxy {} could not connect to {}:{}: {}", self.proxy.url(), self.host, self.port, reason);
+        MushError::ProxyConnectFailed {
+            proxy: self.proxy.url(),
+            target: format!("{}:{}", self.host, self.port),
+            reason,
+        }
+    }
+
+    fn io_failed(&self, e: std::io::Error) -> MushError {
+        self.connect_failed(format!("proxy connection failed ({})", e))
+    }
+
+    /// SOCKS5 greeting, optional authentication and CONNECT request
+    async fn socks5(&self, stream: &mut TcpStream) -> Result<()> {
+        let credentials = self.proxy.username.as_deref().map(|user| {
+            (user, self.proxy.password.as_deref().unwrap_or_default())
+        });
+
+        let greeting: &[u8] = match credentials {
+            Some(_) => &[SOCKS_VERSION, 2, SOCKS_AUTH_NONE, SOCKS_AUTH_PASSWORD],
+            None => &[SOCKS_VERSION, 1, SOCKS_AUTH_NONE],
+        };
+        stream.write_all(greeting).await.map_err(|e| self.io_failed(e))?;
+
+        let mut choice = [0u8; 2];
+        stream.read_exact(&mut choice).await.map_err(|e| self.io_failed(e))?;
+        if choice[0] != SOCKS_VERSION {
+            return Err(self.connect_failed("not a SOCKS5 proxy"));
+        }
+
+        match (choice[1], credentials) {
+            (SOCKS_AUTH_NONE, _) => {}
+            (SOCKS_AUTH_PASSWORD, Some((user, pass))) => self.socks5_login(stream, user, pass).await?,
+            (SOCKS_AUTH_PASSWORD, None) => return Err(self.auth_failed("proxy requires a username and password")),
+            (SOCKS_AUTH_UNACCEPTABLE, _) => return Err(self.auth_failed("no acceptable authentication method")),
+            (method, _) => return Err(self.auth_failed(format!("unsupported authentication method {:#04x}", method))),
+        }
+
+        let mut request = vec![SOCKS_VERSION, SOCKS_CMD_CONNECT, 0];
+        match self.host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(SOCKS_ATYP_IPV4);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(SOCKS_ATYP_IPV6);
+                request.extend_from_slice(&ip.octets());
+            }
+            Err(_) => {
+                let name = u8::try_from(self.host.len())
+                    .map_err(|_| self.connect_failed("hostname is longer than 255 bytes"))?;
+                request.push(SOCKS_ATYP_DOMAIN);
+                request.push(name);
+                request.extend_from_slice(self.host.as_bytes());
+            }
+        }
+        request.extend_from_slice(&self.port.to_be_bytes());
+        stream.write_all(&request).await.map_err(|e| self.io_failed(e))?;
+
+        // VER REP RSV ATYP, then the bound address we skip
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await.map_err(|e| self.io_failed(e))?;
+        if reply[1] != 0 {
+            return Err(self.connect_failed(socks5_reply_reason(reply[1])));
+        }
+
+        let bound_len = match reply[3] {
+            SOCKS_ATYP_IPV4 => 4,
+            SOCKS_ATYP_IPV6 => 16,
+            SOCKS_ATYP_DOMAIN => {
+                let mut len = [0u8; 1];
+                stream.read_exact(&mut len).await.map_err(|e| self.io_failed(e))?;
+                len[0] as usize
+            }
+            atyp => return Err(self.connect_failed(format!("invalid address type {:#04x} in reply", atyp))),
+        };
+        let mut bound = vec![0u8; bound_len + 2];
+        stream.read_exact(&mut bound).await.map_err(|e| self.io_failed(e))?;
+
+        Ok(())
+    }
+
+    /// RFC 1929 username/password sub-negotiation
+    async fn socks5_login(&self, stream: &mut TcpStream, user: &str, pass: &str) -> Result<()> {
+        let (Ok(user_len), Ok(pass_len)) = (u8::try_from(user.len()), u8::try_from(pass.len())) else {
+            return Err(self.auth_failed("username and password must be at most 255 bytes"));
+        };
+
+        let mut login = vec![1, user_len];
+        login.extend_from_slice(user.as_bytes());
+        login.push(pass_len);
+        login.extend_from_slice(pass.as_bytes());
+        stream.write_all(&login).await.map_err(|e| self.io_failed(e))?;
+
+        let mut status = [0u8; 2];
+        stream.read_exact(&mut status).await.map_err(|e| self.io_failed(e))?;
+        if status[1] != 0 {
+            return Err(self.auth_failed("username or password rejected"));
+        }
+        Ok(())
+    }
+
+    /// HTTP CONNECT request; the response header is read byte by byte so no
+    /// tunnelled data is consumed
+    async fn http_connect(&self, stream: &mut TcpStream) -> Result<()> {
+        let authority = match self.host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, self.port),
+            _ => format!("{}:{}", self.host, self.port),
+        };
+
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", authority);
+        if let Some(user) = &self.proxy.username {
+            let token = format!("{}:{}", user, self.proxy.password.as_deref().unwrap_or_default());
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", base64(token.as_bytes())));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await.map_err(|e| self.io_failed(e))?;
+
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+        while !response.ends_with(b"\r\n\r\n") {
+            if response.len() >= MAX_HTTP_RESPONSE {
+                return Err(self.connect_failed("response header too long"));
+            }
+            stream.read_exact(&mut byte).await.map_err(|e| self.io_failed(e))?;
+            response.push(byte[0]);
+        }
+
+        let response = String::from_utf8_lossy(&response);
+        let status_line = response.lines().next().unwrap_or_default();
+        let mut parts = status_line.splitn(3, ' ');
+        let (version, status) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+        if !version.starts_with("HTTP/") {
+            return Err(self.connect_failed("not an HTTP proxy"));
+        }
+
+        match status.parse::<u16>() {
+            Ok(200..=299) => Ok(()),
+            Ok(407) => Err(self.auth_failed(match self.proxy.username {
+                Some(_) => "username or password rejected",
+                None => "proxy requires a username and password",
+            })),
+            _ => Err(self.connect_failed(format!("proxy answered \"{}\"", status_line.trim()))),
+        }
+    }
+}
+
+/// Human-readable SOCKS5 reply code
+fn socks5_reply_reason(code: u8) -> String {
+    match code {
+        0x01 => "general SOCKS server failure".to_string(),
+        0x02 => "connection not allowed by ruleset".to_string(),
+        0x03 => "network unreachable".to_string(),
+        0x04 => "host unreachable".to_string(),
+        0x05 => "connection refused".to_string(),
+        0x06 => "TTL expired".to_string(),
+        0x07 => "command not supported".to_string(),
+        0x08 => "address type not supported".to_string(),
+        code => format!("unknown SOCKS5 error {:#04x}", code),
+    }
+}
+
+/// Standard base64 (RFC 4648) for the Basic authentication header
+fn base64(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                output.push(ALPHABET[(n >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    async fn start_mock_server() -> (TcpListener, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    /// Helper: SOCKS5 proxy that expects `user`/`pass` (if given), answers
+    /// `reply` to the CONNECT request and then greets like a MUD
+    async fn start_socks5_proxy(credentials: Option<(&'static str, &'static str)>, reply: u8) -> u16 {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            let Ok((mut socket, _)) = listener.accept().await else { return };
+
+            let mut greeting = [0u8; 2];
+            socket.read_exact(&mut greeting).await.unwrap();
+            let mut methods = vec![0u8; greeting[1] as usize];
+            socket.read_exact(&mut methods).await.unwrap();
+
+            if let Some((user, pass)) = credentials {
+                socket.write_all(&[5, SOCKS_AUTH_PASSWORD]).await.unwrap();
+                let mut header = [0u8; 2];
+                socket.read_exact(&mut header).await.unwrap();
+                let mut got_user = vec![0u8; header[1] as usize];
+                socket.read_exact(&mut got_user).await.unwrap();
+                let mut pass_len = [0u8; 1];
+                socket.read_exact(&mut pass_len).await.unwrap();
+                let mut got_pass = vec![0u8; pass_len[0] as usize];
+                socket.read_exact(&mut got_pass).await.unwrap();
+
+                let ok = got_user == user.as_bytes() && got_pass == pass.as_bytes();
+                socket.write_all(&[1, if ok { 0 } else { 1 }]).await.unwrap();
+                if !ok {
+                    return;
+                }
+            } else {
+                socket.write_all(&[5, SOCKS_AUTH_NONE]).await.unwrap();
+            }
+
+            // CONNECT mud.example.com:4000
+            let mut request = [0u8; 5];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request[..4], &[5, SOCKS_CMD_CONNECT, 0, SOCKS_ATYP_DOMAIN]);
+            let mut target = vec![0u8; request[4] as usize + 2];
+            socket.read_exact(&mut target).await.unwrap();
+            assert_eq!(&target, b"mud.example.com\x0f\xa0");
+
+            socket.write_all(&[5, reply, 0, SOCKS_ATYP_IPV4, 10, 0, 0, 1, 0x0f, 0xa0]).await.unwrap();
+            if reply == 0 {
+                socket.write_all(b"Welcome!\r\n").await.unwrap();
+            }
+        });
+        port
+    }
+
+    /// Helper: HTTP proxy that answers CONNECT with `status_line` and then greets like a MUD
+    async fn start_http_proxy(status_line: &'static str) -> (u16, tokio::sync::oneshot::Receiver<String>) {
+        let (listener, port) = start_mock_server().await;
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        tokio::spawn(async move {
+            let Ok((mut socket, _)) = listener.accept().await else { return };
+
+            let mut request = Vec::new();
+            let mut buf = [0u8; 256];
+            while !request.ends_with(b"\r\n\r\n") {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let _ = tx.send(String::from_utf8(request).unwrap());
+
+            socket.write_all(format!("{}\r\nVia: proxy\r\n\r\nWelcome!\r\n", status_line).as_bytes()).await.unwrap();
+        });
+        (port, rx)
+    }
+
+    async fn read_greeting(stream: &mut TcpStream) -> Vec<u8> {
+        let mut greeting = vec![0u8; 10];
+        stream.read_exact(&mut greeting).await.unwrap();
+        greeting
+    }
+
+    #[tokio::test]
+    async fn test_socks5_tunnel() {
+        let port = start_socks5_proxy(None, 0).await;
+        let proxy = ProxyConfig::socks5("127.0.0.1", port);
+
+        let mut stream = open_stream(Some(&proxy), "mud.example.com", 4000, TIMEOUT).await.unwrap();
+        assert_eq!(read_greeting(&mut stream).await, b"Welcome!\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_socks5_authentication() {
+        let port = start_socks5_proxy(Some(("alice", "s3cret")), 0).await;
+        let proxy = ProxyConfig::socks5("127.0.0.1", port).credentials("alice", "s3cret");
+        let mut stream = open_stream(Some(&proxy), "mud.example.com", 4000, TIMEOUT).await.unwrap();
+        assert_eq!(read_greeting(&mut stream).await, b"Welcome!\r\n");
+
+        let port = start_socks5_proxy(Some(("alice", "s3cret")), 0).await;
+        let proxy = ProxyConfig::socks5("127.0.0.1", port).credentials("alice", "wrong");
+        let result = open_stream(Some(&proxy), "mud.example.com", 4000, TIMEOUT).await;
+        assert!(matches!(result, Err(MushError::ProxyAuthFailed { .. })), "got {:?}", result.err());
+
+        // Proxy wants credentials we do not have
+        let port = start_socks5_proxy(Some(("alice", "s3cret")), 0).await;
+        let result = open_stream(Some(&ProxyConfig::socks5("127.0.0.1", port)), "mud.example.com", 4000, TIMEOUT).await;
+        assert!(matches!(result, Err(MushError::ProxyAuthFailed { .. })), "got {:?}", result.err());
+    }
+
+    #[tokio::test]
+    async fn test_socks5_connect_refused() {
+        let port = start_socks5_proxy(None, 0x05).await;
+        let proxy = ProxyConfig::socks5("127.0.0.1", port);
+
+        match open_stream(Some(&proxy), "mud.example.com", 4000, TIMEOUT).await {
+            Err(MushError::ProxyConnectFailed { target, reason, .. }) => {
+                assert_eq!(target, "mud.example.com:4000");
+                assert_eq!(reason, "connection refused");
+            }
+            other => panic!("Expected ProxyConnectFailed, got {:?}", other.err()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_tunnel() {
+        let (port, request) = start_http_proxy("HTTP/1.1 200 Connection established").await;
+        let proxy = ProxyConfig::http("127.0.0.1", port).credentials("alice", "s3cret");
+
+        let mut stream = open_stream(Some(&proxy), "mud.example.com", 4000, TIMEOUT).await.unwrap();
+        assert_eq!(read_greeting(&mut stream).await, b"Welcome!\r\n");
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("CONNECT mud.example.com:4000 HTTP/1.1\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic YWxpY2U6czNjcmV0\r\n"));
+    }
+
+    #[tokio::test]
+    async fn test_http_connect_errors() {
+        let (port, _) = start_http_proxy("HTTP/1.1 407 Proxy Authentication Required").await;
+        let result = open_stream(Some(&ProxyConfig::http("127.0.0.1", port)), "mud.example.com", 4000, TIMEOUT).await;
+        assert!(matches!(result, Err(MushError::ProxyAuthFailed { .. })), "got {:?}", result.err());
+
+        let (port, _) = start_http_proxy("HTTP/1.1 403 Forbidden").await;
+        let result = open_stream(Some(&ProxyConfig::http("127.0.0.1", port)), "mud.example.com", 4000, TIMEOUT).await;
+        match result {
+            Err(MushError::ProxyConnectFailed { reason, .. }) => assert!(reason.contains("403 Forbidden")),
+            other => panic!("Expected ProxyConnectFailed, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"alice:s3cret"), "YWxpY2U6czNjcmV0");
+    }
+}
@@ -7,6 +7,7 @@
 /// - Send/receive buffering
 
 use crate::error::{MushError, Result};
+use crate::network::proxy::{self, ProxyConfig};
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
@@ -20,6 +21,7 @@ pub struct TcpClientBuilder {
     port: u16,
     timeout_secs: u64,
     read_buffer_size: usize,
+    proxy: Option<ProxyConfig>,
 }
 
 impl TcpClientBuilder {
@@ -29,6 +31,7 @@ impl TcpClientBuilder {
             port,
             timeout_secs: 30,
             read_buffer_size: 8192,
+            proxy: None,
         }
     }
 
@@ -42,12 +45,19 @@ impl TcpClientBuilder {
         self
     }
 
+    /// Tunnel the connection through a SOCKS5 or HTTP CONNECT proxy
+    pub fn proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
     pub fn build(self) -> TcpClient {
         TcpClient {
             host: self.host,
             port: self.port,
             timeout_secs: self.timeout_secs,
             read_buffer_size: self.read_buffer_size,
+            proxy: self.proxy,
             stream: None,
         }
     }
@@ -59,6 +69,7 @@ pub struct TcpClient {
     port: u16,
     timeout_secs: u64,
     read_buffer_size: usize,
+    proxy: Option<ProxyConfig>,
     stream: Option<TcpStream>,
 }
 
@@ -81,6 +92,8 @@ impl TcpClient {
     /// # Errors
     /// - `MushError::ConnectionFailed`: DNS resolution failed or connection refused
     /// - `MushError::ConnectionTimeout`: Connection attempt exceeded timeout duration
+    /// - `MushError::ProxyAuthFailed`: The proxy rejected our credentials
+    /// - `MushError::ProxyConnectFailed`: The proxy could not reach the server
     pub async fn connect(&mut self) -> Result<()> {
         if self.stream.is_some() {
             warn!("Already connected to {}:{}", self.host, self.port);
@@ -92,7 +105,7 @@ impl TcpClient {
 
         info!("Connecting to {} (timeout: {}s)", addr, self.timeout_secs);
 
-        let connect_future = TcpStream::connect(&addr);
+        let connect_future = proxy::open_stream(self.proxy.as_ref(), &self.host, self.port, timeout_duration);
 
         match timeout(timeout_duration, connect_future).await {
             Ok(Ok(stream)) => {
@@ -102,11 +115,7 @@ impl TcpClient {
             }
             Ok(Err(e)) => {
                 error!("Connection failed to {}: {}", addr, e);
-                Err(MushError::ConnectionFailed {
-                    host: self.host.clone(),
-                    port: self.port,
-                    source: e,
-                })
+                Err(e)
             }
             Err(_) => {
                 error!("Connection timeout after {}s to {}", self.timeout_secs, addr);
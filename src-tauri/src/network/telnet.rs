@@ -0,0 +1,667 @@
+/// Telnet protocol engine
+///
+/// Implements the telnet command parser and option negotiation used by every
+/// MUD protocol handler:
+/// - Stateful IAC parsing (sequences split across reads are buffered)
+/// - RFC 1143 "Q method" option negotiation with loop prevention
+/// - Subnegotiation (IAC SB ... IAC SE) collection
+///
+/// Protocol handlers do not inspect raw bytes themselves. They declare which
+/// options they accept and react to the `TelnetEvent`s produced here.
+///
+/// References:
+/// - https://www.rfc-editor.org/rfc/rfc854
+/// - https://www.rfc-editor.org/rfc/rfc1143
+
+use std::collections::HashMap;
+use tracing::{debug, trace, warn};
+
+/// Telnet command bytes
+pub const IAC: u8 = 255; // Interpret As Command
+pub const DONT: u8 = 254;
+pub const DO: u8 = 253;
+pub const WONT: u8 = 252;
+pub const WILL: u8 = 251;
+pub const SB: u8 = 250; // Subnegotiation Begin
+pub const GA: u8 = 249; // Go Ahead
+pub const NOP: u8 = 241; // No Operation
+pub const SE: u8 = 240; // Subnegotiation End
+pub const EOR: u8 = 239; // End Of Record
+
+/// Telnet option numbers handled by the telnet layer itself
+pub const TELOPT_ECHO: u8 = 1; // Echo (RFC 857)
+pub const TELOPT_EOR: u8 = 25; // End Of Record (RFC 885)
+pub const TELOPT_NAWS: u8 = 31; // Negotiate About Window Size (RFC 1073)
+
+/// Maximum subnegotiation payload kept before the sequence is discarded
+const MAX_SUBNEGOTIATION_SIZE: usize = 64 * 1024;
+
+/// RFC 1143 option state for one side of the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QState {
+    /// Option is disabled
+    #[default]
+    No,
+    /// Option is enabled
+    Yes,
+    /// We asked to disable, waiting for the reply
+    WantNo,
+    /// We asked to disable, and want to re-enable once the reply arrives
+    WantNoOpposite,
+    /// We asked to enable, waiting for the reply
+    WantYes,
+    /// We asked to enable, and want to disable once the reply arrives
+    WantYesOpposite,
+}
+
+/// Negotiation state of a single telnet option
+#[derive(Debug, Clone, Copy, Default)]
+struct OptionState {
+    /// Whether we perform the option (WILL/WONT from us, DO/DONT from server)
+    us: QState,
+    /// Whether the server performs the option (WILL/WONT from server, DO/DONT from us)
+    him: QState,
+    /// Accept the server's DO for this option
+    accept_local: bool,
+    /// Accept the server's WILL for this option
+    accept_remote: bool,
+}
+
+/// Events produced while parsing incoming telnet data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TelnetEvent {
+    /// Application data with telnet commands removed
+    Data(Vec<u8>),
+    /// Stand-alone telnet command (GA, EOR, NOP, ...)
+    Command(u8),
+    /// We now perform the option
+    LocalEnabled(u8),
+    /// We no longer perform the option
+    LocalDisabled(u8),
+    /// The server now performs the option
+    RemoteEnabled(u8),
+    /// The server no longer performs the option
+    RemoteDisabled(u8),
+    /// Subnegotiation payload (IAC IAC already unescaped)
+    Subnegotiation { option: u8, data: Vec<u8> },
+}
+
+/// Incoming parser state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParseState {
+    Data,
+    Iac,
+    Negotiate(u8),
+    SbOption,
+    SbData,
+    SbIac,
+}
+
+/// Telnet handler: parser plus per-option negotiation engine
+#[derive(Debug)]
+pub struct TelnetHandler {
+    state: ParseState,
+    options: HashMap<u8, OptionState>,
+    sb_option: u8,
+    sb_buffer: Vec<u8>,
+    /// Bytes waiting to be sent to the server
+    output: Vec<u8>,
+}
+
+impl TelnetHandler {
+    /// Create a new telnet handler that refuses every option
+    pub fn new() -> Self {
+        Self {
+            state: ParseState::Data,
+            options: HashMap::new(),
+            sb_option: 0,
+            sb_buffer: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Accept the server's DO for `option` (we are willing to perform it)
+    pub fn accept_local(&mut self, option: u8) {
+        self.options.entry(option).or_default().accept_local = true;
+    }
+
+    /// Accept the server's WILL for `option` (we want the server to perform it)
+    pub fn accept_remote(&mut self, option: u8) {
+        self.options.entry(option).or_default().accept_remote = true;
+    }
+
+    /// Check if we currently perform `option`
+    pub fn is_local_enabled(&self, option: u8) -> bool {
+        self.options.get(&option).map(|o| o.us == QState::Yes).unwrap_or(false)
+    }
+
+    /// Check if the server currently performs `option`
+    pub fn is_remote_enabled(&self, option: u8) -> bool {
+        self.options.get(&option).map(|o| o.him == QState::Yes).unwrap_or(false)
+    }
+
+    /// Get the local (us) negotiation state of `option`
+    pub fn local_state(&self, option: u8) -> QState {
+        self.options.get(&option).map(|o| o.us).unwrap_or_default()
+    }
+
+    /// Get the remote (him) negotiation state of `option`
+    pub fn remote_state(&self, option: u8) -> QState {
+        self.options.get(&option).map(|o| o.him).unwrap_or_default()
+    }
+
+    /// Ask to enable `option` on our side (send WILL)
+    pub fn request_local_enable(&mut self, option: u8) {
+        let state = self.options.entry(option).or_default();
+        state.accept_local = true;
+        match state.us {
+            QState::No => {
+                state.us = QState::WantYes;
+                self.queue_negotiation(WILL, option);
+            }
+            QState::WantNo => state.us = QState::WantNoOpposite,
+            QState::WantYesOpposite => state.us = QState::WantYes,
+            QState::Yes | QState::WantNoOpposite | QState::WantYes => {}
+        }
+    }
+
+    /// Ask to disable `option` on our side (send WONT)
+    pub fn request_local_disable(&mut self, option: u8) {
+        let state = self.options.entry(option).or_default();
+        match state.us {
+            QState::Yes => {
+                state.us = QState::WantNo;
+                self.queue_negotiation(WONT, option);
+            }
+            QState::WantNoOpposite => state.us = QState::WantNo,
+            QState::WantYes => state.us = QState::WantYesOpposite,
+            QState::No | QState::WantNo | QState::WantYesOpposite => {}
+        }
+    }
+
+    /// Ask the server to enable `option` (send DO)
+    pub fn request_remote_enable(&mut self, option: u8) {
+        let state = self.options.entry(option).or_default();
+        state.accept_remote = true;
+        match state.him {
+            QState::No => {
+                state.him = QState::WantYes;
+                self.queue_negotiation(DO, option);
+            }
+            QState::WantNo => state.him = QState::WantNoOpposite,
+            QState::WantYesOpposite => state.him = QState::WantYes,
+            QState::Yes | QState::WantNoOpposite | QState::WantYes => {}
+        }
+    }
+
+    /// Ask the server to disable `option` (send DONT)
+    pub fn request_remote_disable(&mut self, option: u8) {
+        let state = self.options.entry(option).or_default();
+        match state.him {
+            QState::Yes => {
+                state.him = QState::WantNo;
+                self.queue_negotiation(DONT, option);
+            }
+            QState::WantNoOpposite => state.him = QState::WantNo,
+            QState::WantYes => state.him = QState::WantYesOpposite,
+            QState::No | QState::WantNo | QState::WantYesOpposite => {}
+        }
+    }
+
+    /// Queue a subnegotiation (IAC SB option data IAC SE), escaping IAC bytes
+    pub fn send_subnegotiation(&mut self, option: u8, data: &[u8]) {
+        self.output.extend_from_slice(&[IAC, SB, option]);
+        for &byte in data {
+            if byte == IAC {
+                self.output.push(IAC);
+            }
+            self.output.push(byte);
+        }
+        self.output.extend_from_slice(&[IAC, SE]);
+    }
+
+    /// Queue a stand-alone telnet command (e.g. NOP)
+    pub fn send_command(&mut self, command: u8) {
+        self.output.extend_from_slice(&[IAC, command]);
+    }
+
+    /// Check if there are bytes waiting to be sent
+    pub fn has_output(&self) -> bool {
+        !self.output.is_empty()
+    }
+
+    /// Take the bytes waiting to be sent to the server
+    pub fn take_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.output)
+    }
+
+    /// Parse incoming bytes into telnet events
+    ///
+    /// Incomplete IAC or subnegotiation sequences at the end of `input` are
+    /// kept and completed by the next call.
+    pub fn receive(&mut self, input: &[u8]) -> Vec<TelnetEvent> {
+        self.parse(input, None).0
+    }
+
+    /// Parse incoming bytes, stopping right after a subnegotiation for `option`
+    ///
+    /// Returns the events and the number of bytes consumed. Used where the
+    /// bytes following a subnegotiation are in a different format, e.g. the
+    /// compressed stream that starts after IAC SB COMPRESS2 IAC SE.
+    pub fn receive_until_subnegotiation(&mut self, input: &[u8], option: u8) -> (Vec<TelnetEvent>, usize) {
+        self.parse(input, Some(option))
+    }
+
+    fn parse(&mut self, input: &[u8], stop_after: Option<u8>) -> (Vec<TelnetEvent>, usize) {
+        let mut events = Vec::new();
+        let mut data = Vec::new();
+
+        for (pos, &byte) in input.iter().enumerate() {
+            match self.state {
+                ParseState::Data => {
+                    if byte == IAC {
+                        self.state = ParseState::Iac;
+                    } else {
+                        data.push(byte);
+                    }
+                }
+                ParseState::Iac => match byte {
+                    IAC => {
+                        data.push(IAC);
+                        self.state = ParseState::Data;
+                    }
+                    WILL | WONT | DO | DONT => self.state = ParseState::Negotiate(byte),
+                    SB => self.state = ParseState::SbOption,
+                    _ => {
+                        Self::flush_data(&mut data, &mut events);
+                        trace!("Telnet command {}", byte);
+                        events.push(TelnetEvent::Command(byte));
+                        self.state = ParseState::Data;
+                    }
+                },
+                ParseState::Negotiate(command) => {
+                    Self::flush_data(&mut data, &mut events);
+                    self.handle_negotiation(command, byte, &mut events);
+                    self.state = ParseState::Data;
+                }
+                ParseState::SbOption => {
+                    self.sb_option = byte;
+                    self.sb_buffer.clear();
+                    self.state = ParseState::SbData;
+                }
+                ParseState::SbData => {
+                    if byte == IAC {
+                        self.state = ParseState::SbIac;
+                    } else if self.sb_buffer.len() < MAX_SUBNEGOTIATION_SIZE {
+                        self.sb_buffer.push(byte);
+                    }
+                }
+                ParseState::SbIac => match byte {
+                    IAC => {
+                        if self.sb_buffer.len() < MAX_SUBNEGOTIATION_SIZE {
+                            self.sb_buffer.push(IAC);
+                        }
+                        self.state = ParseState::SbData;
+                    }
+                    SE => {
+                        Self::flush_data(&mut data, &mut events);
+                        self.finish_subnegotiation(&mut events);
+                        self.state = ParseState::Data;
+                        if stop_after == Some(self.sb_option) {
+                            return (events, pos + 1);
+                        }
+                    }
+                    _ => {
+                        // Protocol violation: treat as end of subnegotiation
+                        warn!("Unexpected IAC {} inside subnegotiation of option {}", byte, self.sb_option);
+                        Self::flush_data(&mut data, &mut events);
+                        self.finish_subnegotiation(&mut events);
+                        self.state = ParseState::Data;
+                    }
+                },
+            }
+        }
+
+        Self::flush_data(&mut data, &mut events);
+        (events, input.len())
+    }
+
+    fn flush_data(data: &mut Vec<u8>, events: &mut Vec<TelnetEvent>) {
+        if !data.is_empty() {
+            events.push(TelnetEvent::Data(std::mem::take(data)));
+        }
+    }
+
+    fn finish_subnegotiation(&mut self, events: &mut Vec<TelnetEvent>) {
+        if self.sb_buffer.len() >= MAX_SUBNEGOTIATION_SIZE {
+            warn!("Discarding oversized subnegotiation for option {}", self.sb_option);
+            self.sb_buffer.clear();
+            return;
+        }
+
+        debug!("Subnegotiation for option {} ({} bytes)", self.sb_option, self.sb_buffer.len());
+        events.push(TelnetEvent::Subnegotiation {
+            option: self.sb_option,
+            data: std::mem::take(&mut self.sb_buffer),
+        });
+    }
+
+    fn queue_negotiation(&mut self, command: u8, option: u8) {
+        trace!("Sending IAC {} {}", command, option);
+        self.output.extend_from_slice(&[IAC, command, option]);
+    }
+
+    /// Apply the RFC 1143 state machine to a received WILL/WONT/DO/DONT
+    fn handle_negotiation(&mut self, command: u8, option: u8, events: &mut Vec<TelnetEvent>) {
+        debug!("Received IAC {} {}", command, option);
+        let mut state = self.options.get(&option).copied().unwrap_or_default();
+
+        match command {
+            WILL => match state.him {
+                QState::No => {
+                    if state.accept_remote {
+                        state.him = QState::Yes;
+                        self.queue_negotiation(DO, option);
+                        events.push(TelnetEvent::RemoteEnabled(option));
+                    } else {
+                        self.queue_negotiation(DONT, option);
+                    }
+                }
+                QState::Yes => {}
+                QState::WantNo => {
+                    warn!("DONT {} answered by WILL", option);
+                    state.him = QState::No;
+                }
+                QState::WantNoOpposite => {
+                    warn!("DONT {} answered by WILL", option);
+                    state.him = QState::Yes;
+                    events.push(TelnetEvent::RemoteEnabled(option));
+                }
+                QState::WantYes => {
+                    state.him = QState::Yes;
+                    events.push(TelnetEvent::RemoteEnabled(option));
+                }
+                QState::WantYesOpposite => {
+                    state.him = QState::WantNo;
+                    self.queue_negotiation(DONT, option);
+                }
+            },
+            WONT => match state.him {
+                QState::No => {}
+                QState::Yes => {
+                    state.him = QState::No;
+                    self.queue_negotiation(DONT, option);
+                    events.push(TelnetEvent::RemoteDisabled(option));
+                }
+                QState::WantNo | QState::WantYes | QState::WantYesOpposite => {
+                    state.him = QState::No;
+                    events.push(TelnetEvent::RemoteDisabled(option));
+                }
+                QState::WantNoOpposite => {
+                    state.him = QState::WantYes;
+                    self.queue_negotiation(DO, option);
+                }
+            },
+            DO => match state.us {
+                QState::No => {
+                    if state.accept_local {
+                        state.us = QState::Yes;
+                        self.queue_negotiation(WILL, option);
+                        events.push(TelnetEvent::LocalEnabled(option));
+                    } else {
+                        self.queue_negotiation(WONT, option);
+                    }
+                }
+                QState::Yes => {}
+                QState::WantNo => {
+                    warn!("WONT {} answered by DO", option);
+                    state.us = QState::No;
+                }
+                QState::WantNoOpposite => {
+                    warn!("WONT {} answered by DO", option);
+                    state.us = QState::Yes;
+                    events.push(TelnetEvent::LocalEnabled(option));
+                }
+                QState::WantYes => {
+                    state.us = QState::Yes;
+                    events.push(TelnetEvent::LocalEnabled(option));
+                }
+                QState::WantYesOpposite => {
+                    state.us = QState::WantNo;
+                    self.queue_negotiation(WONT, option);
+                }
+            },
+            DONT => match state.us {
+                QState::No => {}
+                QState::Yes => {
+                    state.us = QState::No;
+                    self.queue_negotiation(WONT, option);
+                    events.push(TelnetEvent::LocalDisabled(option));
+                }
+                QState::WantNo | QState::WantYes | QState::WantYesOpposite => {
+                    state.us = QState::No;
+                    events.push(TelnetEvent::LocalDisabled(option));
+                }
+                QState::WantNoOpposite => {
+                    state.us = QState::WantYes;
+                    self.queue_negotiation(WILL, option);
+                }
+            },
+            _ => {}
+        }
+
+        self.options.insert(option, state);
+    }
+}
+
+/// Encode one outgoing line for the wire
+///
+/// Doubles every IAC byte (RFC 854) and terminates the line with `eol`.
+/// Embedded newlines split the text into several lines, each terminated
+/// with `eol`, so a bare LF never reaches the server.
+pub fn encode_line(line: &[u8], eol: &[u8]) -> Vec<u8> {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    let mut output = Vec::with_capacity(line.len() + eol.len());
+
+    for part in line.split(|&b| b == b'\n') {
+        let part = part.strip_suffix(b"\r").unwrap_or(part);
+        for &byte in part {
+            if byte == IAC {
+                output.push(IAC);
+            }
+            output.push(byte);
+        }
+        output.extend_from_slice(eol);
+    }
+
+    output
+}
+
+impl Default for TelnetHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_data_passthrough() {
+        let mut telnet = TelnetHandler::new();
+        let events = telnet.receive(b"Hello, world!\n");
+        assert_eq!(events, vec![TelnetEvent::Data(b"Hello, world!\n".to_vec())]);
+        assert!(!telnet.has_output());
+    }
+
+    #[test]
+    fn test_escaped_iac_in_data() {
+        let mut telnet = TelnetHandler::new();
+        let events = telnet.receive(&[b'a', IAC, IAC, b'b']);
+        assert_eq!(events, vec![TelnetEvent::Data(vec![b'a', IAC, b'b'])]);
+    }
+
+    #[test]
+    fn test_refuses_unknown_options() {
+        let mut telnet = TelnetHandler::new();
+        let events = telnet.receive(&[IAC, WILL, 200, IAC, DO, 201]);
+        assert!(events.is_empty());
+        assert_eq!(telnet.take_output(), vec![IAC, DONT, 200, IAC, WONT, 201]);
+    }
+
+    #[test]
+    fn test_accepts_supported_remote_option() {
+        let mut telnet = TelnetHandler::new();
+        telnet.accept_remote(86);
+
+        let events = telnet.receive(&[IAC, WILL, 86]);
+        assert_eq!(events, vec![TelnetEvent::RemoteEnabled(86)]);
+        assert_eq!(telnet.take_output(), vec![IAC, DO, 86]);
+        assert!(telnet.is_remote_enabled(86));
+
+        // Repeated WILL must not be answered again (loop prevention)
+        let events = telnet.receive(&[IAC, WILL, 86]);
+        assert!(events.is_empty());
+        assert!(!telnet.has_output());
+    }
+
+    #[test]
+    fn test_accepts_supported_local_option() {
+        let mut telnet = TelnetHandler::new();
+        telnet.accept_local(31);
+
+        let events = telnet.receive(&[IAC, DO, 31]);
+        assert_eq!(events, vec![TelnetEvent::LocalEnabled(31)]);
+        assert_eq!(telnet.take_output(), vec![IAC, WILL, 31]);
+
+        let events = telnet.receive(&[IAC, DONT, 31]);
+        assert_eq!(events, vec![TelnetEvent::LocalDisabled(31)]);
+        assert_eq!(telnet.take_output(), vec![IAC, WONT, 31]);
+        assert!(!telnet.is_local_enabled(31));
+    }
+
+    #[test]
+    fn test_our_request_is_not_answered_twice() {
+        let mut telnet = TelnetHandler::new();
+        telnet.request_remote_enable(1);
+        assert_eq!(telnet.take_output(), vec![IAC, DO, 1]);
+        assert_eq!(telnet.remote_state(1), QState::WantYes);
+
+        // Server agrees: no reply must be sent
+        let events = telnet.receive(&[IAC, WILL, 1]);
+        assert_eq!(events, vec![TelnetEvent::RemoteEnabled(1)]);
+        assert!(!telnet.has_output());
+        assert_eq!(telnet.remote_state(1), QState::Yes);
+    }
+
+    #[test]
+    fn test_opposite_queue_while_waiting() {
+        let mut telnet = TelnetHandler::new();
+        telnet.request_local_enable(24);
+        assert_eq!(telnet.take_output(), vec![IAC, WILL, 24]);
+
+        // Change our mind before the answer arrives
+        telnet.request_local_disable(24);
+        assert_eq!(telnet.local_state(24), QState::WantYesOpposite);
+        assert!(!telnet.has_output());
+
+        // Server agrees to the enable: we immediately ask to disable
+        let events = telnet.receive(&[IAC, DO, 24]);
+        assert!(events.is_empty());
+        assert_eq!(telnet.take_output(), vec![IAC, WONT, 24]);
+        assert_eq!(telnet.local_state(24), QState::WantNo);
+    }
+
+    #[test]
+    fn test_split_negotiation_across_reads() {
+        let mut telnet = TelnetHandler::new();
+        telnet.accept_remote(86);
+
+        let events = telnet.receive(&[b'x', IAC]);
+        assert_eq!(events, vec![TelnetEvent::Data(vec![b'x'])]);
+
+        let events = telnet.receive(&[WILL]);
+        assert!(events.is_empty());
+
+        let events = telnet.receive(&[86, b'y']);
+        assert_eq!(
+            events,
+            vec![TelnetEvent::RemoteEnabled(86), TelnetEvent::Data(vec![b'y'])]
+        );
+    }
+
+    #[test]
+    fn test_subnegotiation_split_across_reads() {
+        let mut telnet = TelnetHandler::new();
+
+        let events = telnet.receive(&[IAC, SB, 201, b'a', IAC]);
+        assert!(events.is_empty());
+
+        let events = telnet.receive(&[IAC, b'b', IAC]);
+        assert!(events.is_empty());
+
+        let events = telnet.receive(&[SE, b'z']);
+        assert_eq!(
+            events,
+            vec![
+                TelnetEvent::Subnegotiation { option: 201, data: vec![b'a', IAC, b'b'] },
+                TelnetEvent::Data(vec![b'z']),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_receive_until_subnegotiation() {
+        let mut telnet = TelnetHandler::new();
+
+        let input = [b'a', IAC, SB, 86, IAC, SE, 0x78, 0x9c];
+        let (events, consumed) = telnet.receive_until_subnegotiation(&input, 86);
+        assert_eq!(consumed, 6);
+        assert_eq!(
+            events,
+            vec![
+                TelnetEvent::Data(vec![b'a']),
+                TelnetEvent::Subnegotiation { option: 86, data: vec![] },
+            ]
+        );
+
+        // Other subnegotiations do not stop the parser
+        let (_, consumed) = telnet.receive_until_subnegotiation(&[IAC, SB, 201, IAC, SE, b'b'], 86);
+        assert_eq!(consumed, 6);
+    }
+
+    #[test]
+    fn test_commands_are_reported() {
+        let mut telnet = TelnetHandler::new();
+        let events = telnet.receive(&[b'>', IAC, GA, IAC, NOP]);
+        assert_eq!(
+            events,
+            vec![
+                TelnetEvent::Data(vec![b'>']),
+                TelnetEvent::Command(GA),
+                TelnetEvent::Command(NOP),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode_line_escapes_iac_and_terminates() {
+        assert_eq!(encode_line(b"look", b"\r\n"), b"look\r\n".to_vec());
+        assert_eq!(encode_line(&[b'a', IAC, b'b'], b"\r\n"), vec![b'a', IAC, IAC, b'b', b'\r', b'\n']);
+        assert_eq!(encode_line(b"look\n", b"\n"), b"look\n".to_vec());
+        assert_eq!(encode_line(b"say hi\r\nsay bye", b"\r"), b"say hi\rsay bye\r".to_vec());
+        assert_eq!(encode_line(b"", b"\r\n"), b"\r\n".to_vec());
+    }
+
+    #[test]
+    fn test_send_subnegotiation_escapes_iac() {
+        let mut telnet = TelnetHandler::new();
+        telnet.send_subnegotiation(31, &[0, 255, 0, 24]);
+        assert_eq!(
+            telnet.take_output(),
+            vec![IAC, SB, 31, 0, IAC, IAC, 0, 24, IAC, SE]
+        );
+    }
+}
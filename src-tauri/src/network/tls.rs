@@ -4,11 +4,22 @@
 /// - System certificate store integration
 /// - Rustls for modern TLS support
 /// - Fallback to plain TCP
-/// - Certificate validation
+/// - SOCKS5 and HTTP CONNECT proxies (see `network::proxy`)
+/// - WebSocket transport on top of either (see `network::websocket`)
+/// - Certificate validation, with trust-on-first-use pinning for
+///   self-signed servers (see `network::pinning`)
+/// - Client certificate authentication (see `network::client_cert`)
 
 use crate::error::{MushError, Result};
+use crate::network::client_cert::{load_client_certificate, ClientCertResolver};
+use crate::network::proxy::{self, ProxyConfig};
+use crate::network::websocket::{self, ByteStream, WebSocketTransport};
+use crate::network::pinning::{normalize_fingerprint, CertificateTrust, Observation, PinningVerifier};
 use rustls::pki_types::ServerName;
-use std::sync::Arc;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
@@ -16,10 +27,11 @@ use tokio::time::timeout;
 use tokio_rustls::{TlsConnector, client::TlsStream};
 use tracing::{debug, error, info, warn};
 
-/// Stream type enum to handle both plain and TLS connections
+/// Stream type enum to handle plain, TLS and WebSocket connections
 pub enum MudStream {
     Plain(TcpStream),
-    Tls(TlsStream<TcpStream>),
+    Tls(Box<TlsStream<TcpStream>>),
+    WebSocket(Box<WebSocketTransport>),
 }
 
 impl MudStream {
@@ -28,6 +40,7 @@ impl MudStream {
         match self {
             MudStream::Plain(stream) => stream.read(buf).await,
             MudStream::Tls(stream) => stream.read(buf).await,
+            MudStream::WebSocket(stream) => stream.read(buf).await,
         }
     }
 
@@ -36,6 +49,7 @@ impl MudStream {
         match self {
             MudStream::Plain(stream) => stream.write_all(buf).await,
             MudStream::Tls(stream) => stream.write_all(buf).await,
+            MudStream::WebSocket(stream) => stream.write_all(buf).await,
         }
     }
 
@@ -44,10 +58,20 @@ impl MudStream {
         match self {
             MudStream::Plain(stream) => stream.shutdown().await,
             MudStream::Tls(stream) => stream.shutdown().await,
+            MudStream::WebSocket(stream) => stream.shutdown().await,
         }
     }
 }
 
+/// Parameters negotiated during the TLS handshake
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TlsInfo {
+    /// Protocol version, e.g. "TLSv1_3"
+    pub protocol_version: String,
+    /// Cipher suite, e.g. "TLS13_AES_256_GCM_SHA384"
+    pub cipher_suite: String,
+}
+
 /// TLS client configuration builder
 #[derive(Debug, Clone)]
 pub struct TlsClientBuilder {
@@ -57,6 +81,11 @@ pub struct TlsClientBuilder {
     timeout_secs: u64,
     read_buffer_size: usize,
     verify_certificates: bool,
+    pinned_fingerprint: Option<String>,
+    client_certificate: Option<(PathBuf, PathBuf)>,
+    proxy: Option<ProxyConfig>,
+    websocket_path: Option<String>,
+    keepalive: Option<(Duration, Duration)>,
 }
 
 impl TlsClientBuilder {
@@ -68,6 +97,11 @@ impl TlsClientBuilder {
             timeout_secs: 30,
             read_buffer_size: 8192,
             verify_certificates: true,
+            pinned_fingerprint: None,
+            client_certificate: None,
+            proxy: None,
+            websocket_path: None,
+            keepalive: None,
         }
     }
 
@@ -91,6 +125,36 @@ impl TlsClientBuilder {
         self
     }
 
+    /// Require the server certificate to have this SHA-256 fingerprint
+    pub fn pinned_fingerprint(mut self, fingerprint: Option<String>) -> Self {
+        self.pinned_fingerprint = fingerprint;
+        self
+    }
+
+    /// Present this PEM certificate chain and private key when the server asks for one
+    pub fn client_certificate(mut self, cert_path: impl Into<PathBuf>, key_path: impl Into<PathBuf>) -> Self {
+        self.client_certificate = Some((cert_path.into(), key_path.into()));
+        self
+    }
+
+    /// Tunnel the connection through a SOCKS5 or HTTP CONNECT proxy
+    pub fn proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Speak WebSocket at this path (ws://, or wss:// with TLS) instead of raw TCP
+    pub fn websocket(mut self, path: Option<String>) -> Self {
+        self.websocket_path = path;
+        self
+    }
+
+    /// Enable TCP keepalive: first probe after `idle` without traffic, then every `interval`
+    pub fn keepalive(mut self, idle: Duration, interval: Duration) -> Self {
+        self.keepalive = Some((idle, interval));
+        self
+    }
+
     pub fn build(self) -> TlsClient {
         TlsClient {
             host: self.host,
@@ -99,6 +163,14 @@ impl TlsClientBuilder {
             timeout_secs: self.timeout_secs,
             read_buffer_size: self.read_buffer_size,
             verify_certificates: self.verify_certificates,
+            pinned_fingerprint: self.pinned_fingerprint,
+            client_certificate: self.client_certificate,
+            proxy: self.proxy,
+            websocket_path: self.websocket_path,
+            keepalive: self.keepalive,
+            tls_info: None,
+            server_addr: None,
+            observation: Arc::new(Mutex::new(Observation::default())),
             stream: None,
         }
     }
@@ -112,6 +184,21 @@ pub struct TlsClient {
     timeout_secs: u64,
     read_buffer_size: usize,
     verify_certificates: bool,
+    pinned_fingerprint: Option<String>,
+    /// PEM certificate chain and private key paths for mutual TLS
+    client_certificate: Option<(PathBuf, PathBuf)>,
+    /// Proxy the TCP connection is tunnelled through
+    proxy: Option<ProxyConfig>,
+    /// WebSocket path when the server is reached over WebSocket
+    websocket_path: Option<String>,
+    /// TCP keepalive idle time and probe interval
+    keepalive: Option<(Duration, Duration)>,
+    /// TLS parameters negotiated by the last handshake
+    tls_info: Option<TlsInfo>,
+    /// Resolved address the last connect reached (unknown through a proxy)
+    server_addr: Option<SocketAddr>,
+    /// Server certificate seen by the verifier during the last handshake
+    observation: Arc<Mutex<Observation>>,
     stream: Option<MudStream>,
 }
 
@@ -145,9 +232,35 @@ impl TlsClient {
             warn!("Certificate loading error: {}", err);
         }
 
-        let config = rustls::ClientConfig::builder()
-            .with_root_certificates(root_store)
-            .with_no_client_auth();
+        // Select ring explicitly: other dependencies enable aws-lc-rs as well,
+        // which leaves rustls unable to pick a process-wide default
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+
+        *self.observation.lock().map_err(|_| MushError::Internal("Certificate state poisoned".to_string()))? =
+            Observation::default();
+        let verifier = PinningVerifier::new(
+            root_store,
+            provider.clone(),
+            self.pinned_fingerprint.clone(),
+            self.observation.clone(),
+        );
+
+        let client_certificate = self
+            .client_certificate
+            .as_ref()
+            .map(|(cert_path, key_path)| load_client_certificate(cert_path, key_path, &provider))
+            .transpose()?;
+
+        let builder = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| MushError::TlsError(format!("Unsupported TLS configuration: {}", e)))?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(verifier));
+
+        let config = match client_certificate {
+            Some(certified) => builder.with_client_cert_resolver(Arc::new(ClientCertResolver(certified))),
+            None => builder.with_no_client_auth(),
+        };
 
         Ok(TlsConnector::from(Arc::new(config)))
     }
@@ -157,7 +270,12 @@ impl TlsClient {
     /// # Errors
     /// - `MushError::ConnectionFailed`: DNS resolution failed or connection refused
     /// - `MushError::ConnectionTimeout`: Connection attempt exceeded timeout duration
+    /// - `MushError::ProxyAuthFailed`: The proxy rejected our credentials
+    /// - `MushError::ProxyConnectFailed`: The proxy could not reach the server
+    /// - `MushError::CertificateChanged`: Server certificate does not match the pin
+    /// - `MushError::ClientCertificateError`: Client certificate or key cannot be used
     /// - `MushError::TlsError`: TLS handshake failed
+    /// - `MushError::WebSocketError`: WebSocket handshake failed
     pub async fn connect(&mut self) -> Result<()> {
         if self.stream.is_some() {
             warn!("Already connected to {}:{}", self.host, self.port);
@@ -168,23 +286,30 @@ impl TlsClient {
         let timeout_duration = Duration::from_secs(self.timeout_secs);
 
         info!(
-            "Connecting to {} (timeout: {}s, TLS: {})",
-            addr, self.timeout_secs, self.use_tls
+            "Connecting to {} (timeout: {}s, TLS: {}, WebSocket: {})",
+            addr, self.timeout_secs, self.use_tls, self.websocket_path.is_some()
         );
 
-        // Establish TCP connection
-        let tcp_stream = match timeout(timeout_duration, TcpStream::connect(&addr)).await {
+        // Load TLS settings (and any client certificate) before opening the socket
+        let connector = if self.use_tls {
+            Some(self.create_tls_connector()?)
+        } else {
+            None
+        };
+
+        // Establish TCP connection (through the proxy, if any)
+        let connect_future = proxy::open_stream(self.proxy.as_ref(), &self.host, self.port, timeout_duration);
+        let tcp_stream = match timeout(timeout_duration, connect_future).await {
             Ok(Ok(stream)) => {
-                debug!("TCP connection established to {}", addr);
+                // Through a proxy the peer is the proxy, not the server
+                self.server_addr = if self.proxy.is_none() { stream.peer_addr().ok() } else { None };
+                debug!("TCP connection established to {} ({:?})", addr, self.server_addr);
+                self.set_keepalive(&stream);
                 stream
             }
             Ok(Err(e)) => {
                 error!("Connection failed to {}: {}", addr, e);
-                return Err(MushError::ConnectionFailed {
-                    host: self.host.clone(),
-                    port: self.port,
-                    source: e,
-                });
+                return Err(e);
             }
             Err(_) => {
                 error!("Connection timeout after {}s to {}", self.timeout_secs, addr);
@@ -195,8 +320,7 @@ impl TlsClient {
         };
 
         // If TLS enabled, perform TLS handshake
-        if self.use_tls {
-            let connector = self.create_tls_connector()?;
+        if let Some(connector) = connector {
 
             let server_name = ServerName::try_from(self.host.clone())
                 .map_err(|e| MushError::TlsError(
@@ -205,15 +329,30 @@ impl TlsClient {
 
             match timeout(timeout_duration, connector.connect(server_name, tcp_stream)).await {
                 Ok(Ok(tls_stream)) => {
-                    info!("TLS handshake successful with {}", addr);
-                    self.stream = Some(MudStream::Tls(tls_stream));
+                    self.tls_info = Self::negotiated(&tls_stream);
+                    if let Some(tls) = &self.tls_info {
+                        info!(
+                            "TLS handshake successful with {} ({}, {})",
+                            addr, tls.protocol_version, tls.cipher_suite
+                        );
+                    }
+                    self.stream = Some(self.upgrade(tls_stream, |s| MudStream::Tls(Box::new(s))).await?);
                     Ok(())
                 }
                 Ok(Err(e)) => {
                     error!("TLS handshake failed with {}: {}", addr, e);
-                    Err(MushError::TlsError(
-                        format!("TLS handshake failed: {}", e)
-                    ))
+                    let pinned = self.pinned_fingerprint.as_deref().and_then(normalize_fingerprint);
+                    if let (Some(expected), Some(actual)) = (pinned, self.peer_fingerprint()) {
+                        if expected != actual {
+                            return Err(MushError::CertificateChanged {
+                                host: self.host.clone(),
+                                port: self.port,
+                                expected,
+                                actual,
+                            });
+                        }
+                    }
+                    Err(MushError::TlsError(e.to_string()))
                 }
                 Err(_) => {
                     error!("TLS handshake timeout after {}s to {}", self.timeout_secs, addr);
@@ -224,11 +363,60 @@ impl TlsClient {
             }
         } else {
             info!("Plain TCP connection established to {}", addr);
-            self.stream = Some(MudStream::Plain(tcp_stream));
+            self.tls_info = None;
+            self.stream = Some(self.upgrade(tcp_stream, MudStream::Plain).await?);
             Ok(())
         }
     }
 
+    /// Apply the keepalive settings to a new socket (failure is logged, not fatal)
+    fn set_keepalive(&self, stream: &TcpStream) {
+        let Some((idle, interval)) = self.keepalive else {
+            return;
+        };
+        let params = socket2::TcpKeepalive::new().with_time(idle).with_interval(interval);
+        match socket2::SockRef::from(stream).set_tcp_keepalive(&params) {
+            Ok(()) => debug!("TCP keepalive enabled (idle {:?}, interval {:?})", idle, interval),
+            Err(e) => warn!("Failed to enable TCP keepalive: {}", e),
+        }
+    }
+
+    /// Run the WebSocket handshake on an established stream if configured,
+    /// otherwise wrap it with `direct`
+    async fn upgrade<S: ByteStream + 'static>(&self, stream: S, direct: fn(S) -> MudStream) -> Result<MudStream> {
+        let Some(path) = &self.websocket_path else {
+            return Ok(direct(stream));
+        };
+
+        let url = websocket::url(self.use_tls, &self.host, self.port, path);
+        let handshake = WebSocketTransport::connect(Box::new(stream), &url, self.use_tls);
+        match timeout(Duration::from_secs(self.timeout_secs), handshake).await {
+            Ok(Ok(transport)) => Ok(MudStream::WebSocket(Box::new(transport))),
+            Ok(Err(e)) => {
+                error!("{}", e);
+                Err(e)
+            }
+            Err(_) => {
+                error!("WebSocket handshake timeout after {}s to {}", self.timeout_secs, url);
+                Err(MushError::ConnectionTimeout {
+                    timeout_secs: self.timeout_secs,
+                })
+            }
+        }
+    }
+
+    /// Protocol version and cipher suite of a completed TLS handshake
+    fn negotiated(stream: &TlsStream<TcpStream>) -> Option<TlsInfo> {
+        let (_, session) = stream.get_ref();
+        let protocol_version = session.protocol_version()?;
+        let cipher_suite = session.negotiated_cipher_suite()?;
+
+        Some(TlsInfo {
+            protocol_version: format!("{:?}", protocol_version),
+            cipher_suite: format!("{:?}", cipher_suite.suite()),
+        })
+    }
+
     /// Send data to the server
     ///
     /// # Errors
@@ -257,6 +445,9 @@ impl TlsClient {
 
     /// Receive data from the server
     ///
+    /// Cancel safe: if the future is dropped before completing, no data has
+    /// been taken from the stream.
+    ///
     /// # Errors
     /// - `MushError::NotConnected`: Not currently connected to a server
     /// - `MushError::ConnectionFailed`: Read operation failed
@@ -296,7 +487,47 @@ impl TlsClient {
 
     /// Check if using TLS
     pub fn is_tls(&self) -> bool {
-        matches!(self.stream, Some(MudStream::Tls(_)))
+        match &self.stream {
+            Some(MudStream::Tls(_)) => true,
+            Some(MudStream::WebSocket(ws)) => ws.is_secure(),
+            Some(MudStream::Plain(_)) | None => false,
+        }
+    }
+
+    /// Set the SHA-256 fingerprint the server certificate must have
+    ///
+    /// Takes effect on the next `connect`.
+    pub fn set_pinned_fingerprint(&mut self, fingerprint: Option<String>) {
+        self.pinned_fingerprint = fingerprint;
+    }
+
+    /// Get the fingerprint of the certificate the server presented in the last handshake
+    pub fn peer_fingerprint(&self) -> Option<String> {
+        self.observation.lock().ok()?.fingerprint.clone()
+    }
+
+    /// Get how the server certificate was trusted (TLS connections only)
+    pub fn certificate_trust(&self) -> Option<CertificateTrust> {
+        if !self.is_tls() {
+            return None;
+        }
+        self.observation.lock().ok()?.trust.clone()
+    }
+
+    /// Get the negotiated TLS protocol version and cipher suite
+    ///
+    /// Returns `None` for plain TCP connections or when not connected.
+    pub fn tls_info(&self) -> Option<TlsInfo> {
+        self.stream.as_ref()?;
+        self.tls_info.clone()
+    }
+
+    /// Get the resolved server address the connection was made to
+    ///
+    /// Returns `None` when not connected or when connected through a proxy.
+    pub fn server_addr(&self) -> Option<SocketAddr> {
+        self.stream.as_ref()?;
+        self.server_addr
     }
 
     /// Disconnect from the server
@@ -339,6 +570,26 @@ mod tests {
         assert!(result.is_ok(), "Should connect with plain TCP");
         assert!(client.is_connected());
         assert!(!client.is_tls());
+        assert_eq!(client.tls_info(), None);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_keepalive_enabled() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            let _accept = listener.accept().await;
+        });
+
+        let mut client = TlsClient::builder("127.0.0.1", port)
+            .keepalive(Duration::from_secs(60), Duration::from_secs(15))
+            .build();
+        client.connect().await.expect("Should connect");
+
+        match &client.stream {
+            Some(MudStream::Plain(stream)) => assert!(socket2::SockRef::from(stream).keepalive().unwrap()),
+            _ => panic!("Expected a plain TCP stream"),
+        }
     }
 
     #[tokio::test]
@@ -362,4 +613,166 @@ mod tests {
         let sent = client.send(b"test\n").await.expect("Should send");
         assert_eq!(sent, 5);
     }
+
+    #[tokio::test]
+    async fn test_tls_handshake_with_plain_server_fails() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.write_all(b"Welcome to a plain-text MUD!\r\n").await;
+                let mut buf = [0u8; 1024];
+                let _ = socket.read(&mut buf).await;
+            }
+        });
+
+        let mut client = TlsClient::builder("localhost", port)
+            .use_tls(true)
+            .timeout(Duration::from_secs(5))
+            .build();
+
+        let result = client.connect().await;
+        assert!(matches!(result, Err(MushError::TlsError(_))), "got {:?}", result.err());
+        assert!(!client.is_connected());
+        assert_eq!(client.tls_info(), None);
+    }
+
+    /// Helper: Start a TLS echo-less MUD server with a fixture certificate,
+    /// requiring client certificates issued by `client_ca` if given
+    async fn start_tls_server(
+        cert_pem: &'static str,
+        key_pem: &'static str,
+        client_ca: Option<&'static str>,
+        connections: usize,
+    ) -> u16 {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+        use rustls::server::WebPkiClientVerifier;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let certs = vec![CertificateDer::from_pem_slice(cert_pem.as_bytes()).unwrap()];
+        let key = PrivateKeyDer::from_pem_slice(key_pem.as_bytes()).unwrap();
+        let builder = rustls::ServerConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .unwrap();
+        let builder = match client_ca {
+            Some(ca_pem) => {
+                let mut roots = rustls::RootCertStore::empty();
+                roots.add(CertificateDer::from_pem_slice(ca_pem.as_bytes()).unwrap()).unwrap();
+                let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                    .build()
+                    .unwrap();
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let config = builder.with_single_cert(certs, key).unwrap();
+        let acceptor = tokio_rustls::TlsAcceptor::from(Arc::new(config));
+
+        let (listener, port) = start_mock_server().await;
+        tokio::spawn(async move {
+            for _ in 0..connections {
+                if let Ok((socket, _)) = listener.accept().await {
+                    if let Ok(mut tls) = acceptor.accept(socket).await {
+                        let _ = tls.write_all(b"Welcome!\r\n").await;
+                        let _ = tls.flush().await;
+                    }
+                }
+            }
+        });
+        port
+    }
+
+    const SERVER_A_CERT: &str = include_str!("../../tests/fixtures/server_a.pem");
+    const SERVER_A_KEY: &str = include_str!("../../tests/fixtures/server_a.key");
+    const SERVER_B_CERT: &str = include_str!("../../tests/fixtures/server_b.pem");
+    const CLIENT_CA_CERT: &str = include_str!("../../tests/fixtures/client_ca.pem");
+
+    fn fixture_path(name: &str) -> PathBuf {
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    fn fixture_fingerprint(cert_pem: &str) -> String {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::CertificateDer;
+
+        crate::network::pinning::fingerprint(&CertificateDer::from_pem_slice(cert_pem.as_bytes()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_trust_on_first_use_then_pinned() {
+        let port = start_tls_server(SERVER_A_CERT, SERVER_A_KEY, None, 2).await;
+        let fingerprint = fixture_fingerprint(SERVER_A_CERT);
+
+        // First connect: self-signed certificate accepted and reported for pinning
+        let mut client = TlsClient::builder("localhost", port).use_tls(true).build();
+        client.connect().await.expect("Should accept self-signed certificate on first use");
+        assert_eq!(client.certificate_trust(), Some(CertificateTrust::FirstUse(fingerprint.clone())));
+        assert_eq!(client.receive().await.unwrap(), b"Welcome!\r\n");
+        let tls = client.tls_info().unwrap();
+        assert!(tls.protocol_version.starts_with("TLS"));
+        client.disconnect().await.unwrap();
+
+        // Later connect with the pin
+        client.set_pinned_fingerprint(Some(fingerprint.to_lowercase().replace(':', "")));
+        client.connect().await.expect("Should accept pinned certificate");
+        assert_eq!(client.certificate_trust(), Some(CertificateTrust::Pinned));
+    }
+
+    #[tokio::test]
+    async fn test_pinned_certificate_changed() {
+        let port = start_tls_server(SERVER_A_CERT, SERVER_A_KEY, None, 1).await;
+        let pinned = fixture_fingerprint(SERVER_B_CERT);
+
+        let mut client = TlsClient::builder("localhost", port)
+            .use_tls(true)
+            .pinned_fingerprint(Some(pinned.clone()))
+            .build();
+
+        match client.connect().await {
+            Err(MushError::CertificateChanged { expected, actual, .. }) => {
+                assert_eq!(expected, pinned);
+                assert_eq!(actual, fixture_fingerprint(SERVER_A_CERT));
+            }
+            other => panic!("Expected CertificateChanged, got {:?}", other),
+        }
+        assert!(!client.is_connected());
+    }
+
+    #[tokio::test]
+    async fn test_client_certificate_presented() {
+        let port = start_tls_server(SERVER_A_CERT, SERVER_A_KEY, Some(CLIENT_CA_CERT), 2).await;
+
+        let mut client = TlsClient::builder("localhost", port)
+            .use_tls(true)
+            .client_certificate(fixture_path("client.pem"), fixture_path("client.key"))
+            .build();
+        client.connect().await.expect("Should connect with client certificate");
+        assert_eq!(client.receive().await.unwrap(), b"Welcome!\r\n");
+
+        // Without a certificate the server aborts the handshake (TLS 1.3
+        // reports this on the first read rather than in connect)
+        let mut client = TlsClient::builder("localhost", port).use_tls(true).build();
+        let rejected = match client.connect().await {
+            Ok(()) => client.receive().await.is_err(),
+            Err(_) => true,
+        };
+        assert!(rejected, "Server should reject a client without a certificate");
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_client_key_fails_before_connecting() {
+        // Nothing listens here: the key check must fail first
+        let mut client = TlsClient::builder("localhost", 1)
+            .use_tls(true)
+            .client_certificate(fixture_path("client.pem"), fixture_path("server_a.key"))
+            .build();
+
+        let result = client.connect().await;
+        assert!(
+            matches!(result, Err(MushError::ClientCertificateError { .. })),
+            "got {:?}",
+            result.err()
+        );
+    }
 }
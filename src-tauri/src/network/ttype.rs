@@ -0,0 +1,132 @@
+/// TTYPE (Terminal Type, option 24) and MTTS support
+///
+/// Answers IAC SB TTYPE SEND IAC SE by cycling through:
+/// 1. The client name
+/// 2. The terminal type
+/// 3. "MTTS <bitvector>" describing the client's capabilities
+///
+/// Once the list is exhausted the last entry is repeated, which tells the
+/// server the cycle is complete.
+///
+/// References:
+/// - https://www.rfc-editor.org/rfc/rfc1091
+/// - https://tintin.mudhalla.net/protocols/mtts/
+
+use tracing::debug;
+
+/// TTYPE telnet option number
+pub const TELOPT_TTYPE: u8 = 24;
+
+/// TTYPE subnegotiation commands
+pub const TTYPE_IS: u8 = 0;
+pub const TTYPE_SEND: u8 = 1;
+
+/// MTTS capability bits
+pub const MTTS_ANSI: u32 = 1;
+pub const MTTS_VT100: u32 = 2;
+pub const MTTS_UTF8: u32 = 4;
+pub const MTTS_256_COLORS: u32 = 8;
+pub const MTTS_MOUSE_TRACKING: u32 = 16;
+pub const MTTS_OSC_COLOR_PALETTE: u32 = 32;
+pub const MTTS_SCREEN_READER: u32 = 64;
+pub const MTTS_PROXY: u32 = 128;
+pub const MTTS_TRUECOLOR: u32 = 256;
+pub const MTTS_MNES: u32 = 512;
+pub const MTTS_MSLP: u32 = 1024;
+pub const MTTS_SSL: u32 = 2048;
+
+/// Terminal type cycling state
+#[derive(Debug, Clone)]
+pub struct TtypeHandler {
+    /// Names reported in order
+    names: Vec<String>,
+    /// Index of the next name to report
+    index: usize,
+}
+
+impl TtypeHandler {
+    /// Create a handler reporting `client_name`, `terminal_type` and `mtts`
+    pub fn new(client_name: impl Into<String>, terminal_type: impl Into<String>, mtts: u32) -> Self {
+        Self {
+            names: vec![
+                client_name.into().to_uppercase(),
+                terminal_type.into().to_uppercase(),
+                format!("MTTS {}", mtts),
+            ],
+            index: 0,
+        }
+    }
+
+    /// Restart the cycle (TTYPE was renegotiated)
+    pub fn reset(&mut self) {
+        self.index = 0;
+    }
+
+    /// Get the name reported for the next SEND request
+    pub fn current(&self) -> &str {
+        &self.names[self.index.min(self.names.len() - 1)]
+    }
+
+    /// Handle a TTYPE subnegotiation payload
+    ///
+    /// Returns the IS reply payload for a SEND request, `None` otherwise.
+    pub fn handle_subnegotiation(&mut self, data: &[u8]) -> Option<Vec<u8>> {
+        if data.first() != Some(&TTYPE_SEND) {
+            return None;
+        }
+
+        let name = self.current().to_string();
+        debug!("TTYPE SEND -> {}", name);
+
+        if self.index < self.names.len() - 1 {
+            self.index += 1;
+        }
+
+        let mut reply = Vec::with_capacity(name.len() + 1);
+        reply.push(TTYPE_IS);
+        reply.extend_from_slice(name.as_bytes());
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ttype_cycle() {
+        let mut ttype = TtypeHandler::new("MACMush", "xterm-256color", MTTS_ANSI | MTTS_UTF8);
+
+        let reply = ttype.handle_subnegotiation(&[TTYPE_SEND]).unwrap();
+        assert_eq!(reply, b"\x00MACMUSH".to_vec());
+
+        let reply = ttype.handle_subnegotiation(&[TTYPE_SEND]).unwrap();
+        assert_eq!(reply, b"\x00XTERM-256COLOR".to_vec());
+
+        let reply = ttype.handle_subnegotiation(&[TTYPE_SEND]).unwrap();
+        assert_eq!(reply, b"\x00MTTS 5".to_vec());
+
+        // End of list: the last value is repeated
+        let reply = ttype.handle_subnegotiation(&[TTYPE_SEND]).unwrap();
+        assert_eq!(reply, b"\x00MTTS 5".to_vec());
+    }
+
+    #[test]
+    fn test_ttype_reset() {
+        let mut ttype = TtypeHandler::new("MACMUSH", "ANSI", MTTS_ANSI);
+        ttype.handle_subnegotiation(&[TTYPE_SEND]);
+        ttype.handle_subnegotiation(&[TTYPE_SEND]);
+        assert_eq!(ttype.current(), "MTTS 1");
+
+        ttype.reset();
+        assert_eq!(ttype.current(), "MACMUSH");
+    }
+
+    #[test]
+    fn test_ttype_ignores_non_send() {
+        let mut ttype = TtypeHandler::new("MACMUSH", "ANSI", MTTS_ANSI);
+        assert!(ttype.handle_subnegotiation(&[TTYPE_IS, b'X']).is_none());
+        assert!(ttype.handle_subnegotiation(&[]).is_none());
+        assert_eq!(ttype.current(), "MACMUSH");
+    }
+}
@@ -0,0 +1,202 @@
+/// WebSocket transport for web-enabled MUDs
+///
+/// Carries the telnet byte stream in WebSocket messages (RFC 6455), so the
+/// telnet, MCCP and trigger layers above it work unchanged:
+/// - Binary (and text) message payloads are joined into one byte stream;
+///   message boundaries carry no meaning and may split telnet sequences
+/// - Pings are answered with pongs
+/// - A close frame from the server ends the stream like a TCP EOF
+///
+/// The WebSocket runs on the plain or TLS stream opened by `TlsClient`, so
+/// proxies, certificate pinning and client certificates apply to wss:// too.
+
+use crate::error::{MushError, Result};
+use futures_util::{SinkExt, StreamExt};
+use std::io;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, info};
+
+/// Byte stream a WebSocket can run on (TCP or TLS)
+pub trait ByteStream: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send + Sync> ByteStream for T {}
+
+/// Build the WebSocket URL for a server, e.g. "wss://mud.example.com:4443/ws"
+pub fn url(secure: bool, host: &str, port: u16, path: &str) -> String {
+    let scheme = if secure { "wss" } else { "ws" };
+    let host = if host.contains(':') { format!("[{}]", host) } else { host.to_string() };
+    let path = if path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+    format!("{}://{}:{}{}", scheme, host, port, path)
+}
+
+/// WebSocket connection exposing the byte-stream interface of `MudStream`
+pub struct WebSocketTransport {
+    ws: WebSocketStream<Box<dyn ByteStream>>,
+    /// Received payload not yet returned by `read`
+    pending: Vec<u8>,
+    secure: bool,
+}
+
+impl WebSocketTransport {
+    /// Perform the WebSocket handshake for `url` on an open stream
+    ///
+    /// # Errors
+    /// - `MushError::WebSocketError`: The server refused the upgrade
+    pub async fn connect(stream: Box<dyn ByteStream>, url: &str, secure: bool) -> Result<Self> {
+        let (ws, response) = tokio_tungstenite::client_async(url, stream)
+            .await
+            .map_err(|e| MushError::WebSocketError(format!("Handshake with {} failed: {}", url, e)))?;
+
+        info!("WebSocket connected to {} (HTTP {})", url, response.status());
+        Ok(Self {
+            ws,
+            pending: Vec::new(),
+            secure,
+        })
+    }
+
+    /// Whether the WebSocket runs over TLS
+    pub fn is_secure(&self) -> bool {
+        self.secure
+    }
+
+    /// Read payload bytes; returns 0 once the server closed the WebSocket
+    pub async fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() {
+            match self.ws.next().await {
+                Some(Ok(Message::Binary(data))) => self.pending.extend_from_slice(&data),
+                Some(Ok(Message::Text(text))) => self.pending.extend_from_slice(text.as_bytes()),
+                Some(Ok(Message::Ping(_))) => {
+                    // The pong is queued by tungstenite; push it out now
+                    debug!("WebSocket ping received");
+                    self.ws.flush().await.map_err(to_io_error)?;
+                }
+                Some(Ok(Message::Pong(_) | Message::Frame(_))) => {}
+                Some(Ok(Message::Close(frame))) => {
+                    debug!("WebSocket closed by server: {:?}", frame);
+                    return Ok(0);
+                }
+                Some(Err(WsError::ConnectionClosed | WsError::AlreadyClosed)) | None => return Ok(0),
+                Some(Err(e)) => return Err(to_io_error(e)),
+            }
+        }
+
+        let n = buf.len().min(self.pending.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+
+    /// Send bytes as one binary message
+    pub async fn write_all(&mut self, data: &[u8]) -> io::Result<()> {
+        self.ws
+            .send(Message::Binary(data.to_vec().into()))
+            .await
+            .map_err(to_io_error)
+    }
+
+    /// Send a close frame and close the underlying stream
+    pub async fn shutdown(&mut self) -> io::Result<()> {
+        match self.ws.close(None).await {
+            Ok(()) | Err(WsError::ConnectionClosed | WsError::AlreadyClosed) => Ok(()),
+            Err(e) => Err(to_io_error(e)),
+        }
+    }
+}
+
+fn to_io_error(e: WsError) -> io::Error {
+    match e {
+        WsError::Io(e) => e,
+        e => io::Error::other(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn test_url() {
+        assert_eq!(url(false, "mud.example.com", 4000, "/"), "ws://mud.example.com:4000/");
+        assert_eq!(url(true, "mud.example.com", 4443, "ws"), "wss://mud.example.com:4443/ws");
+        assert_eq!(url(false, "::1", 4000, "/telnet"), "ws://[::1]:4000/telnet");
+    }
+
+    #[tokio::test]
+    async fn test_messages_form_a_byte_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+
+            ws.send(Message::Binary(b"Hel".to_vec().into())).await.unwrap();
+            ws.send(Message::Ping(b"beat".to_vec().into())).await.unwrap();
+            ws.send(Message::Binary(b"lo!\r\n".to_vec().into())).await.unwrap();
+
+            // Pong for our ping, then the client's command
+            let mut received = Vec::new();
+            while received.len() < 2 {
+                match ws.next().await {
+                    Some(Ok(message)) => received.push(message),
+                    _ => break,
+                }
+            }
+            let _ = tx.send(received);
+            ws.close(None).await.unwrap();
+        });
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut transport = WebSocketTransport::connect(Box::new(stream), &url(false, "127.0.0.1", port, "/"), false)
+            .await
+            .unwrap();
+        assert!(!transport.is_secure());
+
+        // Small reads cut through message boundaries
+        let mut text = Vec::new();
+        let mut buf = [0u8; 2];
+        while !text.ends_with(b"\r\n") {
+            let n = transport.read(&mut buf).await.unwrap();
+            assert!(n > 0);
+            text.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(text, b"Hello!\r\n");
+
+        transport.write_all(b"look\r\n").await.unwrap();
+        let received = rx.await.unwrap();
+        assert_eq!(
+            received,
+            vec![
+                Message::Pong(b"beat".to_vec().into()),
+                Message::Binary(b"look\r\n".to_vec().into()),
+            ]
+        );
+
+        // Close frame ends the stream
+        assert_eq!(transport.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_handshake_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let _ = socket.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await;
+        });
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let result = WebSocketTransport::connect(Box::new(stream), &url(false, "127.0.0.1", port, "/"), false).await;
+        assert!(matches!(result, Err(MushError::WebSocketError(_))));
+    }
+}
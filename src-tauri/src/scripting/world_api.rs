@@ -3,18 +3,31 @@
 /// Implements the MUSHclient scripting API that scripts can call via the `world` object.
 /// Reference: https://www.gammon.com.au/scripts/doc.php?general=lua
 
+use crate::core::{RateLimit, SendQueue};
 use crate::error::Result;
-use mlua::{Lua, Table};
+use crate::network::{GmcpMessage, GmcpStore, MsdpCommand};
+use mlua::{Lua, LuaSerdeExt, SerializeOptions, Table};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tracing::{debug, info};
 
+/// MSDP command and arguments queued by world.SendMSDP()
+pub type MsdpRequest = (MsdpCommand, Vec<String>);
+
 /// World API function registry
 pub struct WorldApi {
     world_id: String,
     variables: Arc<Mutex<HashMap<String, String>>>,
     /// Command queue for world.Send() calls
     command_queue: Arc<Mutex<Vec<String>>>,
+    /// Latest GMCP data, shared with the session
+    gmcp: Arc<Mutex<GmcpStore>>,
+    /// GMCP queue for world.SendGMCP() calls
+    gmcp_queue: Arc<Mutex<Vec<GmcpMessage>>>,
+    /// MSDP queue for world.SendMSDP() calls
+    msdp_queue: Arc<Mutex<Vec<MsdpRequest>>>,
+    /// Outgoing command queue, shared with the connection
+    send_queue: Arc<Mutex<SendQueue>>,
 }
 
 impl WorldApi {
@@ -24,9 +37,21 @@ impl WorldApi {
             world_id: world_id.into(),
             variables: Arc::new(Mutex::new(HashMap::new())),
             command_queue: Arc::new(Mutex::new(Vec::new())),
+            gmcp: Arc::new(Mutex::new(GmcpStore::new())),
+            gmcp_queue: Arc::new(Mutex::new(Vec::new())),
+            msdp_queue: Arc::new(Mutex::new(Vec::new())),
+            send_queue: Arc::new(Mutex::new(SendQueue::new(RateLimit::default()))),
         }
     }
 
+    /// Use the connection's send queue for world.GetQueue() and friends
+    ///
+    /// Must be called before `register_functions`.
+    pub fn with_send_queue(mut self, send_queue: Arc<Mutex<SendQueue>>) -> Self {
+        self.send_queue = send_queue;
+        self
+    }
+
     /// Register all world API functions with Lua runtime
     pub fn register_functions(&self, lua: &Lua) -> Result<()> {
         info!("Registering World API functions for world '{}'", self.world_id);
@@ -54,6 +79,18 @@ impl WorldApi {
         // Register world.GetInfo(info_type)
         self.register_get_info(lua, &world_table)?;
 
+        // Register world.SendGMCP(package, data)
+        self.register_send_gmcp(lua, &world_table)?;
+
+        // Register world.GetGMCP(package)
+        self.register_get_gmcp(lua, &world_table)?;
+
+        // Register world.SendMSDP(command, ...)
+        self.register_send_msdp(lua, &world_table)?;
+
+        // Register world.GetQueue(), world.DiscardQueue(), world.SetQueuePaused(paused)
+        self.register_send_queue(lua, &world_table)?;
+
         // Set world table as global
         lua.globals().set("world", world_table)?;
 
@@ -178,6 +215,109 @@ impl WorldApi {
         Ok(())
     }
 
+    /// Register world.SendGMCP(package, data) - Send a GMCP message
+    ///
+    /// `data` is converted to JSON. A single argument containing a space is
+    /// treated as a complete message, e.g. `world.SendGMCP('Core.Ping')` or
+    /// `world.SendGMCP('Char.Skills.Get {"group": "combat"}')`.
+    fn register_send_gmcp(&self, lua: &Lua, world_table: &Table) -> Result<()> {
+        let gmcp_queue = Arc::clone(&self.gmcp_queue);
+
+        let send_gmcp_fn = lua.create_function(move |lua, (package, data): (String, Option<mlua::Value>)| {
+            let message = match data {
+                Some(data) => GmcpMessage::new(package, lua.from_value(data)?),
+                None => GmcpMessage::parse(package.as_bytes()).map_err(mlua::Error::external)?,
+            };
+
+            info!("[Lua SendGMCP] {}", message.package);
+            gmcp_queue.lock().unwrap().push(message);
+            Ok(())
+        })?;
+
+        world_table.set("SendGMCP", send_gmcp_fn)?;
+        debug!("Registered world.SendGMCP()");
+        Ok(())
+    }
+
+    /// Register world.GetGMCP(package) - Get the latest GMCP data
+    ///
+    /// Returns the whole package tree when called without a package name.
+    fn register_get_gmcp(&self, lua: &Lua, world_table: &Table) -> Result<()> {
+        let gmcp = Arc::clone(&self.gmcp);
+
+        let get_gmcp_fn = lua.create_function(move |lua, package: Option<String>| {
+            let gmcp = gmcp.lock().unwrap();
+            let value = match package {
+                Some(package) => gmcp.get(&package),
+                None => Some(gmcp.tree()),
+            };
+
+            let options = SerializeOptions::new()
+                .serialize_none_to_null(false)
+                .serialize_unit_to_null(false);
+
+            match value {
+                Some(value) => lua.to_value_with(value, options),
+                None => Ok(mlua::Value::Nil),
+            }
+        })?;
+
+        world_table.set("GetGMCP", get_gmcp_fn)?;
+        debug!("Registered world.GetGMCP()");
+        Ok(())
+    }
+
+    /// Register world.SendMSDP(command, ...) - Send an MSDP command
+    ///
+    /// e.g. `world.SendMSDP("REPORT", "HEALTH", "MANA")`
+    fn register_send_msdp(&self, lua: &Lua, world_table: &Table) -> Result<()> {
+        let msdp_queue = Arc::clone(&self.msdp_queue);
+
+        let send_msdp_fn = lua.create_function(move |_lua, (command, values): (String, mlua::Variadic<String>)| {
+            let command: MsdpCommand = command.parse().map_err(mlua::Error::external)?;
+            info!("[Lua SendMSDP] {} {:?}", command, *values);
+            msdp_queue.lock().unwrap().push((command, values.to_vec()));
+            Ok(())
+        })?;
+
+        world_table.set("SendMSDP", send_msdp_fn)?;
+        debug!("Registered world.SendMSDP()");
+        Ok(())
+    }
+
+    /// Register the send queue functions
+    ///
+    /// - `world.GetQueue()`: commands waiting to be sent (rate limit, pause or flood guard)
+    /// - `world.DiscardQueue()`: drop them, returning how many there were
+    /// - `world.SetQueuePaused(paused)`: pause or resume sending (resuming resets the flood guard)
+    fn register_send_queue(&self, lua: &Lua, world_table: &Table) -> Result<()> {
+        let send_queue = Arc::clone(&self.send_queue);
+        let get_queue_fn = lua.create_function(move |lua, ()| {
+            let status = send_queue.lock().unwrap().status();
+            lua.create_sequence_from(status.commands.into_iter().map(|queued| queued.command))
+        })?;
+
+        let send_queue = Arc::clone(&self.send_queue);
+        let discard_queue_fn = lua.create_function(move |_lua, ()| {
+            let discarded = send_queue.lock().unwrap().clear();
+            info!("[Lua DiscardQueue] {} command(s)", discarded);
+            Ok(discarded)
+        })?;
+
+        let send_queue = Arc::clone(&self.send_queue);
+        let set_paused_fn = lua.create_function(move |_lua, paused: bool| {
+            info!("[Lua SetQueuePaused] {}", paused);
+            send_queue.lock().unwrap().set_paused(paused);
+            Ok(())
+        })?;
+
+        world_table.set("GetQueue", get_queue_fn)?;
+        world_table.set("DiscardQueue", discard_queue_fn)?;
+        world_table.set("SetQueuePaused", set_paused_fn)?;
+        debug!("Registered world.GetQueue(), world.DiscardQueue(), world.SetQueuePaused()");
+        Ok(())
+    }
+
     /// Get variable value (for Rust code access)
     pub fn get_variable(&self, name: &str) -> Option<String> {
         self.variables.lock().unwrap().get(name).cloned()
@@ -213,6 +353,23 @@ impl WorldApi {
     pub fn clear_command_queue(&self) {
         self.command_queue.lock().unwrap().clear();
     }
+
+    /// Get the GMCP store shared with scripts
+    pub fn gmcp_store(&self) -> Arc<Mutex<GmcpStore>> {
+        Arc::clone(&self.gmcp)
+    }
+
+    /// Get and clear all queued commands from world.SendMSDP() calls
+    pub fn drain_msdp_queue(&self) -> Vec<MsdpRequest> {
+        let mut queue = self.msdp_queue.lock().unwrap();
+        std::mem::take(&mut *queue)
+    }
+
+    /// Get and clear all queued messages from world.SendGMCP() calls
+    pub fn drain_gmcp_queue(&self) -> Vec<GmcpMessage> {
+        let mut queue = self.gmcp_queue.lock().unwrap();
+        std::mem::take(&mut *queue)
+    }
 }
 
 #[cfg(test)]
@@ -376,4 +533,93 @@ mod tests {
         assert_eq!(commands.len(), 1);
         assert_eq!(commands[0], "kill orc");
     }
+
+    #[test]
+    fn test_lua_gmcp() {
+        let api = WorldApi::new("test-world");
+        let lua = Lua::new();
+
+        api.register_functions(&lua).unwrap();
+
+        api.gmcp_store()
+            .lock()
+            .unwrap()
+            .update("Char.Vitals", serde_json::json!({"hp": 42, "maxhp": 100}));
+
+        let hp: i64 = lua.load(r#"return world.GetGMCP("Char.Vitals").hp"#).eval().unwrap();
+        assert_eq!(hp, 42);
+
+        let maxhp: i64 = lua.load(r#"return world.GetGMCP().Char.Vitals.maxhp"#).eval().unwrap();
+        assert_eq!(maxhp, 100);
+
+        let missing: Option<String> = lua.load(r#"return world.GetGMCP("Room.Info")"#).eval().unwrap();
+        assert!(missing.is_none());
+
+        lua.load(r#"
+            world.SendGMCP("Core.Ping")
+            world.SendGMCP("Char.Skills.Get", { group = "combat" })
+            world.SendGMCP('Comm.Channel.Players {"all": true}')
+        "#)
+        .exec()
+        .unwrap();
+
+        let queued = api.drain_gmcp_queue();
+        assert_eq!(queued.len(), 3);
+        assert_eq!(queued[0], GmcpMessage::new("Core.Ping", serde_json::Value::Null));
+        assert_eq!(queued[1].package, "Char.Skills.Get");
+        assert_eq!(queued[1].data["group"], "combat");
+        assert_eq!(queued[2].data["all"], true);
+        assert!(api.drain_gmcp_queue().is_empty());
+    }
+
+    #[test]
+    fn test_lua_send_msdp() {
+        let api = WorldApi::new("test-world");
+        let lua = Lua::new();
+
+        api.register_functions(&lua).unwrap();
+
+        lua.load(r#"
+            world.SendMSDP("LIST", "REPORTABLE_VARIABLES")
+            world.SendMSDP("report", "HEALTH", "MANA")
+        "#)
+        .exec()
+        .unwrap();
+
+        let queued = api.drain_msdp_queue();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0], (MsdpCommand::List, vec!["REPORTABLE_VARIABLES".to_string()]));
+        assert_eq!(queued[1], (MsdpCommand::Report, vec!["HEALTH".to_string(), "MANA".to_string()]));
+
+        assert!(lua.load(r#"world.SendMSDP("BOGUS")"#).exec().is_err());
+    }
+
+    #[test]
+    fn test_lua_outgoing_queue() {
+        use crate::core::CommandOrigin;
+        use tokio::time::Instant;
+
+        let send_queue = Arc::new(Mutex::new(SendQueue::new(RateLimit::default())));
+        let api = WorldApi::new("test-world").with_send_queue(Arc::clone(&send_queue));
+        let lua = Lua::new();
+
+        api.register_functions(&lua).unwrap();
+
+        lua.load("world.SetQueuePaused(true)").exec().unwrap();
+        {
+            let mut queue = send_queue.lock().unwrap();
+            assert!(queue.is_paused());
+            queue.push("north", CommandOrigin::User, Instant::now());
+            queue.push("get all", CommandOrigin::Automation, Instant::now());
+        }
+
+        let queued: Vec<String> = lua.load("return world.GetQueue()").eval().unwrap();
+        assert_eq!(queued, vec!["north", "get all"]);
+
+        let discarded: usize = lua.load("return world.DiscardQueue()").eval().unwrap();
+        assert_eq!(discarded, 2);
+        lua.load("world.SetQueuePaused(false)").exec().unwrap();
+        assert!(!send_queue.lock().unwrap().is_paused());
+        assert!(send_queue.lock().unwrap().is_empty());
+    }
 }
@@ -6,13 +6,14 @@ use crate::automation::triggers::{Trigger, TriggerAction};
 use crate::automation::timers::{Timer, TimerAction, TimerType};
 use crate::automation::aliases::{Alias, AliasAction};
 use crate::automation::highlights::Highlight as AutoHighlight;
-use crate::core::{Session, World};
+use crate::core::{AntiIdle, ClientCertificate, Connection, LineEnding, RateLimit, ReconnectPolicy, SendQueueStatus, Session, TcpKeepalive, Transport, World};
+use crate::network::{CertificatePin, CertificateTrust, CompressionStats, EnvironOverride, MsspData, PinStore, ProxyConfig, TextEncoding};
 use crate::ui::events::{start_data_receiver, start_event_forwarder};
 use crate::ui::state::AppState;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager, State};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
@@ -20,7 +21,7 @@ use uuid::Uuid;
 /// Result type for Tauri commands (serializable error strings)
 type CommandResult<T> = Result<T, String>;
 
-/// Connection request from frontend
+/// Quick connect request from frontend, for a world that isn't saved
 #[derive(Debug, Deserialize)]
 pub struct ConnectRequest {
     pub name: String,
@@ -28,6 +29,44 @@ pub struct ConnectRequest {
     pub port: u16,
     #[serde(default)]
     pub use_tls: bool,
+    #[serde(default)]
+    pub client_certificate: Option<ClientCertificate>,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default)]
+    pub tcp_keepalive: TcpKeepalive,
+    #[serde(default)]
+    pub anti_idle: AntiIdle,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    /// Defaults to the app's sounds directory
+    #[serde(default)]
+    pub sounds_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub new_environ: Vec<EnvironOverride>,
+}
+
+impl ConnectRequest {
+    /// World configuration for the request; other settings keep their defaults
+    fn into_world(self) -> Result<World, String> {
+        World::builder(&self.name, &self.host, self.port)
+            .use_tls(self.use_tls)
+            .client_certificate(self.client_certificate)
+            .reconnect(self.reconnect)
+            .proxy(self.proxy)
+            .transport(self.transport)
+            .tcp_keepalive(self.tcp_keepalive)
+            .anti_idle(self.anti_idle)
+            .rate_limit(self.rate_limit)
+            .sounds_dir(self.sounds_dir)
+            .new_environ(self.new_environ)
+            .build()
+            .map_err(|e| format!("Invalid world configuration: {}", e))
+    }
 }
 
 /// Trigger creation request from frontend
@@ -52,26 +91,33 @@ pub struct ConnectionStatus {
 }
 
 /// Connect to a MUD server
+///
+/// Connects with the saved world `world_id` (all its settings), or with a
+/// quick connect `request` when no world is given.
 #[tauri::command]
 pub async fn connect_to_world(
-    request: ConnectRequest,
+    world_id: Option<String>,
+    request: Option<ConnectRequest>,
     app_handle: AppHandle,
     state: State<'_, AppState>,
 ) -> CommandResult<ConnectionStatus> {
-    info!(
-        "Connect request: {} ({}:{})",
-        request.name, request.host, request.port
-    );
-
     // Check if already connected
     if state.is_connected().await {
         error!("Already connected to a MUD");
         return Err("Already connected. Disconnect first.".to_string());
     }
 
-    // Create world configuration
-    let world = World::new(&request.name, &request.host, request.port)
-        .map_err(|e| format!("Invalid world configuration: {}", e))?;
+    // Load or create world configuration
+    let mut world = match (world_id, request) {
+        (Some(id), _) => load_world(&get_worlds_dir(&app_handle)?, &id)?,
+        (None, Some(request)) => request.into_world()?,
+        (None, None) => return Err("No world to connect to".to_string()),
+    };
+    if world.sounds_dir.is_none() {
+        world.sounds_dir = Some(get_sounds_dir(&app_handle)?);
+    }
+
+    info!("Connect request: {} ({}:{})", world.name, world.host, world.port);
 
     // Create new session
     let mut session = Session::new(world.clone(), state.event_bus.clone())
@@ -87,13 +133,15 @@ pub async fn connect_to_world(
     );
 
     // Attempt connection
-    session
-        .start()
-        .await
-        .map_err(|e| format!("Connection failed: {}", e))?;
+    let window_size = *state.window_size.lock().await;
+    let pins = PinStore::load(&get_pins_path(&app_handle)?).map_err(|e| format!("Failed to load certificate pins: {}", e))?;
+    start_session(&mut session, window_size, &pins).await?;
 
     info!("Successfully connected to {}", world.name);
 
+    // Trust on first use: pin the self-signed certificate just accepted
+    save_first_use_pin(&app_handle, &session)?;
+
     // Store session in state
     *state.session.lock().await = Some(session);
 
@@ -112,6 +160,27 @@ pub async fn connect_to_world(
     })
 }
 
+/// Start a new session's connection
+///
+/// Reports `window_size` once NAWS is negotiated and requires a certificate
+/// pinned for the world to match.
+async fn start_session(session: &mut Session, window_size: (u16, u16), pins: &PinStore) -> Result<(), String> {
+    let (width, height) = window_size;
+    session
+        .set_window_size(width, height)
+        .await
+        .map_err(|e| format!("Failed to set window size: {}", e))?;
+
+    let world = session.world();
+    let pin = pins.get(&world.host, world.port).map(|pin| pin.fingerprint.clone());
+    session.set_certificate_pin(pin);
+
+    session
+        .start()
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))
+}
+
 /// Disconnect from current MUD server
 #[tauri::command]
 pub async fn disconnect(state: State<'_, AppState>) -> CommandResult<ConnectionStatus> {
@@ -150,13 +219,15 @@ pub async fn disconnect(state: State<'_, AppState>) -> CommandResult<ConnectionS
 /// Send command to MUD server
 #[tauri::command]
 pub async fn send_command(command: String, state: State<'_, AppState>) -> CommandResult<()> {
-    debug!("Send command: {}", command);
-
     // Hold lock and send command
     let mut session_guard = state.session.lock().await;
 
     match session_guard.as_mut() {
         Some(session) => {
+            if !session.is_input_masked() {
+                debug!("Send command: {}", command);
+            }
+
             session
                 .send_command(&command)
                 .await
@@ -166,6 +237,76 @@ pub async fn send_command(command: String, state: State<'_, AppState>) -> Comman
     }
 }
 
+/// Run the command of a clicked MXP `<send>` link
+///
+/// The command goes through speedwalk and alias expansion like typed
+/// input but is kept out of the command history.
+#[tauri::command]
+pub async fn send_mxp_link(command: String, state: State<'_, AppState>) -> CommandResult<()> {
+    let mut session_guard = state.session.lock().await;
+
+    match session_guard.as_mut() {
+        Some(session) => session
+            .send_link_command(&command)
+            .await
+            .map_err(|e| format!("Failed to send link command: {}", e)),
+        None => Err("Not connected".to_string()),
+    }
+}
+
+/// Read a sound file named by a PlaySound event
+///
+/// Only files inside the connected world's sounds directory can be read.
+#[tauri::command]
+pub async fn read_sound_file(path: PathBuf, state: State<'_, AppState>) -> CommandResult<Vec<u8>> {
+    let sounds_dir = {
+        let session_guard = state.session.lock().await;
+        match session_guard.as_ref() {
+            Some(session) => session.world().sounds_dir.clone(),
+            None => return Err("Not connected".to_string()),
+        }
+    };
+
+    let sounds_dir = sounds_dir
+        .and_then(|dir| dir.canonicalize().ok())
+        .ok_or_else(|| "No sounds directory".to_string())?;
+    let path = path
+        .canonicalize()
+        .map_err(|e| format!("Sound file not found: {}", e))?;
+    if !path.starts_with(&sounds_dir) {
+        warn!("Refusing to read sound outside {}: {}", sounds_dir.display(), path.display());
+        return Err("Sound file is outside the sounds directory".to_string());
+    }
+
+    fs::read(&path).map_err(|e| format!("Failed to read sound file: {}", e))
+}
+
+/// Update the output window size (in characters)
+///
+/// The size is remembered for future connections and sent to the server
+/// via NAWS when the current session has negotiated it.
+#[tauri::command]
+pub async fn set_window_size(
+    width: u16,
+    height: u16,
+    state: State<'_, AppState>,
+) -> CommandResult<()> {
+    debug!("Window size: {}x{}", width, height);
+
+    *state.window_size.lock().await = (width, height);
+
+    let mut session_guard = state.session.lock().await;
+
+    if let Some(session) = session_guard.as_mut() {
+        session
+            .set_window_size(width, height)
+            .await
+            .map_err(|e| format!("Failed to send window size: {}", e))?;
+    }
+
+    Ok(())
+}
+
 /// Add trigger to current session
 #[tauri::command]
 pub async fn add_trigger(
@@ -266,6 +407,22 @@ fn get_logs_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(logs_dir)
 }
 
+/// Get default sounds directory path
+fn get_sounds_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    let sounds_dir = app_data_dir.join("sounds");
+
+    // Create sounds directory if it doesn't exist
+    fs::create_dir_all(&sounds_dir)
+        .map_err(|e| format!("Failed to create sounds directory: {}", e))?;
+
+    Ok(sounds_dir)
+}
+
 /// Generate log filename with timestamp
 fn generate_log_filename(world_name: &str, format: &str) -> String {
     let now = chrono::Local::now();
@@ -392,6 +549,14 @@ pub async fn write_log_entry(
     message_type: String,
     state: State<'_, AppState>,
 ) -> CommandResult<()> {
+    // Never log what is typed while the server is echoing (passwords)
+    if message_type == "command" {
+        let session_guard = state.session.lock().await;
+        if session_guard.as_ref().is_some_and(|s| s.is_input_masked()) {
+            return Ok(());
+        }
+    }
+
     let log_file_guard = state.log_file.lock().await;
     let log_format_guard = state.log_format.lock().await;
 
@@ -740,6 +905,44 @@ pub struct CreateWorldRequest {
     pub timeout_secs: u64,
     #[serde(default)]
     pub use_tls: bool,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    #[serde(default = "default_true")]
+    pub colors_256: bool,
+    #[serde(default = "default_true")]
+    pub truecolor: bool,
+    #[serde(default = "default_true")]
+    pub mccp: bool,
+    #[serde(default = "crate::core::world::default_gmcp_modules")]
+    pub gmcp_modules: Vec<String>,
+    #[serde(default = "crate::core::world::default_msdp_variable_prefix")]
+    pub msdp_variable_prefix: String,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    #[serde(default)]
+    pub client_certificate: Option<ClientCertificate>,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    #[serde(default)]
+    pub connect_commands: Vec<String>,
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default)]
+    pub tcp_keepalive: TcpKeepalive,
+    #[serde(default)]
+    pub anti_idle: AntiIdle,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default = "default_true")]
+    pub mxp: bool,
+    #[serde(default = "default_true")]
+    pub msp: bool,
+    #[serde(default)]
+    pub sounds_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub new_environ: Vec<EnvironOverride>,
 }
 
 fn default_timeout() -> u64 {
@@ -761,6 +964,44 @@ pub struct UpdateWorldRequest {
     pub timeout_secs: u64,
     #[serde(default)]
     pub use_tls: bool,
+    #[serde(default)]
+    pub line_ending: LineEnding,
+    #[serde(default = "default_true")]
+    pub colors_256: bool,
+    #[serde(default = "default_true")]
+    pub truecolor: bool,
+    #[serde(default = "default_true")]
+    pub mccp: bool,
+    #[serde(default = "crate::core::world::default_gmcp_modules")]
+    pub gmcp_modules: Vec<String>,
+    #[serde(default = "crate::core::world::default_msdp_variable_prefix")]
+    pub msdp_variable_prefix: String,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    #[serde(default)]
+    pub client_certificate: Option<ClientCertificate>,
+    #[serde(default)]
+    pub reconnect: ReconnectPolicy,
+    #[serde(default)]
+    pub connect_commands: Vec<String>,
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default)]
+    pub tcp_keepalive: TcpKeepalive,
+    #[serde(default)]
+    pub anti_idle: AntiIdle,
+    #[serde(default)]
+    pub rate_limit: RateLimit,
+    #[serde(default = "default_true")]
+    pub mxp: bool,
+    #[serde(default = "default_true")]
+    pub msp: bool,
+    #[serde(default)]
+    pub sounds_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub new_environ: Vec<EnvironOverride>,
 }
 
 /// Get worlds directory path
@@ -779,6 +1020,83 @@ fn get_worlds_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
     Ok(worlds_dir)
 }
 
+/// Get certificate pin store path
+fn get_pins_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data dir: {}", e))?;
+
+    fs::create_dir_all(&app_data_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    Ok(app_data_dir.join("certificate_pins.json"))
+}
+
+/// Persist the certificate a session just trusted on first use
+///
+/// Called after every successful connect, including reconnects; does
+/// nothing unless the last handshake pinned a new certificate.
+pub(crate) fn save_first_use_pin(app_handle: &AppHandle, session: &Session) -> Result<(), String> {
+    let Some(CertificateTrust::FirstUse(fingerprint)) = session.certificate_trust() else {
+        return Ok(());
+    };
+
+    let world = session.world();
+    let path = get_pins_path(app_handle)?;
+    let mut pins = PinStore::load(&path).map_err(|e| format!("Failed to load certificate pins: {}", e))?;
+    pins.pin(&world.host, world.port, &fingerprint)
+        .and_then(|_| pins.save(&path))
+        .map_err(|e| format!("Failed to save certificate pin: {}", e))?;
+    Ok(())
+}
+
+/// List pinned server certificates
+#[tauri::command]
+pub async fn list_certificate_pins(app_handle: AppHandle) -> CommandResult<Vec<CertificatePin>> {
+    let pins = PinStore::load(&get_pins_path(&app_handle)?)
+        .map_err(|e| format!("Failed to load certificate pins: {}", e))?;
+
+    Ok(pins.list())
+}
+
+/// Pin a server certificate fingerprint (e.g. accept a changed certificate)
+#[tauri::command]
+pub async fn accept_certificate_pin(
+    host: String,
+    port: u16,
+    fingerprint: String,
+    app_handle: AppHandle,
+) -> CommandResult<CertificatePin> {
+    info!("Accepting certificate for {}:{}", host, port);
+
+    let path = get_pins_path(&app_handle)?;
+    let mut pins = PinStore::load(&path).map_err(|e| format!("Failed to load certificate pins: {}", e))?;
+    let pin = pins
+        .pin(&host, port, &fingerprint)
+        .map_err(|e| format!("Invalid fingerprint: {}", e))?;
+    pins.save(&path)
+        .map_err(|e| format!("Failed to save certificate pins: {}", e))?;
+
+    Ok(pin)
+}
+
+/// Revoke a pinned server certificate; the next connect trusts on first use again
+#[tauri::command]
+pub async fn revoke_certificate_pin(host: String, port: u16, app_handle: AppHandle) -> CommandResult<bool> {
+    info!("Revoking certificate pin for {}:{}", host, port);
+
+    let path = get_pins_path(&app_handle)?;
+    let mut pins = PinStore::load(&path).map_err(|e| format!("Failed to load certificate pins: {}", e))?;
+    let revoked = pins.revoke(&host, port);
+    if revoked {
+        pins.save(&path)
+            .map_err(|e| format!("Failed to save certificate pins: {}", e))?;
+    }
+
+    Ok(revoked)
+}
+
 /// Create a new world configuration
 #[tauri::command]
 pub async fn create_world(
@@ -793,6 +1111,25 @@ pub async fn create_world(
         .auto_connect(request.auto_connect)
         .timeout_secs(request.timeout_secs)
         .use_tls(request.use_tls)
+        .line_ending(request.line_ending)
+        .colors_256(request.colors_256)
+        .truecolor(request.truecolor)
+        .mccp(request.mccp)
+        .gmcp_modules(request.gmcp_modules)
+        .msdp_variable_prefix(request.msdp_variable_prefix)
+        .encoding(request.encoding)
+        .client_certificate(request.client_certificate)
+        .reconnect(request.reconnect)
+        .connect_commands(request.connect_commands)
+        .proxy(request.proxy)
+        .transport(request.transport)
+        .tcp_keepalive(request.tcp_keepalive)
+        .anti_idle(request.anti_idle)
+        .rate_limit(request.rate_limit)
+        .mxp(request.mxp)
+        .msp(request.msp)
+        .sounds_dir(request.sounds_dir)
+        .new_environ(request.new_environ)
         .build()
         .map_err(|e| format!("Failed to create world: {}", e))?;
 
@@ -851,18 +1188,80 @@ pub async fn list_worlds(app_handle: AppHandle) -> CommandResult<Vec<World>> {
 pub async fn get_world(id: String, app_handle: AppHandle) -> CommandResult<World> {
     info!("Getting world: {}", id);
 
-    // Get worlds directory
-    let worlds_dir = get_worlds_dir(&app_handle)?;
+    load_world(&get_worlds_dir(&app_handle)?, &id)
+}
 
-    // Read world file
+/// Read a saved world from the worlds directory
+fn load_world(worlds_dir: &Path, id: &str) -> Result<World, String> {
     let world_file = worlds_dir.join(format!("{}.json", id));
     let json = fs::read_to_string(&world_file)
         .map_err(|e| format!("World not found: {}", e))?;
 
-    let world: World = serde_json::from_str(&json)
-        .map_err(|e| format!("Failed to parse world: {}", e))?;
+    serde_json::from_str(&json)
+        .map_err(|e| format!("Failed to parse world: {}", e))
+}
 
-    Ok(world)
+/// Probe a saved world's MSSP server status without starting a session
+///
+/// Connects, negotiates MSSP, collects the status and disconnects within
+/// the world's timeout.
+#[tauri::command]
+pub async fn probe_world(id: String, app_handle: AppHandle) -> CommandResult<MsspData> {
+    let world = get_world(id, app_handle).await?;
+    info!("Probing world: {}", world.name);
+
+    Connection::probe_mssp(world)
+        .await
+        .map_err(|e| format!("Failed to probe world: {}", e))
+}
+
+/// Get the MSSP server status reported by the current session
+#[tauri::command]
+pub async fn get_mssp_status(state: State<'_, AppState>) -> CommandResult<Option<MsspData>> {
+    let session_guard = state.session.lock().await;
+
+    Ok(session_guard.as_ref().and_then(|s| s.mssp().cloned()))
+}
+
+/// Get MCCP compression statistics for the current session
+#[tauri::command]
+pub async fn get_compression_stats(state: State<'_, AppState>) -> CommandResult<Option<CompressionStats>> {
+    let session_guard = state.session.lock().await;
+
+    Ok(session_guard.as_ref().map(|s| s.get_compression_stats()))
+}
+
+/// Get the commands waiting in the send queue (rate limit, pause or flood guard)
+#[tauri::command]
+pub async fn get_send_queue(state: State<'_, AppState>) -> CommandResult<Option<SendQueueStatus>> {
+    let session_guard = state.session.lock().await;
+
+    Ok(session_guard.as_ref().map(|s| s.send_queue_status()))
+}
+
+/// Discard the commands waiting in the send queue, returning how many there were
+#[tauri::command]
+pub async fn clear_send_queue(state: State<'_, AppState>) -> CommandResult<usize> {
+    let mut session_guard = state.session.lock().await;
+
+    match session_guard.as_mut() {
+        Some(session) => Ok(session.clear_send_queue()),
+        None => Err("Not connected".to_string()),
+    }
+}
+
+/// Pause or resume the send queue (resuming also resets the flood guard)
+#[tauri::command]
+pub async fn set_send_queue_paused(paused: bool, state: State<'_, AppState>) -> CommandResult<()> {
+    let mut session_guard = state.session.lock().await;
+
+    match session_guard.as_mut() {
+        Some(session) => session
+            .set_send_queue_paused(paused)
+            .await
+            .map_err(|e| format!("Failed to update send queue: {}", e)),
+        None => Err("Not connected".to_string()),
+    }
 }
 
 /// Update an existing world configuration
@@ -884,6 +1283,25 @@ pub async fn update_world(
         .auto_connect(request.auto_connect)
         .timeout_secs(request.timeout_secs)
         .use_tls(request.use_tls)
+        .line_ending(request.line_ending)
+        .colors_256(request.colors_256)
+        .truecolor(request.truecolor)
+        .mccp(request.mccp)
+        .gmcp_modules(request.gmcp_modules)
+        .msdp_variable_prefix(request.msdp_variable_prefix)
+        .encoding(request.encoding)
+        .client_certificate(request.client_certificate)
+        .reconnect(request.reconnect)
+        .connect_commands(request.connect_commands)
+        .proxy(request.proxy)
+        .transport(request.transport)
+        .tcp_keepalive(request.tcp_keepalive)
+        .anti_idle(request.anti_idle)
+        .rate_limit(request.rate_limit)
+        .mxp(request.mxp)
+        .msp(request.msp)
+        .sounds_dir(request.sounds_dir)
+        .new_environ(request.new_environ)
         .build()
         .map_err(|e| format!("Failed to update world: {}", e))?;
 
@@ -1540,6 +1958,8 @@ pub struct CreateTriggerRequest {
     pub script: Option<String>,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    #[serde(default)]
+    pub prompt_only: bool,
 }
 
 /// Trigger update request
@@ -1553,6 +1973,7 @@ pub struct UpdateTriggerRequest {
     pub text: Option<String>,
     pub script: Option<String>,
     pub enabled: Option<bool>,
+    pub prompt_only: Option<bool>,
 }
 
 /// Get triggers directory path
@@ -1594,6 +2015,7 @@ pub async fn create_trigger(request: CreateTriggerRequest, app_handle: AppHandle
         .map_err(|e| format!("Failed to create trigger: {}", e))?;
 
     trigger.enabled = request.enabled;
+    trigger.prompt_only = request.prompt_only;
 
     // Get triggers directory
     let triggers_dir = get_triggers_dir(&app_handle)?;
@@ -1714,6 +2136,10 @@ pub async fn update_trigger(request: UpdateTriggerRequest, app_handle: AppHandle
         trigger.enabled = enabled;
     }
 
+    if let Some(prompt_only) = request.prompt_only {
+        trigger.prompt_only = prompt_only;
+    }
+
     // Get triggers directory
     let triggers_dir = get_triggers_dir(&app_handle)?;
 
@@ -2060,13 +2486,197 @@ pub async fn delete_highlight(id: String, app_handle: AppHandle) -> CommandResul
 
 #[cfg(test)]
 mod tests {
-    // TODO: These tests need to be rewritten as Tauri integration tests
-    // using tauri::test::mock_builder() or similar test framework
-    // The issue is that tauri::State cannot be easily mocked in unit tests
-    //
-    // For now, the command logic is tested indirectly through:
-    // - Integration tests in tests/integration_tests.rs
-    // - Manual testing through the Tauri frontend
-    //
-    // Future: Set up proper Tauri test harness for command testing
+    // TODO: The commands themselves need to be tested as Tauri integration
+    // tests using tauri::test::mock_builder() or similar test framework,
+    // since tauri::State cannot be easily mocked in unit tests. The helpers
+    // they share (loading worlds, starting sessions) are tested here.
+
+    use super::*;
+    use crate::core::EventBus;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    async fn start_mock_server() -> (TcpListener, u16) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    /// Helper: save `world` the way `create_world` does, then connect it the
+    /// way `connect_to_world` does
+    async fn connect_saved_world(world: &World) -> Session {
+        let worlds_dir = std::env::temp_dir().join(format!("macmush-worlds-{}", Uuid::new_v4()));
+        fs::create_dir_all(&worlds_dir).unwrap();
+        let json = serde_json::to_string_pretty(world).unwrap();
+        fs::write(worlds_dir.join(format!("{}.json", world.id)), json).unwrap();
+
+        let loaded = load_world(&worlds_dir, &world.id.to_string()).unwrap();
+        fs::remove_dir_all(&worlds_dir).unwrap();
+        assert_eq!(&loaded, world);
+
+        let mut session = Session::new(loaded, Arc::new(EventBus::new())).unwrap();
+        start_session(&mut session, (80, 24), &PinStore::default()).await.unwrap();
+        session
+    }
+
+    #[tokio::test]
+    async fn test_connect_saved_world_uses_its_settings() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let send = [255, 250, 24, 1, 255, 240];
+                let mut request = vec![255, 253, 24];
+                for _ in 0..3 {
+                    request.extend_from_slice(&send);
+                }
+                let _ = socket.write_all(&request).await;
+
+                let mut buf = vec![0u8; 256];
+                let mut received = Vec::new();
+                while !received.ends_with(b"\xff\xf0") || !received.windows(4).any(|w| w == b"MTTS") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Saved MUD", "127.0.0.1", port)
+            .encoding(TextEncoding::Latin1)
+            .truecolor(false)
+            .build()
+            .unwrap();
+        let mut session = connect_saved_world(&world).await;
+        let _ = tokio::time::timeout(std::time::Duration::from_millis(200), session.process_incoming_data()).await;
+
+        // MTTS from the saved settings: ANSI (1) + 256 colours (8) + MNES (512), no UTF-8
+        let received = rx.await.unwrap();
+        assert!(received.ends_with(b"MTTS 521\xff\xf0"), "{:?}", String::from_utf8_lossy(&received));
+    }
+
+    #[tokio::test]
+    async fn test_connect_saved_world_with_cr_line_ending() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = [0u8; 64];
+                let mut received = Vec::new();
+                while !received.ends_with(b"look\r") {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Saved MUD", "127.0.0.1", port)
+            .line_ending(LineEnding::Cr)
+            .build()
+            .unwrap();
+        let mut session = connect_saved_world(&world).await;
+        session.send_command("look").await.unwrap();
+
+        // NAWS offer, then the command ended with a bare CR
+        assert_eq!(rx.await.unwrap(), b"\xff\xfb\x1flook\r".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_saved_world_replays_connect_commands() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for _ in 0..2 {
+                if let Ok((mut socket, _)) = listener.accept().await {
+                    let mut buf = [0u8; 64];
+                    let mut received = Vec::new();
+                    while !received.ends_with(b"\r\n") {
+                        match socket.read(&mut buf).await {
+                            Ok(0) | Err(_) => break,
+                            Ok(n) => received.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    let _ = tx.send(received);
+                }
+            }
+        });
+
+        let world = World::builder("Saved MUD", "127.0.0.1", port)
+            .connect_commands(vec!["connect bob secret".to_string()])
+            .build()
+            .unwrap();
+        let mut session = connect_saved_world(&world).await;
+        assert_eq!(rx.recv().await.unwrap(), b"\xff\xfb\x1fconnect bob secret\r\n".to_vec());
+
+        session.reconnect().await.unwrap();
+        assert_eq!(rx.recv().await.unwrap(), b"\xff\xfb\x1fconnect bob secret\r\n".to_vec());
+    }
+
+    #[tokio::test]
+    async fn test_connect_saved_world_announces_its_gmcp_modules() {
+        let (listener, port) = start_mock_server().await;
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.write_all(&[255, 251, 201]).await;
+
+                let mut buf = [0u8; 256];
+                let mut received = Vec::new();
+                while !received.windows(17).any(|w| w == b"Core.Supports.Set") || !received.ends_with(&[255, 240]) {
+                    match socket.read(&mut buf).await {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => received.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = tx.send(received);
+            }
+        });
+
+        let world = World::builder("Saved MUD", "127.0.0.1", port)
+            .gmcp_modules(vec!["Room 1".to_string(), "Comm.Channel 1".to_string()])
+            .build()
+            .unwrap();
+        let mut session = connect_saved_world(&world).await;
+        let _ = tokio::time::timeout(std::time::Duration::from_millis(200), session.process_incoming_data()).await;
+
+        let received = rx.await.unwrap();
+        assert!(received.ends_with(b"Core.Supports.Set [\"Room 1\",\"Comm.Channel 1\"]\xff\xf0"), "{:?}", String::from_utf8_lossy(&received));
+    }
+
+    #[tokio::test]
+    async fn test_connect_saved_world_uses_its_msdp_prefix() {
+        let (listener, port) = start_mock_server().await;
+
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let mut msg = vec![255, 251, 69, 255, 250, 69];
+                msg.extend_from_slice(b"\x01HEALTH\x02100");
+                msg.extend_from_slice(&[255, 240]);
+                let _ = socket.write_all(&msg).await;
+
+                let mut buf = [0u8; 64];
+                let _ = socket.read(&mut buf).await;
+            }
+        });
+
+        let world = World::builder("Saved MUD", "127.0.0.1", port)
+            .msdp_variable_prefix("m_")
+            .build()
+            .unwrap();
+        let mut session = connect_saved_world(&world).await;
+        session.process_incoming_data().await.unwrap();
+
+        assert_eq!(session.get_variable("m_HEALTH"), Some("100".to_string()));
+        assert_eq!(session.get_variable("msdp_HEALTH"), None);
+    }
 }
@@ -3,7 +3,10 @@
 /// Handles background data reception and forwarding events to the frontend.
 
 use crate::automation::HighlightStyle;
-use crate::core::MudEvent;
+use crate::core::{MudEvent, ThrottleReason};
+use crate::error::MushError;
+use crate::network::{MspRequest, MsdpValue, MsspData, MxpLink, MxpSpan, SoundKind};
+use crate::ui::commands::save_first_use_pin;
 use crate::ui::state::AppState;
 use serde::Serialize;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -19,11 +22,40 @@ pub enum FrontendEvent {
     DataReceived { text: String },
 
     /// Connection status changed
-    ConnectionStatus { connected: bool, world_name: Option<String> },
+    ConnectionStatus {
+        connected: bool,
+        world_name: Option<String>,
+        /// Server address connected to, e.g. "[2001:db8::1]:4000"
+        address: Option<String>,
+    },
+
+    /// Reconnect attempt scheduled after the connection dropped
+    Reconnecting { attempt: u32, max_attempts: u32, delay_ms: u64 },
+
+    /// Connection re-established
+    Reconnected { attempts: u32 },
+
+    /// Outgoing commands are waiting in the send queue
+    SendThrottled { queued: usize, reason: ThrottleReason },
+
+    /// Anti-idle sent `command` (`None`: a telnet NOP)
+    AntiIdleSent { command: Option<String> },
 
     /// Error occurred
     Error { message: String },
 
+    /// MXP styles and links for the text of the next DataReceived event
+    MxpStyled { spans: Vec<MxpSpan>, links: Vec<MxpLink> },
+
+    /// Play a sound file from the world's sounds directory
+    PlaySound { path: String, sound: MspRequest },
+
+    /// Stop the sound or music playing
+    StopSound { kind: SoundKind },
+
+    /// Sound file missing or not allowed
+    SoundError { file: String, error: String },
+
     /// Highlight matched incoming text
     HighlightMatched { matches: Vec<(usize, usize, HighlightStyle)> },
 
@@ -50,6 +82,27 @@ pub enum FrontendEvent {
 
     /// Timer error occurred
     TimerError { error: String },
+
+    /// Prompt received
+    PromptReceived { prompt: String },
+
+    /// Input box masking changed (server echo for passwords)
+    InputMaskChanged { masked: bool },
+
+    /// GMCP package received
+    GmcpReceived { package: String, data: serde_json::Value },
+
+    /// MSDP variable received
+    MsdpReceived { variable: String, value: MsdpValue },
+
+    /// MSSP server status received
+    MsspReceived { data: MsspData },
+
+    /// TLS handshake completed
+    TlsNegotiated { protocol_version: String, cipher_suite: String },
+
+    /// Self-signed certificate pinned on first use
+    CertificatePinned { fingerprint: String },
 }
 
 /// Data receiver loop state
@@ -77,19 +130,25 @@ pub fn start_data_receiver(app_handle: AppHandle, state: AppState) {
                 break;
             }
 
-            // Try to receive data with timeout to allow send_command to acquire lock
-            let receive_result = timeout(Duration::from_millis(100), async {
+            // Wait briefly for data so send_command gets the lock in between.
+            // Only the read is timed out; once bytes arrive they are
+            // processed to completion, as cancelling midway would lose
+            // telnet replies and MCCP state.
+            let receive_result = {
                 let mut session_guard = state.session.lock().await;
                 match session_guard.as_mut() {
-                    Some(session) => session.process_incoming_data().await,
+                    Some(session) => match timeout(Duration::from_millis(100), session.read_incoming()).await {
+                        Ok(Ok(data)) => Ok(session.process_received_data(data).await),
+                        Ok(Err(e)) => Ok(Err(e)),
+                        Err(elapsed) => Err(elapsed),
+                    },
                     None => {
                         debug!("No session, stopping receiver");
                         RECEIVER_RUNNING.store(false, Ordering::SeqCst);
-                        Err(crate::error::MushError::NotConnected)
+                        Ok(Err(MushError::NotConnected))
                     }
                 }
-            })
-            .await;
+            };
 
             // Handle result
             match receive_result {
@@ -107,7 +166,10 @@ pub fn start_data_receiver(app_handle: AppHandle, state: AppState) {
                         },
                     );
 
-                    // Connection likely closed, stop receiver
+                    // Connection likely closed: retry per the world's policy, else stop receiver
+                    if reconnect(&app_handle, &state).await {
+                        continue;
+                    }
                     RECEIVER_RUNNING.store(false, Ordering::SeqCst);
                     break;
                 }
@@ -118,11 +180,15 @@ pub fn start_data_receiver(app_handle: AppHandle, state: AppState) {
                 }
             }
 
-            // Process timers periodically
+            // Process timers, anti-idle and the send queue periodically
             let timer_result = timeout(Duration::from_millis(50), async {
                 let mut session_guard = state.session.lock().await;
                 match session_guard.as_mut() {
-                    Some(session) => session.process_timers().await,
+                    Some(session) => {
+                        session.process_timers().await?;
+                        session.process_anti_idle().await?;
+                        session.flush_send_queue().await
+                    }
                     None => Ok(()),
                 }
             })
@@ -137,6 +203,75 @@ pub fn start_data_receiver(app_handle: AppHandle, state: AppState) {
     });
 }
 
+/// Reconnect the current session after its connection dropped
+///
+/// Waits out each backoff delay and makes each connect attempt without
+/// holding the session lock, so user commands aren't stalled and a user
+/// `disconnect` can stop the session (and cancel the retry) meanwhile.
+/// A certificate pinned on first use during the attempt is saved.
+/// Returns true once reconnected.
+async fn reconnect(app_handle: &AppHandle, state: &AppState) -> bool {
+    loop {
+        let (delay, mut cancelled) = {
+            let mut session_guard = state.session.lock().await;
+            let Some(session) = session_guard.as_mut() else {
+                return false;
+            };
+            match session.schedule_reconnect() {
+                Ok(Some(delay)) => (delay, session.reconnect_cancellation()),
+                Ok(None) => return false,
+                Err(e) => {
+                    error!("Failed to schedule reconnect: {}", e);
+                    return false;
+                }
+            }
+        };
+
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = cancelled.wait_for(|cancelled| *cancelled) => {
+                info!("Reconnect cancelled");
+                return false;
+            }
+        }
+
+        let mut client = {
+            let mut session_guard = state.session.lock().await;
+            let Some(session) = session_guard.as_mut() else {
+                return false;
+            };
+            match session.take_reconnect_client().await {
+                Ok(client) => client,
+                Err(_) => return false,
+            }
+        };
+
+        // Connect outside the lock; the attempt is abandoned on cancel
+        let result = tokio::select! {
+            result = client.connect() => result,
+            _ = cancelled.wait_for(|cancelled| *cancelled) => {
+                info!("Reconnect cancelled");
+                return false;
+            }
+        };
+
+        let mut session_guard = state.session.lock().await;
+        let Some(session) = session_guard.as_mut() else {
+            return false;
+        };
+        match session.finish_reconnect(client, result).await {
+            Ok(()) => {
+                if let Err(e) = save_first_use_pin(app_handle, session) {
+                    error!("{}", e);
+                }
+                return true;
+            }
+            Err(MushError::NotConnected) => return false,
+            Err(e) => warn!("Reconnect attempt failed: {}", e),
+        }
+    }
+}
+
 /// Forward EventBus events to frontend
 ///
 /// Subscribes to the EventBus and forwards relevant events to the frontend.
@@ -150,7 +285,7 @@ pub fn start_event_forwarder(app_handle: AppHandle, state: AppState) {
             let frontend_event = match event {
                 MudEvent::DataReceived { text, .. } => Some(FrontendEvent::DataReceived { text }),
 
-                MudEvent::Connected { .. } => {
+                MudEvent::Connected { address, .. } => {
                     // Update status
                     let world_name = state
                         .session
@@ -162,6 +297,7 @@ pub fn start_event_forwarder(app_handle: AppHandle, state: AppState) {
                     Some(FrontendEvent::ConnectionStatus {
                         connected: true,
                         world_name,
+                        address: address.map(|a| a.to_string()),
                     })
                 }
 
@@ -170,13 +306,43 @@ pub fn start_event_forwarder(app_handle: AppHandle, state: AppState) {
                     Some(FrontendEvent::ConnectionStatus {
                         connected: false,
                         world_name: None,
+                        address: None,
                     })
                 }
 
+                MudEvent::Reconnecting { attempt, max_attempts, delay_ms, .. } => {
+                    Some(FrontendEvent::Reconnecting { attempt, max_attempts, delay_ms })
+                }
+
+                MudEvent::Reconnected { attempts, .. } => Some(FrontendEvent::Reconnected { attempts }),
+
+                MudEvent::AntiIdleSent { command, .. } => Some(FrontendEvent::AntiIdleSent { command }),
+
+                MudEvent::SendThrottled { queued, reason, .. } => {
+                    Some(FrontendEvent::SendThrottled { queued, reason })
+                }
+
                 MudEvent::ConnectionError { error, .. } => Some(FrontendEvent::Error {
                     message: format!("Connection error: {}", error),
                 }),
 
+                MudEvent::TlsNegotiated { protocol_version, cipher_suite, .. } => {
+                    Some(FrontendEvent::TlsNegotiated { protocol_version, cipher_suite })
+                }
+
+                MudEvent::CertificatePinned { fingerprint, .. } => {
+                    Some(FrontendEvent::CertificatePinned { fingerprint })
+                }
+
+                MudEvent::TlsError { error, .. } => Some(FrontendEvent::Error {
+                    message: format!("TLS error: {}", error),
+                }),
+
+                MudEvent::MxpStyled { spans, links, .. } => Some(FrontendEvent::MxpStyled { spans, links }),
+                MudEvent::PlaySound { path, sound, .. } => Some(FrontendEvent::PlaySound { path, sound }),
+                MudEvent::StopSound { kind, .. } => Some(FrontendEvent::StopSound { kind }),
+                MudEvent::SoundError { file, error, .. } => Some(FrontendEvent::SoundError { file, error }),
+
                 MudEvent::HighlightMatched { matches, .. } => {
                     Some(FrontendEvent::HighlightMatched { matches })
                 }
@@ -213,6 +379,33 @@ pub fn start_event_forwarder(app_handle: AppHandle, state: AppState) {
                     Some(FrontendEvent::TimerError { error })
                 }
 
+                MudEvent::PromptReceived { prompt, .. } => {
+                    Some(FrontendEvent::PromptReceived { prompt })
+                }
+
+                MudEvent::InputMaskChanged { masked, .. } => {
+                    Some(FrontendEvent::InputMaskChanged { masked })
+                }
+
+                MudEvent::GmcpReceived { package, data, .. } => {
+                    Some(FrontendEvent::GmcpReceived { package, data })
+                }
+
+                MudEvent::MsdpReceived { variable, value, .. } => {
+                    Some(FrontendEvent::MsdpReceived { variable, value })
+                }
+
+                MudEvent::MsspReceived { data, .. } => {
+                    Some(FrontendEvent::MsspReceived { data })
+                }
+
+                MudEvent::CompressionError { error, .. } => Some(FrontendEvent::Error {
+                    message: format!("Compression error: {}", error),
+                }),
+
+                // Compression ending is logged but not forwarded to frontend
+                MudEvent::CompressionEnded { .. } => None,
+
                 // CommandSent is logged but not forwarded to frontend
                 MudEvent::CommandSent { .. } => None,
             };
@@ -21,6 +21,9 @@ pub struct AppState {
 
     /// Log format (plain, html, raw)
     pub log_format: Arc<Mutex<String>>,
+
+    /// Output window size (columns, rows) reported to servers via NAWS
+    pub window_size: Arc<Mutex<(u16, u16)>>,
 }
 
 impl AppState {
@@ -33,6 +36,7 @@ impl AppState {
             event_bus,
             log_file: Arc::new(Mutex::new(None)),
             log_format: Arc::new(Mutex::new("plain".to_string())),
+            window_size: Arc::new(Mutex::new((80, 24))),
         }
     }
 
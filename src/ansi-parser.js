@@ -94,7 +94,8 @@ function color256ToHex(index) {
  * Parse ANSI escape sequences and return styled text segments
  *
  * @param {string} text - Text with ANSI codes
- * @returns {Array} Array of {text, style} objects
+ * @returns {Array} Array of {text, style, start} objects, start being the
+ *   segment's offset in the original text
  */
 export function parseAnsi(text) {
   if (!text) return [{ text: '', style: {} }];
@@ -126,7 +127,8 @@ export function parseAnsi(text) {
       if (textSegment) {
         segments.push({
           text: textSegment,
-          style: { ...currentStyle }
+          style: { ...currentStyle },
+          start: lastIndex
         });
       }
     }
@@ -267,13 +269,14 @@ export function parseAnsi(text) {
   if (lastIndex < text.length) {
     segments.push({
       text: text.substring(lastIndex),
-      style: { ...currentStyle }
+      style: { ...currentStyle },
+      start: lastIndex
     });
   }
 
   // If no segments were created, return the original text with no style
   if (segments.length === 0) {
-    segments.push({ text, style: {} });
+    segments.push({ text, style: {}, start: 0 });
   }
 
   return segments;
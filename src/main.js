@@ -215,6 +215,10 @@ class World {
 let worlds = []; // Array of World objects
 let activeWorldId = null; // Currently active world ID
 let pendingHighlights = []; // Pending highlight matches from backend
+let pendingMxp = { spans: [], links: [] }; // MXP styles and links for the next received text
+let inputMasked = false; // Server is echoing input (password entry)
+let currentSound = null; // { audio, request } of the MSP/trigger sound playing
+let currentMusic = null; // { audio, request } of the MSP music playing
 
 // UI editing state (shared across worlds)
 let editingTriggerIndex = null;
@@ -228,8 +232,12 @@ let editingTimerIndex = null;
 
 // Global state (not per-world)
 let savedWorlds = []; // Saved connection profiles
+let connectWorldId = null; // Backend world the next connect uses (null for quick connect)
 let recentConnections = []; // Recent connection history
 let statusBarUpdateInterval = null;
+let windowSize = null; // Output size in characters last reported for NAWS
+let windowSizeObserver = null;
+let windowSizeTimeout = null;
 
 // ===========================
 // World Management Functions
@@ -590,14 +598,53 @@ function handleFavoriteWorld(event) {
   }
 }
 
+/**
+ * Save a world profile as a backend world, returning its ID
+ *
+ * Settings the form doesn't show (encoding, line ending, connect commands...)
+ * are kept from the existing backend world.
+ */
+async function saveBackendWorld(worldId, { name, host, port, timeout, useTls, autoReconnect, keepAlive }) {
+  const settings = { name, host, port, timeout_secs: timeout, use_tls: useTls };
+
+  if (worldId) {
+    try {
+      const existing = await invoke('get_world', { id: worldId });
+      const world = await invoke('update_world', {
+        request: {
+          ...existing,
+          ...settings,
+          reconnect: { ...existing.reconnect, enabled: autoReconnect },
+          tcp_keepalive: { ...existing.tcp_keepalive, enabled: keepAlive },
+          anti_idle: { ...existing.anti_idle, enabled: keepAlive }
+        }
+      });
+      return world.id;
+    } catch (error) {
+      console.warn('Saved world missing from backend, creating it again:', error);
+    }
+  }
+
+  const world = await invoke('create_world', {
+    request: {
+      ...settings,
+      reconnect: { enabled: autoReconnect },
+      tcp_keepalive: { enabled: keepAlive },
+      anti_idle: { enabled: keepAlive }
+    }
+  });
+  return world.id;
+}
+
 /**
  * Handle save world button click
  */
-function handleSaveWorld() {
+async function handleSaveWorld() {
   const name = document.getElementById('world-name').value.trim();
   const host = document.getElementById('host').value.trim();
   const port = parseInt(document.getElementById('port').value);
   const timeout = parseInt(document.getElementById('timeout').value) || 30;
+  const useTls = document.getElementById('use-tls').checked;
   const autoReconnect = document.getElementById('auto-reconnect').checked;
   const keepAlive = document.getElementById('keep-alive').checked;
 
@@ -609,16 +656,27 @@ function handleSaveWorld() {
   // Check if world already exists
   const existingIndex = savedWorlds.findIndex(w => w.name === name);
 
-  const worldData = { name, host, port, timeout, autoReconnect, keepAlive };
+  if (existingIndex !== -1 && !confirm(`A world named "${name}" already exists. Update it?`)) {
+    return;
+  }
+
+  const worldData = { name, host, port, timeout, useTls, autoReconnect, keepAlive };
+
+  // The backend keeps the full world configuration used when connecting
+  try {
+    const existingId = existingIndex !== -1 ? savedWorlds[existingIndex].worldId : null;
+    worldData.worldId = await saveBackendWorld(existingId, worldData);
+  } catch (error) {
+    connectError.textContent = `Failed to save world: ${error}`;
+    return;
+  }
 
   if (existingIndex !== -1) {
     // Update existing world
-    if (confirm(`A world named "${name}" already exists. Update it?`)) {
-      savedWorlds[existingIndex] = worldData;
-      saveSavedWorlds();
-      renderSavedWorldsList();
-      appendOutput(`✓ World profile updated: "${name}"`, 'system');
-    }
+    savedWorlds[existingIndex] = worldData;
+    saveSavedWorlds();
+    renderSavedWorldsList();
+    appendOutput(`✓ World profile updated: "${name}"`, 'system');
   } else {
     // Add new world
     savedWorlds.push(worldData);
@@ -848,10 +906,12 @@ function handleQuickConnect(event) {
   document.getElementById('host').value = world.host;
   document.getElementById('port').value = world.port;
   document.getElementById('timeout').value = world.timeout || 30;
+  document.getElementById('use-tls').checked = world.useTls || false;
   document.getElementById('auto-reconnect').checked = world.autoReconnect || false;
   document.getElementById('keep-alive').checked = world.keepAlive !== false; // Default true
 
-  // Auto-connect
+  // Auto-connect with the saved world's full configuration
+  connectWorldId = world.worldId || null;
   connectForm.dispatchEvent(new Event('submit'));
 }
 
@@ -868,6 +928,7 @@ function handleEditWorld(event) {
   document.getElementById('host').value = world.host;
   document.getElementById('port').value = world.port;
   document.getElementById('timeout').value = world.timeout || 30;
+  document.getElementById('use-tls').checked = world.useTls || false;
   document.getElementById('auto-reconnect').checked = world.autoReconnect || false;
   document.getElementById('keep-alive').checked = world.keepAlive !== false; // Default true
 
@@ -887,6 +948,12 @@ function handleDeleteWorld(event) {
     return;
   }
 
+  if (world.worldId) {
+    invoke('delete_world', { id: world.worldId }).catch(error => {
+      console.error('Failed to delete backend world:', error);
+    });
+  }
+
   savedWorlds.splice(index, 1);
   saveSavedWorlds();
   renderSavedWorldsList();
@@ -1283,7 +1350,7 @@ function checkTriggerMatches(text) {
 /**
  * Append text to output display with ANSI color support
  */
-function appendOutput(text, className = '') {
+function appendOutput(text, className = '', mxp = { spans: [], links: [] }) {
   // Check output filters
   const messageType = className || 'mud';
   if (!outputFilters[messageType]) {
@@ -1306,27 +1373,33 @@ function appendOutput(text, className = '') {
       const segments = parseAnsi(text);
 
       segments.forEach(segment => {
-        if (segment.text) {
-          const span = document.createElement('span');
-          span.className = 'ansi-text';
-
-          // Apply highlighting and variable capture to MUD text
-          const highlightedText = processHighlights(segment.text);
-
-          if (highlightedText !== segment.text) {
-            // Highlighting was applied, use innerHTML
-            span.innerHTML = highlightedText;
-          } else {
-            // No highlighting, use textContent
-            span.textContent = segment.text;
+        splitMxpSpans(segment, mxp).forEach(piece => {
+          if (piece.text) {
+            const span = document.createElement('span');
+            span.className = 'ansi-text';
+
+            // Apply highlighting and variable capture to MUD text
+            const highlightedText = processHighlights(piece.text);
+
+            if (highlightedText !== piece.text) {
+              // Highlighting was applied, use innerHTML
+              span.innerHTML = highlightedText;
+            } else {
+              // No highlighting, use textContent
+              span.textContent = piece.text;
+            }
+
+            // Apply ANSI styles, then MXP styles on top
+            const css = styleToCSS(segment.style);
+            Object.assign(span.style, css, mxpStyleToCSS(piece.style));
+
+            if (piece.link) {
+              attachMxpLink(span, piece.link);
+            }
+
+            line.appendChild(span);
           }
-
-          // Apply ANSI styles
-          const css = styleToCSS(segment.style);
-          Object.assign(span.style, css);
-
-          line.appendChild(span);
-        }
+        });
       });
     }
   } else {
@@ -1365,6 +1438,224 @@ function appendOutput(text, className = '') {
   }
 }
 
+/**
+ * Split an ANSI segment where MXP spans and links start and end
+ * Offsets index the text including escape codes, like segment.start
+ * Returns pieces of {text, style, link}, style and link being null outside MXP ranges
+ */
+function splitMxpSpans(segment, mxp) {
+  const spans = mxp?.spans || [];
+  const links = mxp?.links || [];
+  if ((spans.length === 0 && links.length === 0) || segment.start === undefined) {
+    return [{ text: segment.text, style: null, link: null }];
+  }
+
+  const start = segment.start;
+  const end = start + segment.text.length;
+  const covering = (ranges, pos) => ranges.find(r => r.start <= pos && pos < r.end) || null;
+
+  // Cut wherever a span or link starts or ends inside the segment
+  const cuts = new Set([start, end]);
+  for (const range of [...spans, ...links]) {
+    if (range.start > start && range.start < end) cuts.add(range.start);
+    if (range.end > start && range.end < end) cuts.add(range.end);
+  }
+  const points = [...cuts].sort((a, b) => a - b);
+
+  const pieces = [];
+  for (let i = 0; i < points.length - 1; i++) {
+    const span = covering(spans, points[i]);
+    pieces.push({
+      text: segment.text.substring(points[i] - start, points[i + 1] - start),
+      style: span ? span.style : null,
+      link: covering(links, points[i]),
+    });
+  }
+  return pieces;
+}
+
+/**
+ * Make an output span act as an MXP link
+ */
+function attachMxpLink(span, link) {
+  span.classList.add('mxp-link');
+  span.title = link.hint || link.labels.join(' | ');
+  span.addEventListener('click', (event) => {
+    event.stopPropagation();
+    if (link.kind === 'url') {
+      openMxpUrl(link.hrefs[0]);
+    } else if (link.hrefs.length > 1) {
+      showMxpMenu(link, event.clientX, event.clientY);
+    } else {
+      runMxpLink(link, link.hrefs[0]);
+    }
+  });
+}
+
+/**
+ * Send a link's command (through aliases and speedwalks) or put it in the input box
+ */
+async function runMxpLink(link, command) {
+  if (link.prompt) {
+    commandInput.value = command;
+    commandInput.focus();
+    return;
+  }
+
+  try {
+    await invoke('send_mxp_link', { command });
+  } catch (error) {
+    appendOutput(`Link failed: ${error}`, 'error');
+  }
+}
+
+/**
+ * Open an MXP <a> link in the browser
+ */
+async function openMxpUrl(url) {
+  try {
+    await invoke('plugin:opener|open_url', { url });
+  } catch (error) {
+    appendOutput(`Could not open ${url}: ${error}`, 'error');
+  }
+}
+
+/**
+ * Pop-up menu for a <send> link with several commands
+ */
+function showMxpMenu(link, x, y) {
+  document.querySelector('.mxp-menu')?.remove();
+
+  const menu = document.createElement('div');
+  menu.className = 'mxp-menu';
+  menu.style.left = `${x}px`;
+  menu.style.top = `${y}px`;
+
+  link.hrefs.forEach((command, index) => {
+    const item = document.createElement('div');
+    item.className = 'mxp-menu-item';
+    item.textContent = link.labels[index] || command;
+    item.title = command;
+    item.addEventListener('click', () => {
+      menu.remove();
+      runMxpLink(link, command);
+    });
+    menu.appendChild(item);
+  });
+
+  document.body.appendChild(menu);
+  document.addEventListener('click', () => menu.remove(), { once: true });
+}
+
+// ===========================
+// Sound (MSP and trigger sounds)
+// ===========================
+
+const SOUND_MIME_TYPES = {
+  wav: 'audio/wav',
+  mp3: 'audio/mpeg',
+  ogg: 'audio/ogg',
+  mid: 'audio/midi',
+  midi: 'audio/midi',
+  m4a: 'audio/mp4',
+};
+
+/**
+ * Play a sound or music file from a playSound event
+ */
+async function playSound(path, request) {
+  // Music asked to continue keeps playing when requested again
+  if (request.kind === 'music' && currentMusic && request.continue_music
+      && currentMusic.request.file === request.file) {
+    currentMusic.audio.volume = request.volume / 100;
+    return;
+  }
+  // A sound only interrupts one of lower or equal priority
+  if (request.kind === 'sound' && currentSound && !currentSound.audio.ended
+      && currentSound.request.priority > request.priority) {
+    return;
+  }
+
+  let bytes;
+  try {
+    bytes = await invoke('read_sound_file', { path });
+  } catch (error) {
+    appendOutput(`🔇 Cannot play ${request.file}: ${error}`, 'error');
+    return;
+  }
+
+  const extension = path.split('.').pop().toLowerCase();
+  const blob = new Blob([new Uint8Array(bytes)], { type: SOUND_MIME_TYPES[extension] || 'audio/wav' });
+  const audio = new Audio(URL.createObjectURL(blob));
+  audio.volume = request.volume / 100;
+
+  // L=-1 repeats until stopped, otherwise play L times
+  let remaining = request.loops;
+  if (remaining < 0) {
+    audio.loop = true;
+  } else {
+    audio.addEventListener('ended', () => {
+      remaining -= 1;
+      if (remaining > 0) {
+        audio.play();
+      } else {
+        URL.revokeObjectURL(audio.src);
+      }
+    });
+  }
+
+  stopSound(request.kind);
+  if (request.kind === 'music') {
+    currentMusic = { audio, request };
+  } else {
+    currentSound = { audio, request };
+  }
+
+  try {
+    await audio.play();
+  } catch (error) {
+    appendOutput(`🔇 Cannot play ${request.file}: ${error}`, 'error');
+  }
+}
+
+/**
+ * Stop the sound or music playing
+ */
+function stopSound(kind) {
+  const playing = kind === 'music' ? currentMusic : currentSound;
+  if (!playing) return;
+
+  playing.audio.pause();
+  URL.revokeObjectURL(playing.audio.src);
+  if (kind === 'music') {
+    currentMusic = null;
+  } else {
+    currentSound = null;
+  }
+}
+
+/**
+ * Convert an MXP span style to CSS properties
+ */
+function mxpStyleToCSS(style) {
+  const css = {};
+  if (!style) return css;
+
+  if (style.bold) css.fontWeight = 'bold';
+  if (style.italic) css.fontStyle = 'italic';
+  if (style.underline || style.strikethrough) {
+    css.textDecoration = [style.underline && 'underline', style.strikethrough && 'line-through']
+      .filter(Boolean)
+      .join(' ');
+  }
+  if (style.fore) css.color = style.fore;
+  if (style.back) css.backgroundColor = style.back;
+  if (style.font) css.fontFamily = style.font;
+  if (style.size) css.fontSize = /^\d+$/.test(style.size) ? `${style.size}px` : style.size;
+
+  return css;
+}
+
 /**
  * Clear output display
  */
@@ -2003,6 +2294,7 @@ function stopStatusBar() {
 
   // Hide status bar
   statusBar.style.display = 'none';
+  document.getElementById('status-prompt').textContent = '';
 
   // Reset values
   connectionStartTime = null;
@@ -2735,6 +3027,56 @@ async function toggleTimerEnabled(index) {
   }
 }
 
+/**
+ * Measure the output area in characters (columns and rows)
+ */
+function measureOutputSize() {
+  const style = getComputedStyle(outputDisplay);
+  const context = document.createElement('canvas').getContext('2d');
+  context.font = `${style.fontSize} ${style.fontFamily}`;
+  const charWidth = context.measureText('M'.repeat(10)).width / 10;
+  const lineHeight = parseFloat(style.lineHeight) || parseFloat(style.fontSize) * 1.5;
+
+  const width = outputDisplay.clientWidth - parseFloat(style.paddingLeft) - parseFloat(style.paddingRight);
+  const height = outputDisplay.clientHeight - parseFloat(style.paddingTop) - parseFloat(style.paddingBottom);
+
+  return {
+    width: Math.max(1, Math.floor(width / charWidth)),
+    height: Math.max(1, Math.floor(height / lineHeight))
+  };
+}
+
+/**
+ * Report the output size to the backend, which sends it to the server via NAWS
+ */
+async function reportWindowSize(force = false) {
+  if (!outputDisplay || outputDisplay.clientWidth === 0) return; // Hidden, nothing to measure
+
+  const { width, height } = measureOutputSize();
+  if (!force && windowSize && windowSize.width === width && windowSize.height === height) return;
+  windowSize = { width, height };
+
+  try {
+    await invoke('set_window_size', { width, height });
+  } catch (error) {
+    console.error('Failed to set window size:', error);
+  }
+}
+
+/**
+ * Report the output size now and again (debounced) whenever it is resized
+ */
+function watchWindowSize() {
+  reportWindowSize(true);
+  if (windowSizeObserver) return;
+
+  windowSizeObserver = new ResizeObserver(() => {
+    clearTimeout(windowSizeTimeout);
+    windowSizeTimeout = setTimeout(() => reportWindowSize(), 200);
+  });
+  windowSizeObserver.observe(outputDisplay);
+}
+
 /**
  * Handle connection form submission
  */
@@ -2749,6 +3091,10 @@ async function handleConnect(event) {
   const autoReconnect = document.getElementById('auto-reconnect').checked;
   const keepAlive = document.getElementById('keep-alive').checked;
 
+  // Saved worlds connect with their backend configuration, anything else is a quick connect
+  const worldId = connectWorldId;
+  connectWorldId = null;
+
   connectError.textContent = '';
 
   try {
@@ -2759,9 +3105,18 @@ async function handleConnect(event) {
     // Make this the active world
     activeWorldId = world.id;
 
+    // Size reported on connect, once the server negotiates NAWS
+    await reportWindowSize(true);
+
     // Call Rust backend to connect
-    const result = await invoke('connect_to_world', {
-      request: { name: world.name, host, port, use_tls: useTls }
+    const result = await invoke('connect_to_world', worldId ? { worldId } : {
+      request: {
+        name: world.name, host, port, use_tls: useTls,
+        reconnect: { enabled: autoReconnect },
+        // Backend sends a telnet NOP after 10 idle minutes and keeps the socket alive
+        tcp_keepalive: { enabled: keepAlive },
+        anti_idle: { enabled: keepAlive }
+      }
     });
 
     if (result.connected) {
@@ -2807,13 +3162,6 @@ async function handleConnect(event) {
       // Add to recent connections
       addRecentConnection(world.name, host, port);
 
-      // Start keep-alive if enabled
-      if (keepAlive) {
-        world.keepAliveInterval = setInterval(() => {
-          // Keep-alive logic
-        }, 60000);
-      }
-
       // Start status bar
       startStatusBar();
 
@@ -2828,6 +3176,9 @@ async function handleConnect(event) {
 
       // Hide connection dialog
       connectionDialog.style.display = 'none';
+
+      // Keep the server's idea of the window size current
+      watchWindowSize();
     }
   } catch (error) {
     connectError.textContent = `Connection failed: ${error}`;
@@ -2849,9 +3200,7 @@ async function handleDisconnect() {
     // Stop all timers
     stopAllTimers();
 
-    // Disable auto-reconnect on manual disconnect
-    autoReconnectEnabled = false;
-
+    // Manual disconnect also cancels any pending reconnect in the backend
     await invoke('disconnect');
     appendOutput('', 'system');
     appendOutput('=== Disconnected ===', 'system');
@@ -2900,45 +3249,22 @@ function stopKeepAlive() {
 }
 
 /**
- * Attempt to reconnect with last connection settings
+ * Send command to MUD server
  */
-async function attemptReconnect() {
-  if (!lastConnectionSettings || !autoReconnectEnabled) {
-    return;
-  }
-
-  appendOutput('', 'system');
-  appendOutput('🔄 Auto-reconnect enabled, attempting to reconnect...', 'system');
-
-  // Wait 3 seconds before reconnecting
-  await new Promise(resolve => setTimeout(resolve, 3000));
-
-  // Check if still disconnected
-  if (!isConnected && autoReconnectEnabled) {
-    // Populate form with last settings
-    document.getElementById('world-name').value = lastConnectionSettings.name;
-    document.getElementById('host').value = lastConnectionSettings.host;
-    document.getElementById('port').value = lastConnectionSettings.port;
-    document.getElementById('timeout').value = lastConnectionSettings.timeout;
-    document.getElementById('auto-reconnect').checked = lastConnectionSettings.autoReconnect;
-    document.getElementById('keep-alive').checked = lastConnectionSettings.keepAlive;
+async function sendCommand(command) {
+  if (!command.trim()) return;
 
-    // Trigger connection
+  // Password entry: no local echo, history or aliases
+  if (inputMasked) {
     try {
-      await handleConnect({ preventDefault: () => {} });
+      trackCommandSent(command);
+      await invoke('send_command', { command });
     } catch (error) {
-      appendOutput(`⚠️ Auto-reconnect failed: ${error}`, 'error');
-      appendOutput('💡 Reconnection will not be attempted again', 'system');
-      autoReconnectEnabled = false;
+      appendOutput(`Error: ${error}`, 'error');
+      console.error('Send command error:', error);
     }
+    return;
   }
-}
-
-/**
- * Send command to MUD server
- */
-async function sendCommand(command) {
-  if (!command.trim()) return;
 
   // Process through aliases
   const processedCommand = processAliases(command);
@@ -3149,11 +3475,13 @@ async function checkAutoConnect() {
     document.getElementById('host').value = favoriteWorld.host;
     document.getElementById('port').value = favoriteWorld.port;
     document.getElementById('timeout').value = favoriteWorld.timeout || 30;
+    document.getElementById('use-tls').checked = favoriteWorld.useTls || false;
     document.getElementById('auto-reconnect').checked = favoriteWorld.autoReconnect || false;
     document.getElementById('keep-alive').checked = favoriteWorld.keepAlive !== false;
 
     // Trigger connection after a short delay
     setTimeout(() => {
+      connectWorldId = favoriteWorld.worldId || null;
       connectForm.dispatchEvent(new Event('submit'));
     }, 500);
   }
@@ -4215,12 +4543,36 @@ function handleMudEvent(event) {
 
       // Display received text from MUD server
       // Add visual indicator if triggers matched
-      appendOutput(displayText, matched ? 'trigger-matched' : '');
+      appendOutput(displayText, matched ? 'trigger-matched' : '', pendingMxp);
+      pendingMxp = { spans: [], links: [] };
+      break;
+
+    case 'mxpStyled':
+      // Store MXP styles and links to apply to the dataReceived event that follows
+      pendingMxp = { spans: payload.spans || [], links: payload.links || [] };
+      break;
+
+    case 'playSound':
+      // Sound requested by MSP or a trigger
+      playSound(payload.path, payload.sound);
+      break;
+
+    case 'stopSound':
+      // MSP !!SOUND(Off) / !!MUSIC(Off)
+      stopSound(payload.kind);
+      break;
+
+    case 'soundError':
+      // Sound file missing from the sounds directory
+      appendOutput(`🔇 Sound not found: ${payload.file} (${payload.error})`, 'error');
       break;
 
     case 'connectionStatus':
       // Update connection status
       updateConnectionStatus(payload.connected, payload.worldName);
+      if (payload.connected && payload.address) {
+        appendOutput(`Connected to ${payload.address}`, 'system');
+      }
       if (!payload.connected) {
         // Stop keep-alive
         stopKeepAlive();
@@ -4231,16 +4583,41 @@ function handleMudEvent(event) {
         // Stop all timers
         stopAllTimers();
 
+        // Server echo ends with the connection
+        inputMasked = false;
+        commandInput.type = 'text';
+
         appendOutput('', 'system');
         appendOutput('=== Disconnected ===', 'system');
-
-        // Attempt auto-reconnect if enabled
-        if (autoReconnectEnabled) {
-          attemptReconnect();
-        }
       }
       break;
 
+    case 'reconnecting':
+      // Backend retries with backoff when the world has auto-reconnect enabled
+      appendOutput(`🔄 Reconnecting in ${(payload.delay_ms / 1000).toFixed(1)}s (attempt ${payload.attempt}/${payload.max_attempts})...`, 'system');
+      break;
+
+    case 'sendThrottled': {
+      // Backend send queue is holding commands (rate limit, pause or flood guard)
+      const reasons = {
+        rateLimit: 'rate limit',
+        paused: 'queue paused',
+        floodGuard: 'flood guard tripped'
+      };
+      appendOutput(`⏳ ${payload.queued} command(s) queued (${reasons[payload.reason] || payload.reason})`, 'system');
+      break;
+    }
+
+    case 'antiIdleSent':
+      console.log(`Anti-idle sent ${payload.command ?? 'NOP'}`);
+      break;
+
+    case 'reconnected':
+      appendOutput('=== Reconnected ===', 'system');
+      startStatusBar();
+      startAllTimers();
+      break;
+
     case 'error':
       // Display error message
       appendOutput(`Error: ${payload.message}`, 'error');
@@ -4294,6 +4671,27 @@ function handleMudEvent(event) {
       appendOutput(`❌ Timer error: ${payload.error}`, 'error');
       break;
 
+    case 'tlsNegotiated':
+      // Secure connection established
+      appendOutput(`🔒 TLS connection (${payload.protocol_version}, ${payload.cipher_suite})`, 'system');
+      break;
+
+    case 'certificatePinned':
+      // Self-signed certificate trusted on first use
+      appendOutput(`🔒 Pinned server certificate ${payload.fingerprint}`, 'system');
+      break;
+
+    case 'promptReceived':
+      // Prompt ended by IAC GA / IAC EOR: keep the latest in the status bar
+      document.getElementById('status-prompt').textContent = payload.prompt.trim();
+      break;
+
+    case 'inputMaskChanged':
+      // Server toggled echo (password prompt): mask the input box
+      inputMasked = payload.masked;
+      commandInput.type = inputMasked ? 'password' : 'text';
+      break;
+
     default:
       console.warn('Unknown event type:', payload.type);
   }